@@ -0,0 +1,9 @@
+use xprite::prelude::*;
+
+#[derive(Default)]
+pub struct AdjustColorsState {
+    pub adjustment: Adjustment,
+    pub scope: ReplaceScope,
+    /// adjust the palette entries of indexed pixels instead of the pixels
+    pub palette_mode: bool,
+}
@@ -1,11 +1,21 @@
 // Note: this is necessary because of imgui
 
+use xprite::prelude::PatternMode;
+
 pub struct BrushState {
     pub sz: [i32; 2],
+    /// mode used when picking a library brush
+    pub pattern_mode: PatternMode,
+    /// name given to the next captured brush
+    pub capture_name: String,
 }
 
 impl Default for BrushState {
     fn default() -> Self {
-        Self { sz: [1, 0] }
+        Self {
+            sz: [1, 0],
+            pattern_mode: PatternMode::default(),
+            capture_name: "brush".to_owned(),
+        }
     }
 }
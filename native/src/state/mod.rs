@@ -3,9 +3,11 @@ use crate::render::imgui::ImguiRenderer;
 use std::collections::BTreeMap;
 use xprite::rendering::image_renderer::ImageRenderer;
 
+pub mod adjust_colors_state;
 pub mod brush_state;
 pub mod filepopup_state;
 pub mod preview_window;
+pub mod replace_color_state;
 
 pub struct State {
     pub xprs: Vec<Xprite>,
@@ -21,13 +23,31 @@ pub struct State {
     pub show_console: bool,
     pub show_brush: bool,
     pub show_symmetry: bool,
+    pub show_replace_color: bool,
+    pub show_adjust_colors: bool,
+    pub show_palette_report: bool,
     pub script_fname: Option<String>,
 
     pub cols_per_row: i32,
+    pub palette_extraction: PaletteExtraction,
+    pub palette_extraction_colors: i32,
+    pub palette_color_space: ColorSpace,
+    pub palette_dithering: Dithering,
+    /// palette entry being dragged to a new position
+    pub palette_drag: Option<usize>,
+    pub ramp: RampParams,
+    /// frame rate color cycles are baked and exported at
+    pub cycle_fps: i32,
+    /// canvas and preview are shown as seen with this kind of vision
+    pub vision: VisionSimulation,
+    /// ramp neighbours below this contrast ratio are reported
+    pub contrast_threshold: f32,
 
     pub rename_layer: Option<(usize, usize)>,
     pub rename_group: Option<usize>,
     pub brush: brush_state::BrushState,
+    pub replace_color: replace_color_state::ReplaceColorState,
+    pub adjust_colors: adjust_colors_state::AdjustColorsState,
 
     /// rendered texture
     pub texture: Option<usize>,
@@ -48,13 +68,27 @@ impl Default for State {
             exporter: Default::default(),
 
             brush: Default::default(),
+            replace_color: Default::default(),
+            adjust_colors: Default::default(),
             show_exporter: false,
             show_console: false,
             show_brush: false,
             show_symmetry: false,
+            show_replace_color: false,
+            show_adjust_colors: false,
+            show_palette_report: false,
             script_fname: None,
             texture: None,
             cols_per_row: 8,
+            palette_extraction: Default::default(),
+            palette_extraction_colors: 16,
+            palette_color_space: Default::default(),
+            palette_dithering: Default::default(),
+            palette_drag: None,
+            ramp: Default::default(),
+            cycle_fps: 25,
+            vision: Default::default(),
+            contrast_threshold: 1.2,
             rename_layer: None,
             rename_group: None,
 
@@ -119,6 +153,16 @@ impl State {
             .insert(ToolType::Marquee.as_str(), Self::add_icon(rdr, include_bytes!("../../assets/marquee.png")));
         self.icons
             .insert(ToolType::AutoShade.as_str(), Self::add_icon(rdr, include_bytes!("../../assets/autoshade.png")));
+        self.icons
+            .insert(ToolType::Spray.as_str(), Self::add_icon(rdr, include_bytes!("../../assets/spray.png")));
+        self.icons
+            .insert(ToolType::Polygon.as_str(), Self::add_icon(rdr, include_bytes!("../../assets/polygon.png")));
+        self.icons
+            .insert(ToolType::IsoBox.as_str(), Self::add_icon(rdr, include_bytes!("../../assets/iso_box.png")));
+        self.icons
+            .insert(ToolType::Shape.as_str(), Self::add_icon(rdr, include_bytes!("../../assets/shape.png")));
+        self.icons
+            .insert(ToolType::Gradient.as_str(), Self::add_icon(rdr, include_bytes!("../../assets/gradient.png")));
         self.icons.insert(
             ToolType::FilledRect.as_str(),
             Self::add_icon(rdr, include_bytes!("../../assets/filledrect.png")),
@@ -147,6 +191,7 @@ impl State {
         img_rdr.fill_canvas();
         self.xpr().preview(&mut img_rdr).unwrap();
         img_rdr.render(Some(self.xpr()))?;
+        img_rdr.simulate(self.vision);
         let img = img_rdr.as_img();
         if let Some(id) = self.texture {
             rdr.replace_img(img.to_owned(), image::RGBA(0), id);
@@ -177,6 +222,27 @@ impl State {
         self.show_symmetry = !self.show_symmetry;
     }
 
+    pub fn toggle_replace_color(&mut self) {
+        self.show_replace_color = !self.show_replace_color;
+    }
+
+    pub fn toggle_palette_report(&mut self) {
+        self.show_palette_report = !self.show_palette_report;
+    }
+
+    pub fn set_vision(&mut self, vision: VisionSimulation) {
+        self.vision = vision;
+        self.xpr_mut().set_redraw(true);
+    }
+
+    /// closing the window drops the preview
+    pub fn toggle_adjust_colors(&mut self) {
+        self.show_adjust_colors = !self.show_adjust_colors;
+        if !self.show_adjust_colors {
+            self.xpr_mut().cancel_adjustment_preview();
+        }
+    }
+
     pub fn push_xpr(&mut self, xpr: Xprite) {
         self.xprs.push(xpr);
     }
@@ -215,6 +281,7 @@ impl State {
             SetPaletteIndex(i) => {
                 self.xpr_mut().palette.current_palette_mut().idx = i;
             }
+            SwapColors => self.xpr_mut().palette.swap_colors(),
             ToggleSymmetryPanel => self.toggle_symmetry(),
             ToggleExporterPanel => self.toggle_exporter(),
             Unmapped => (),
@@ -223,13 +290,24 @@ impl State {
     }
 
     pub fn export(&self) {
-        self.exporter.run_export(&self.xpr());
+        if let Err(e) = self.exporter.run_export(&self.xpr()) {
+            error!("{}", e);
+        }
     }
 
     pub fn set_brush_for_tool(&mut self, brush: BrushType, tool_type: ToolType) {
         if self.brush.sz[0] < 1 {
             self.brush.sz[0] = 1;
         }
+        if let BrushType::Pattern(idx, mode) = brush {
+            // library brushes have a fixed size
+            if let Some(b) = self.xpr().brushes.brush(idx, mode) {
+                if let Err(e) = self.xpr().set_brush_for_tool(tool_type, b) {
+                    error!("{}", e);
+                }
+            }
+            return;
+        }
         macro_rules! tool {
             ($tool: expr) => {
                 match brush {
@@ -242,6 +320,7 @@ impl State {
                         let sz1 = self.brush.sz[1];
                         $tool.set(self.xpr_mut(), "brush", &format!("{}{},{}", brush.as_str(), sz0, sz1)).unwrap();
                     }
+                    BrushType::Pattern(_, _) => unreachable!(),
                 };
             };
         }
@@ -250,6 +329,7 @@ impl State {
             ToolType::Pencil => tool!(self.xpr_mut().toolbox.pencil.clone().borrow_mut()),
             ToolType::Vector => tool!(self.xpr_mut().toolbox.vector.clone().borrow_mut()),
             ToolType::Eraser => tool!(self.xpr_mut().toolbox.eraser.clone().borrow_mut()),
+            ToolType::Polygon => tool!(self.xpr_mut().toolbox.polygon.clone().borrow_mut()),
             _ => return,
         }
     }
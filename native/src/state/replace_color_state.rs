@@ -0,0 +1,19 @@
+use xprite::prelude::*;
+
+pub struct ReplaceColorState {
+    pub from: Color,
+    pub to: Color,
+    pub tolerance: i32,
+    pub scope: ReplaceScope,
+}
+
+impl Default for ReplaceColorState {
+    fn default() -> Self {
+        Self {
+            from: Color::black(),
+            to: Color::white(),
+            tolerance: 0,
+            scope: Default::default(),
+        }
+    }
+}
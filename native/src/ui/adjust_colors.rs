@@ -0,0 +1,99 @@
+use crate::prelude::*;
+
+pub fn draw_adjust_colors(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
+    if !state.show_adjust_colors {
+        return;
+    }
+    let sz = ui.io().display_size;
+    Window::new(&im_str!("Adjust Colors"))
+        .bring_to_front_on_focus(true)
+        .movable(true)
+        .position([sz[0] as f32 - RIGHT_SIDE_WIDTH * 3., 360.], Condition::Once)
+        .size([RIGHT_SIDE_WIDTH, 400.], Condition::Once)
+        .collapsible(true)
+        .resizable(true)
+        .build(&ui, || {
+            let mut changed = false;
+            for adj in Adjustment::VARIANTS.iter() {
+                let is_sel = state.adjust_colors.adjustment.as_str() == adj.as_str();
+                if Selectable::new(&im_str!("{}", adj.as_str()))
+                    .selected(is_sel)
+                    .flags(SelectableFlags::empty())
+                    .size([0., 0.])
+                    .build(&ui)
+                    && !is_sel
+                {
+                    state.adjust_colors.adjustment = *adj;
+                    changed = true;
+                }
+            }
+            ui.separator();
+
+            changed |= draw_params(&mut state.adjust_colors.adjustment, ui);
+            ui.separator();
+
+            for scope in ReplaceScope::VARIANTS.iter() {
+                let is_sel = &state.adjust_colors.scope == scope;
+                if Selectable::new(&im_str!("{}##adjust", scope.as_str()))
+                    .selected(is_sel)
+                    .flags(SelectableFlags::empty())
+                    .size([0., 0.])
+                    .build(&ui)
+                {
+                    changed |= !is_sel;
+                    state.adjust_colors.scope = *scope;
+                }
+            }
+            changed |= ui.checkbox(&im_str!("palette entries"), &mut state.adjust_colors.palette_mode);
+            if ui.is_item_hovered() {
+                ui.tooltip_text("adjust the palette colors of indexed pixels, the pixels keep their index");
+            }
+
+            let adj = state.adjust_colors.adjustment;
+            let scope = state.adjust_colors.scope;
+            let palette_mode = state.adjust_colors.palette_mode;
+            if changed {
+                if let Err(e) = state.xpr_mut().preview_adjustment(adj, scope, palette_mode) {
+                    state.xpr_mut().cancel_adjustment_preview();
+                    error!("cannot preview adjustment: {}", e);
+                }
+            }
+
+            if ui.button(&im_str!("Apply"), [0., 0.]) {
+                if let Err(e) = state.xpr_mut().adjust_colors(adj, scope, palette_mode) {
+                    error!("cannot adjust colors: {}", e);
+                }
+            }
+            ui.same_line(0.);
+            if ui.button(&im_str!("Cancel"), [0., 0.]) {
+                state.xpr_mut().cancel_adjustment_preview();
+            }
+        });
+}
+
+/// returns true if a parameter changed
+fn draw_params(adj: &mut Adjustment, ui: &Ui) -> bool {
+    let drag = |label: &str, val: &mut f64, min: f32, max: f32, speed: f32| {
+        let mut v = *val as f32;
+        let changed = ui.drag_float(&im_str!("{}", label), &mut v).min(min).max(max).speed(speed).build();
+        *val = f64::from(v);
+        changed
+    };
+    match adj {
+        Adjustment::Hsv { hue, saturation, value } => {
+            drag("hue", hue, -180., 180., 1.) | drag("saturation", saturation, -1., 1., 0.01) | drag("value", value, -1., 1., 0.01)
+        }
+        Adjustment::BrightnessContrast { brightness, contrast } => drag("brightness", brightness, -1., 1., 0.01) | drag("contrast", contrast, -1., 1., 0.01),
+        Adjustment::Levels { black, white, gamma } => {
+            drag("black", black, 0., 1., 0.01) | drag("white", white, 0., 1., 0.01) | drag("gamma", gamma, 0.1, 10., 0.01)
+        }
+        Adjustment::Posterize { levels } => {
+            let mut n = i32::from(*levels);
+            let changed = ui.drag_int(&im_str!("levels"), &mut n).min(2).max(16).build();
+            *levels = n as u8;
+            changed
+        }
+        Adjustment::Colorize { hue, saturation } => drag("hue", hue, 0., 360., 1.) | drag("saturation", saturation, 0., 1., 0.01),
+        Adjustment::Invert | Adjustment::Desaturate => false,
+    }
+}
@@ -15,17 +15,83 @@ pub fn draw_brush(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
         .resizable(true)
         .build(&ui, || {
             let current_tool = state.xpr_mut().toolbox.selected;
-            let brush_type = if let Some(b) = state.xpr().get_brush_for_tool(current_tool) {
-                b.brush_type
-            } else if let Some(b) = state.xpr().get_brush_for_tool(state.xpr().last_tool()) {
-                b.brush_type
+            let last_tool = state.xpr().last_tool();
+            // while selecting, the panel edits the brush of the previous tool
+            let (brush_type, tool_type) = if let Some(b) = state.xpr().get_brush_for_tool(current_tool) {
+                (b.brush_type, current_tool)
+            } else if let Some(b) = state.xpr().get_brush_for_tool(last_tool) {
+                (b.brush_type, last_tool)
             } else {
                 return;
             };
-            draw_brush_tree(state, ui, brush_type, current_tool);
+            draw_brush_tree(state, ui, brush_type, tool_type);
+            draw_brush_library(state, ui, brush_type, tool_type);
         });
 }
 
+/// brushes captured from selections
+pub fn draw_brush_library(state: &mut State, ui: &Ui, current_brush: BrushType, tool_type: ToolType) {
+    ui.tree_node(&im_str!("Library")).default_open(true).build(|| {
+        for (i, mode) in PatternMode::VARIANTS.iter().enumerate() {
+            if i > 0 {
+                ui.same_line(0.);
+            }
+            let is_sel = &state.brush.pattern_mode == mode;
+            if Selectable::new(&im_str!("{}##pattern_mode", mode.as_str()))
+                .selected(is_sel)
+                .flags(SelectableFlags::empty())
+                .size([50., 0.])
+                .build(&ui)
+                && !is_sel
+            {
+                state.brush.pattern_mode = *mode;
+                if let BrushType::Pattern(idx, _) = current_brush {
+                    state.set_brush_for_tool(BrushType::Pattern(idx, *mode), tool_type);
+                }
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Mask paints the captured shape with the current color");
+        }
+
+        let names: Vec<_> = state.xpr().brushes.brushes.keys().cloned().collect();
+        for (idx, name) in names.iter().enumerate() {
+            let is_sel = match current_brush {
+                BrushType::Pattern(i, _) => i == idx,
+                _ => false,
+            };
+            if Selectable::new(&im_str!("{}##library_brush", name))
+                .selected(is_sel)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                let mode = state.brush.pattern_mode;
+                state.set_brush_for_tool(BrushType::Pattern(idx, mode), tool_type);
+            }
+        }
+
+        let mut name = ImString::with_capacity(100);
+        name.push_str(&state.brush.capture_name);
+        if ui.input_text(&im_str!("Name"), &mut name).build() {
+            state.brush.capture_name = name.to_str().to_owned();
+        }
+        if ui.button(&im_str!("Capture selection"), [0., 0.]) {
+            let name = state.brush.capture_name.clone();
+            match state.xpr_mut().capture_brush(&name) {
+                Ok(idx) => {
+                    if let Err(e) = state.xpr().brushes.save(idx) {
+                        error!("cannot save brush: {}", e);
+                    }
+                    let mode = state.brush.pattern_mode;
+                    state.set_brush_for_tool(BrushType::Pattern(idx, mode), tool_type);
+                }
+                Err(e) => error!("cannot capture brush: {}", e),
+            }
+        }
+    });
+}
+
 pub fn draw_brush_tree(state: &mut State, ui: &Ui, current_brush: BrushType, tool_type: ToolType) {
     ui.tree_node(&im_str!("Brush")).default_open(true).build(|| {
         for (_index, brush) in BrushType::VARIANTS.iter().enumerate() {
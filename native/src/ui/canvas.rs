@@ -93,6 +93,9 @@ pub fn draw_canvas(rdr: &mut ImguiRenderer, state: &mut State, ui: &Ui) {
                     } else {
                         draw_cursor_cross(ui);
                     }
+                    if state.xpr_mut().toolbox.selected == ToolType::Line {
+                        draw_line_ratio(state, ui);
+                    }
                 });
 
             // ui.drag_float(&im_str!("scale"), &mut state.xpr_mut().canvas.scale)
@@ -164,3 +167,13 @@ fn draw_color_picker_icon(state: &mut State, ui: &Ui) {
     ui.set_cursor_screen_pos([x - 10., y - 10.]);
     Image::new(TextureId::from(state.icons["color_picker"]), [20., 20.]).build(&ui);
 }
+
+/// pixel step ratio of the line being drawn, next to the cursor
+fn draw_line_ratio(state: &mut State, ui: &Ui) {
+    let ratio = state.xpr().toolbox.line.borrow().ratio();
+    if let Some((x, y)) = ratio {
+        let pos = ui.io().mouse_pos;
+        let color: [f32; 4] = XpriteRgba::white().into();
+        ui.get_window_draw_list().add_text([pos[0] + 12., pos[1] + 12.], color, &format!("{}:{}", x, y));
+    }
+}
@@ -52,6 +52,16 @@ pub fn draw_exporter(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
 
                     ui.same_line(0.);
                     ui.checkbox(&im_str!("trim"), &mut spec!().trim);
+                    if spec!().format == ExporterFormat::GIF {
+                        ui.same_line(0.);
+                        let mut fps = spec!().fps as i32;
+                        if ui.drag_int(&im_str!("fps"), &mut fps).min(1).max(100).build() {
+                            spec!().fps = fps as u32;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text("frame rate of the palette color cycles");
+                        }
+                    }
 
                     ui.text("layer(s):");
                     ui.same_line(0.);
@@ -154,6 +164,19 @@ pub fn draw_exporter(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
                         if ui.drag_int(&im_str!("scale"), &mut scale).min(1).max(100).build() {
                             state.exporter.set_scale(i, scale as u32);
                         }
+                        ui.text("filter:");
+                        ui.same_line(0.);
+                        if ui.button(&im_str!("{}", spec!().filter.as_str()), [0., 0.]) {
+                            ui.open_popup(&im_str!("Upscale Filter"));
+                        }
+                        ui.popup(&im_str!("Upscale Filter"), || {
+                            for filter in &UpscaleFilter::VARIANTS {
+                                if ui.button(&im_str!("{}", filter.as_str()), [0., 0.]) {
+                                    state.exporter.set_filter(i, *filter);
+                                    ui.close_current_popup();
+                                }
+                            }
+                        });
                     }
 
                     let mut fname = ImString::with_capacity(100);
@@ -35,7 +35,7 @@ pub fn draw_file_manager(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
                         }
                     } else if fname.ends_with(".png") || fname.ends_with(".jpg") || fname.ends_with(".jpeg") {
                         if save {
-                            state.xpr_mut().save_img(&fname.to_owned(), 1);
+                            state.xpr_mut().save_img(&fname.to_owned(), 1, UpscaleFilter::Nearest);
                         } else {
                             state.push_xpr(Xprite::load_img(&fname.to_owned()));
                         }
@@ -32,6 +32,21 @@ pub fn draw_menu(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
             if MenuItem::new(&im_str!("Redo")).shortcut(&im_str!("Ctrl+Y")).build(&ui) {
                 state.xpr_mut().redo();
             }
+            if MenuItem::new(&im_str!("Replace Color")).build(&ui) {
+                state.toggle_replace_color();
+            }
+            if MenuItem::new(&im_str!("Adjust Colors")).build(&ui) {
+                state.toggle_adjust_colors();
+            }
+        });
+
+        ui.menu(&im_str!("View"), true, || {
+            for vision in VisionSimulation::VARIANTS.iter() {
+                let is_sel = state.vision == *vision;
+                if MenuItem::new(&im_str!("{}", vision.as_str())).selected(is_sel).build(&ui) {
+                    state.set_vision(*vision);
+                }
+            }
         });
 
         ui.menu(&im_str!("Panels"), true, || {
@@ -44,6 +59,9 @@ pub fn draw_menu(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
             if MenuItem::new(&im_str!("Brush")).build(&ui) {
                 state.toggle_brush();
             }
+            if MenuItem::new(&im_str!("Palette Report")).build(&ui) {
+                state.toggle_palette_report();
+            }
         });
 
         ui.menu(&im_str!("Docs"), true, || {
@@ -1,3 +1,4 @@
+pub mod adjust_colors;
 pub mod brush;
 pub mod canvas;
 pub mod console;
@@ -8,7 +9,9 @@ pub mod inputs;
 pub mod layers;
 pub mod menu;
 pub mod palette;
+pub mod palette_report;
 pub mod preview;
+pub mod replace_color;
 pub mod symmetry;
 pub mod tool_panel;
 pub mod toolbar;
@@ -28,6 +31,10 @@ pub fn draw(rdr: &mut ImguiRenderer, state: &mut State, ui: &Ui) -> bool {
         return true;
     }
 
+    if state.xpr().cycle_time.is_some() {
+        let dt = f64::from(ui.io().delta_time);
+        state.xpr_mut().advance_color_cycles(dt);
+    }
     state.xpr_mut().draw().unwrap();
     state.redraw_pixels(rdr).unwrap();
     state.xpr_mut().update().unwrap();
@@ -43,6 +50,9 @@ pub fn draw(rdr: &mut ImguiRenderer, state: &mut State, ui: &Ui) -> bool {
     self::layers::draw_layers(rdr, state, ui);
 
     self::symmetry::draw_symmetry(rdr, state, ui);
+    self::replace_color::draw_replace_color(rdr, state, ui);
+    self::adjust_colors::draw_adjust_colors(rdr, state, ui);
+    self::palette_report::draw_palette_report(rdr, state, ui);
     self::brush::draw_brush(rdr, state, ui);
     self::console::draw_console(rdr, state, ui);
 
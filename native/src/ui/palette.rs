@@ -81,12 +81,23 @@ pub fn draw_color_picker(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
 fn draw_cells(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
     let items: Vec<_> = state.xpr_mut().palette.palettes.keys().cloned().map(ImString::new).collect();
     let refs: Vec<_> = items.iter().collect();
-    let pal_idx: i32 = state.xpr_mut().palette.selected_palette_idx as i32;
-    if ComboBox::new(&im_str!("Palette")).build_simple_string(&ui, &mut (pal_idx as usize), &refs) {
-        state.xpr_mut().palette.selected_palette_idx = pal_idx as usize;
-        state.xpr_mut().set_redraw(true);
+    let mut pal_idx = state.xpr_mut().palette.selected_palette_idx;
+    if ComboBox::new(&im_str!("Library")).build_simple_string(&ui, &mut pal_idx, &refs) {
+        state.xpr_mut().palette.selected_palette_idx = pal_idx;
+    }
+    if ui.small_button(&im_str!("Use")) {
+        // the document palette is only replaced on request
+        if let Err(e) = state.xpr_mut().use_library_palette() {
+            error!("{}", e);
+        }
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("replace the document palette with the library palette");
     }
     ui.text(&im_str!("Color: {}", state.xpr().palette.current_color().0));
+    if ui.is_item_hovered() {
+        ui.tooltip_text("left click: primary, right click: secondary, X: swap");
+    }
 
     let temp = ui.cursor_screen_pos();
     let mut MARGIN = temp[0];
@@ -104,14 +115,19 @@ fn draw_cells(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
     //     LIGHT_GREY
     // ).filled(false).build(&ui);
     let mut modified = false;
+    let mut hovered = None;
+    let mut drag_start = None;
+    let dragging = state.palette_drag;
     let cols_per_row = state.cols_per_row as usize;
     let xpr = state.xpr_mut();
     let pal = xpr.palette.current_palette_mut();
     let color_idx = pal.idx;
+    let secondary_idx = pal.secondary_idx;
     let mut pal_ = pal.colors.borrow_mut();
     for (i, (_col_name, col)) in pal_.iter_mut().enumerate() {
         //        let is_sel = col == &xpr.selected_color;
         let is_sel = i == color_idx;
+        let is_secondary = i == secondary_idx;
         let x = MARGIN + BLOCK_SZ * ((i % cols_per_row) as f32);
         let y = PALETTE_BEGIN_Y + BLOCK_SZ * ((i / cols_per_row) as f32);
 
@@ -120,7 +136,8 @@ fn draw_cells(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
             let mut f = ColorEditFlags::empty();
             f.set(ColorEditFlags::HDR, true);
             f.set(ColorEditFlags::ALPHA_PREVIEW, true);
-            f.set(ColorEditFlags::NO_OPTIONS, false);
+            // right click selects the secondary color instead of opening options
+            f.set(ColorEditFlags::NO_OPTIONS, true);
             f.set(ColorEditFlags::NO_INPUTS, true);
             f.set(ColorEditFlags::NO_LABEL, true);
             f.set(ColorEditFlags::NO_PICKER, false);
@@ -133,10 +150,31 @@ fn draw_cells(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
             modified = true;
         }
 
-        if ui.is_item_hovered() && ui.io().mouse_down[0] {
+        // while reordering, only a new click changes the selection
+        let pressed = if dragging.is_some() {
+            ui.is_mouse_clicked(MouseButton::Left)
+        } else {
+            ui.io().mouse_down[0]
+        };
+        if ui.is_item_hovered() && pressed {
             // if clicked
             pal.idx = i;
         }
+        if ui.is_item_hovered() && ui.is_mouse_clicked(MouseButton::Right) {
+            pal.secondary_idx = i;
+        }
+        if ui.is_item_hovered() {
+            hovered = Some(i);
+            if ui.is_mouse_clicked(MouseButton::Left) {
+                drag_start = Some(i);
+            }
+        }
+
+        if dragging.is_some() && dragging != Some(i) && hovered == Some(i) {
+            // drop target
+            let draw_list = ui.get_window_draw_list();
+            draw_list.add_rect([x, y], [x + BLOCK_SZ, y + BLOCK_SZ], LIGHT_GREY).filled(false).build();
+        }
 
         if is_sel {
             let draw_list = ui.get_window_draw_list();
@@ -150,15 +188,381 @@ fn draw_cells(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
                 .filled(true)
                 .build();
         }
+        if is_secondary {
+            let draw_list = ui.get_window_draw_list();
+            draw_list
+                .add_triangle(
+                    [x + BLOCK_SZ - MARGIN / 4., y - MARGIN / 8.],
+                    [x + BLOCK_SZ / 2. - MARGIN / 4., y - MARGIN / 8.],
+                    [x + BLOCK_SZ - MARGIN / 4., y + BLOCK_SZ / 2. - MARGIN / 8.],
+                    LIGHT_GREY,
+                )
+                .filled(false)
+                .build();
+        }
     }
     drop(pal_);
     if modified {
         xpr.set_redraw(true);
     }
 
+    if drag_start.is_some() {
+        state.palette_drag = drag_start;
+    }
+    if let Some(from) = state.palette_drag {
+        if !ui.io().mouse_down[0] {
+            state.palette_drag = None;
+            if let Some(to) = hovered.filter(|&to| to != from) {
+                // indexed pixels follow the moved entry
+                state.xpr_mut().edit_palette(|pal| pal.move_entry(from, to));
+            }
+        }
+    }
+
     if ui.small_button(&im_str!("+")) {
-        let pal = state.xpr_mut().palette.current_palette();
-        let key = format!("my_color##{}", pal.colors.borrow().len());
-        pal.colors.borrow_mut().insert(key, Color::black());
+        let idx = state.xpr().palette.current_palette().idx;
+        state.xpr_mut().edit_palette(|pal| pal.insert(idx + 1, "my_color", Color::black()));
+    }
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Tools")) {
+        ui.open_popup(&im_str!("Palette tools"));
+    }
+    ui.popup(&im_str!("Palette tools"), || draw_palette_tools(state, ui));
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Ramp")) {
+        ui.open_popup(&im_str!("Color ramp"));
+    }
+    ui.popup(&im_str!("Color ramp"), || draw_color_ramp(state, ui));
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Cycle")) {
+        ui.open_popup(&im_str!("Color cycling"));
+    }
+    ui.popup(&im_str!("Color cycling"), || draw_color_cycles(state, ui));
+
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Add to library")) {
+        let name = state.xpr().name.clone();
+        state.xpr_mut().palette.copy_to_library(&name);
+    }
+
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Extract")) {
+        ui.open_popup(&im_str!("Extract palette"));
+    }
+    ui.popup(&im_str!("Extract palette"), || draw_extract_palette(state, ui));
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Convert")) {
+        ui.open_popup(&im_str!("Convert to palette"));
+    }
+    ui.popup(&im_str!("Convert to palette"), || draw_convert_to_palette(state, ui));
+
+    let formats = "gpl,pal,act,ase,hex,png";
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Import")) {
+        if let Ok(nfd::Response::Okay(fname)) = nfd::open_file_dialog(Some(formats), None) {
+            if let Err(e) = state.xpr_mut().palette.load_palette(&fname) {
+                error!("cannot import palette {}: {}", fname, e);
+            }
+        }
+    }
+    ui.same_line(0.);
+    if ui.small_button(&im_str!("Export")) {
+        ui.open_popup(&im_str!("Export palette"));
+    }
+    ui.popup(&im_str!("Export palette"), || {
+        // the format is picked here, `.ase` alone could also be an aseprite document
+        for fmt in PaletteFormat::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{} (.{})", fmt.as_str(), fmt.as_file_extension()))
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                if let Ok(nfd::Response::Okay(fname)) = nfd::open_save_dialog(Some(fmt.as_file_extension()), None) {
+                    if let Err(e) = state.xpr().palette.save_palette(&fname, *fmt) {
+                        error!("cannot export palette {}: {}", fname, e);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// builds a new library palette from the current layer or an image file
+fn draw_extract_palette(state: &mut State, ui: &Ui) {
+    for mode in PaletteExtraction::VARIANTS.iter() {
+        let is_sel = &state.palette_extraction == mode;
+        if Selectable::new(&im_str!("{}", mode.as_str()))
+            .selected(is_sel)
+            .flags(SelectableFlags::empty())
+            .size([0., 0.])
+            .build(&ui)
+        {
+            state.palette_extraction = *mode;
+        }
+    }
+    ui.drag_int(&im_str!("colors"), &mut state.palette_extraction_colors).min(1).max(256).build();
+    let mode = state.palette_extraction;
+    let n = state.palette_extraction_colors as usize;
+
+    if ui.button(&im_str!("From layer"), [0., 0.]) {
+        // restricted to the marquee selection if there is one
+        let selection = state.xpr().selection();
+        match state.xpr().extract_palette(mode, n, selection) {
+            Ok(pal) => {
+                let name = format!("{} ({})", state.xpr().name, mode.as_str());
+                state.xpr_mut().palette.add_to_library(&name, pal);
+            }
+            Err(e) => error!("cannot extract palette: {}", e),
+        }
+        ui.close_current_popup();
+    }
+    ui.same_line(0.);
+    if ui.button(&im_str!("From image..."), [0., 0.]) {
+        if let Ok(nfd::Response::Okay(fname)) = nfd::open_file_dialog(Some("png,jpg,jpeg,gif,bmp"), None) {
+            match image::open(&fname) {
+                Ok(im) => {
+                    let pal = PaletteGroup::extract_from_image(&im, n, mode);
+                    let name = std::path::Path::new(&fname)
+                        .file_stem()
+                        .and_then(|i| i.to_str())
+                        .unwrap_or("extracted")
+                        .to_owned();
+                    state.xpr_mut().palette.add_to_library(&name, pal);
+                }
+                Err(e) => error!("cannot open image {}: {}", fname, e),
+            }
+        }
+        ui.close_current_popup();
+    }
+}
+
+/// maps pixels onto the document palette as indexed colors
+fn draw_convert_to_palette(state: &mut State, ui: &Ui) {
+    ui.text("Color space");
+    for space in ColorSpace::VARIANTS.iter() {
+        let is_sel = &state.palette_color_space == space;
+        if Selectable::new(&im_str!("{}", space.as_str()))
+            .selected(is_sel)
+            .flags(SelectableFlags::empty())
+            .size([0., 0.])
+            .build(&ui)
+        {
+            state.palette_color_space = *space;
+        }
+    }
+    ui.separator();
+    ui.text("Dithering");
+    for dithering in Dithering::VARIANTS.iter() {
+        let is_sel = &state.palette_dithering == dithering;
+        if Selectable::new(&im_str!("{}", dithering.as_str()))
+            .selected(is_sel)
+            .flags(SelectableFlags::empty())
+            .size([0., 0.])
+            .build(&ui)
+        {
+            state.palette_dithering = *dithering;
+        }
+    }
+    ui.separator();
+    let space = state.palette_color_space;
+    let dithering = state.palette_dithering;
+
+    let mut ret = None;
+    if ui.button(&im_str!("Layer"), [0., 0.]) {
+        ret = Some(state.xpr_mut().convert_layer_to_palette(space, dithering, None));
+    }
+    ui.same_line(0.);
+    if ui.button(&im_str!("Selection"), [0., 0.]) {
+        let selection = state.xpr().selection();
+        if selection.is_some() {
+            ret = Some(state.xpr_mut().convert_layer_to_palette(space, dithering, selection));
+        } else {
+            ret = Some(Err("nothing selected".to_owned()));
+        }
+    }
+    ui.same_line(0.);
+    if ui.button(&im_str!("Document"), [0., 0.]) {
+        ret = Some(state.xpr_mut().convert_document_to_palette(space, dithering));
+    }
+    if let Some(ret) = ret {
+        if let Err(e) = ret {
+            error!("cannot convert to palette: {}", e);
+        }
+        ui.close_current_popup();
+    }
+}
+
+/// palette edits, indexed pixels are remapped so the artwork does not change
+fn draw_palette_tools(state: &mut State, ui: &Ui) {
+    for by in PaletteSort::VARIANTS.iter() {
+        if Selectable::new(&im_str!("Sort by {}", by.as_str().to_lowercase()))
+            .selected(false)
+            .flags(SelectableFlags::empty())
+            .size([0., 0.])
+            .build(&ui)
+        {
+            state.xpr_mut().edit_palette(|pal| pal.sort(*by));
+        }
+    }
+    ui.separator();
+    if Selectable::new(&im_str!("Remove duplicates"))
+        .selected(false)
+        .flags(SelectableFlags::empty())
+        .size([0., 0.])
+        .build(&ui)
+    {
+        state.xpr_mut().edit_palette(|pal| pal.dedupe());
+    }
+    if Selectable::new(&im_str!("Remove unused"))
+        .selected(false)
+        .flags(SelectableFlags::empty())
+        .size([0., 0.])
+        .build(&ui)
+    {
+        state.xpr_mut().remove_unused_colors();
+    }
+    if Selectable::new(&im_str!("Delete color"))
+        .selected(false)
+        .flags(SelectableFlags::empty())
+        .size([0., 0.])
+        .build(&ui)
+    {
+        let idx = state.xpr().palette.current_palette().idx;
+        state.xpr_mut().edit_palette(|pal| pal.retain(|i| i != idx));
+    }
+}
+
+/// generates a shading ramp around the current color
+fn draw_color_ramp(state: &mut State, ui: &Ui) {
+    let mut steps = state.ramp.steps as i32;
+    if ui.drag_int(&im_str!("steps"), &mut steps).min(2).max(16).build() {
+        state.ramp.steps = steps as usize;
+    }
+    let mut light = state.ramp.light as f32;
+    if ui.drag_float(&im_str!("light"), &mut light).min(0.).max(1.).speed(0.01).build() {
+        state.ramp.light = f64::from(light);
+    }
+    let mut dark = state.ramp.dark as f32;
+    if ui.drag_float(&im_str!("dark"), &mut dark).min(0.).max(1.).speed(0.01).build() {
+        state.ramp.dark = f64::from(dark);
+    }
+    let mut hue_shift = state.ramp.hue_shift as f32;
+    if ui.drag_float(&im_str!("hue shift"), &mut hue_shift).min(-90.).max(90.).speed(0.5).build() {
+        state.ramp.hue_shift = f64::from(hue_shift);
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("positive: warm highlights, cool shadows");
+    }
+    let mut saturation = state.ramp.saturation as f32;
+    if ui.drag_float(&im_str!("saturation"), &mut saturation).min(-1.).max(1.).speed(0.01).build() {
+        state.ramp.saturation = f64::from(saturation);
+    }
+
+    let base = match state.xpr().palette.current_color().1.to_rgba(None) {
+        Some(base) => base,
+        None => return,
+    };
+    let misc_flags = {
+        let mut f = ColorEditFlags::empty();
+        f.set(ColorEditFlags::NO_INPUTS, true);
+        f.set(ColorEditFlags::NO_LABEL, true);
+        f.set(ColorEditFlags::NO_PICKER, true);
+        f
+    };
+    for (i, c) in xprite::core::ramp::color_ramp(base, &state.ramp).into_iter().enumerate() {
+        if i != 0 {
+            ui.same_line(0.);
+        }
+        let mut sel: [f32; 4] = c.into();
+        ColorEdit::new(&im_str!("##ramp{}", i), &mut sel).flags(misc_flags).alpha(false).build(&ui);
+    }
+
+    if ui.button(&im_str!("Add ramp"), [0., 0.]) {
+        let params = state.ramp;
+        let name = format!("ramp{}", state.xpr().palette.current_palette().ramps.len());
+        state.xpr_mut().add_ramp(&name, base, &params);
+        ui.close_current_popup();
+    }
+}
+
+/// color cycling ranges of the document palette, with playback and baking
+fn draw_color_cycles(state: &mut State, ui: &Ui) {
+    let mut playing = state.xpr().cycle_time.is_some();
+    if ui.checkbox(&im_str!("play"), &mut playing) {
+        if playing {
+            state.xpr_mut().advance_color_cycles(0.);
+        } else {
+            state.xpr_mut().stop_color_cycles();
+        }
+    }
+
+    let last = state.xpr().palette.current_palette().colors.borrow().len().saturating_sub(1) as i32;
+    let mut to_remove = None;
+    for i in 0..state.xpr().palette.current_palette().cycles.len() {
+        let pushed_id = ui.push_id(i as i32);
+        let cycle = state.xpr().palette.current_palette().cycles[i];
+        let mut start = cycle.start as i32;
+        let mut end = cycle.end as i32;
+        let mut speed = cycle.speed as f32;
+        let mut direction = cycle.direction;
+        let mut changed = ui.drag_int(&im_str!("start"), &mut start).min(0).max(last).build();
+        changed |= ui.drag_int(&im_str!("end"), &mut end).min(0).max(last).build();
+        changed |= ui.drag_float(&im_str!("speed"), &mut speed).min(0.).max(60.).speed(0.1).build();
+        if ui.is_item_hovered() {
+            ui.tooltip_text("steps per second");
+        }
+        if ui.small_button(&im_str!("{}", direction.as_str())) {
+            let pos = CycleDirection::VARIANTS.iter().position(|d| *d == direction).unwrap();
+            direction = CycleDirection::VARIANTS[(pos + 1) % CycleDirection::VARIANTS.len()];
+            changed = true;
+        }
+        ui.same_line(0.);
+        if ui.small_button(&im_str!("remove")) {
+            to_remove = Some(i);
+        }
+        if changed {
+            state.xpr_mut().palette.current_palette_mut().cycles[i] = ColorCycle::new(start as usize, end as usize, f64::from(speed), direction);
+            state.xpr_mut().set_redraw(true);
+        }
+        ui.separator();
+        pushed_id.pop(&ui);
+    }
+    if let Some(i) = to_remove {
+        state.xpr_mut().palette.current_palette_mut().cycles.remove(i);
+        state.xpr_mut().set_redraw(true);
+    }
+
+    if ui.button(&im_str!("Add cycle"), [0., 0.]) {
+        let pal = state.xpr_mut().palette.current_palette_mut();
+        let cycle = ColorCycle::new(pal.idx, pal.secondary_idx, 8., CycleDirection::Forward);
+        pal.cycles.push(cycle);
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("cycles the entries from the primary to the secondary color");
+    }
+
+    ui.drag_int(&im_str!("fps"), &mut state.cycle_fps).min(1).max(100).build();
+    let fps = f64::from(state.cycle_fps);
+    if ui.button(&im_str!("Bake frames"), [0., 0.]) {
+        match state.xpr_mut().bake_color_cycles(fps) {
+            Ok(n) => info!("added {} frames", n),
+            Err(e) => error!("cannot bake color cycles: {}", e),
+        }
+    }
+    ui.same_line(0.);
+    if ui.button(&im_str!("Export GIF"), [0., 0.]) {
+        if let Ok(nfd::Response::Okay(fname)) = nfd::open_save_dialog(Some("gif"), None) {
+            let xpr = state.xpr();
+            let bb = Rect(
+                Vec2f { x: 0., y: 0. },
+                Vec2f {
+                    x: xpr.canvas.art_w - 1.,
+                    y: xpr.canvas.art_h - 1.,
+                },
+            );
+            if let Err(e) = xpr.save_cycle_gif(&xpr.flatten_visible(), bb, &fname, 1, UpscaleFilter::Nearest, fps) {
+                error!("cannot export gif {}: {}", fname, e);
+            }
+        }
     }
 }
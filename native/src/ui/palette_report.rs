@@ -0,0 +1,65 @@
+use crate::prelude::*;
+
+pub fn draw_palette_report(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
+    if !state.show_palette_report {
+        return;
+    }
+    let sz = ui.io().display_size;
+    Window::new(&im_str!("Palette Report"))
+        .bring_to_front_on_focus(true)
+        .movable(true)
+        .position([sz[0] as f32 - RIGHT_SIDE_WIDTH * 2., 40.], Condition::Once)
+        .size([RIGHT_SIDE_WIDTH, 300.], Condition::Once)
+        .collapsible(true)
+        .resizable(true)
+        .build(&ui, || {
+            ui.drag_float(&im_str!("min contrast"), &mut state.contrast_threshold)
+                .min(1.)
+                .max(3.)
+                .speed(0.01)
+                .build();
+            if ui.is_item_hovered() {
+                ui.tooltip_text("WCAG contrast ratio between neighbouring ramp entries");
+            }
+            ui.text(&im_str!("seen with {} vision", state.vision.as_str().to_lowercase()));
+            ui.separator();
+
+            let pal = state.xpr().palette.current_palette();
+            if pal.ramps.is_empty() {
+                ui.text("the palette has no ramps");
+                return;
+            }
+            let issues = pal.contrast_report(f64::from(state.contrast_threshold), state.vision);
+            if issues.is_empty() {
+                ui.text("no issues");
+                return;
+            }
+            let misc_flags = {
+                let mut f = ColorEditFlags::empty();
+                f.set(ColorEditFlags::NO_INPUTS, true);
+                f.set(ColorEditFlags::NO_LABEL, true);
+                f.set(ColorEditFlags::NO_PICKER, true);
+                f
+            };
+            let mut selected = None;
+            for (i, issue) in issues.iter().enumerate() {
+                let (a, b) = issue.entries;
+                for (j, idx) in [a, b].iter().enumerate() {
+                    let col = Color::Indexed(*idx).to_rgba(Some(state.xpr())).unwrap_or_else(XpriteRgba::transparent);
+                    let mut sel: [f32; 4] = state.vision.apply(col).into();
+                    ColorEdit::new(&im_str!("##issue{}_{}", i, j), &mut sel)
+                        .flags(misc_flags)
+                        .alpha(false)
+                        .build(&ui);
+                    if ui.is_item_hovered() && ui.is_mouse_clicked(MouseButton::Left) {
+                        selected = Some(*idx);
+                    }
+                    ui.same_line(0.);
+                }
+                ui.text(&im_str!("{}: #{} / #{} ({:.2}:1)", issue.ramp, a, b, issue.ratio));
+            }
+            if let Some(idx) = selected {
+                state.xpr_mut().palette.current_palette_mut().idx = idx;
+            }
+        });
+}
@@ -0,0 +1,72 @@
+use crate::prelude::*;
+
+pub fn draw_replace_color(_rdr: &dyn Renderer, state: &mut State, ui: &Ui) {
+    if !state.show_replace_color {
+        return;
+    }
+    let sz = ui.io().display_size;
+    Window::new(&im_str!("Replace Color"))
+        .bring_to_front_on_focus(true)
+        .movable(true)
+        .position([sz[0] as f32 - RIGHT_SIDE_WIDTH * 3., 40.], Condition::Once)
+        .size([RIGHT_SIDE_WIDTH, 300.], Condition::Once)
+        .collapsible(true)
+        .resizable(true)
+        .build(&ui, || {
+            let misc_flags = {
+                let mut f = ColorEditFlags::empty();
+                f.set(ColorEditFlags::ALPHA_PREVIEW, true);
+                f.set(ColorEditFlags::NO_INPUTS, true);
+                f
+            };
+            let current = state.xpr().color();
+
+            let from = state.replace_color.from;
+            let mut sel: [f32; 4] = from.to_rgba(Some(state.xpr())).unwrap_or_else(XpriteRgba::transparent).into();
+            if ColorEdit::new(&im_str!("from"), &mut sel).flags(misc_flags).alpha(true).build(&ui) {
+                state.replace_color.from = sel.into();
+            }
+            ui.same_line(0.);
+            if ui.small_button(&im_str!("current##from")) {
+                state.replace_color.from = current;
+            }
+
+            let to = state.replace_color.to;
+            let mut sel: [f32; 4] = to.to_rgba(Some(state.xpr())).unwrap_or_else(XpriteRgba::transparent).into();
+            if ColorEdit::new(&im_str!("to"), &mut sel).flags(misc_flags).alpha(true).build(&ui) {
+                state.replace_color.to = sel.into();
+            }
+            ui.same_line(0.);
+            if ui.small_button(&im_str!("current##to")) {
+                state.replace_color.to = current;
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text("use the selected palette color, kept as an index");
+            }
+
+            ui.drag_int(&im_str!("tolerance"), &mut state.replace_color.tolerance).min(0).max(255).build();
+
+            for scope in ReplaceScope::VARIANTS.iter() {
+                let is_sel = &state.replace_color.scope == scope;
+                if Selectable::new(&im_str!("{}", scope.as_str()))
+                    .selected(is_sel)
+                    .flags(SelectableFlags::empty())
+                    .size([0., 0.])
+                    .build(&ui)
+                {
+                    state.replace_color.scope = *scope;
+                }
+            }
+
+            if ui.button(&im_str!("Replace"), [0., 0.]) {
+                let from = state.replace_color.from;
+                let to = state.replace_color.to;
+                let tolerance = state.replace_color.tolerance as u8;
+                let scope = state.replace_color.scope;
+                match state.xpr_mut().replace_color(from, to, tolerance, scope) {
+                    Ok(n) => info!("replaced {} pixels", n),
+                    Err(e) => error!("cannot replace color: {}", e),
+                }
+            }
+        });
+}
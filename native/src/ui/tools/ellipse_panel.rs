@@ -1,9 +1,18 @@
 use crate::prelude::*;
 use std::f64::consts::PI;
+use std::rc::Rc;
 
 pub fn draw(state: &mut State, ui: &Ui) {
-    let mut tool = state.xpr_mut().toolbox.ellipse.borrow_mut();
+    let tool = Rc::clone(&state.xpr_mut().toolbox.ellipse);
+    let mut tool = tool.borrow_mut();
     if ui.checkbox(&im_str!("filled"), &mut tool.filled) {}
+    Slider::new(&im_str!("Angle"), (0.)..=(360.)).build(&ui, &mut tool.angle);
+    super::draw_pattern_picker(ui, &mut tool.pattern);
+    if !tool.filled {
+        ui.tree_node(&im_str!("Outline")).build(|| {
+            super::draw_stroke_pipeline(state, ui, &mut tool.pipeline);
+        });
+    }
 
     if let Some(info) = tool.get_info() {
         ui.tree_node(&im_str!("Status")).default_open(true).build(|| {
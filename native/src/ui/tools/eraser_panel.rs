@@ -1,6 +1,9 @@
 use crate::prelude::*;
+use std::rc::Rc;
 
 pub fn draw(state: &mut State, ui: &Ui) {
+    let tool = Rc::clone(&state.xpr_mut().toolbox.eraser);
+    super::draw_stroke_pipeline(state, ui, &mut tool.borrow_mut().pipeline);
     if ui.button(&im_str!("toggle brush"), [0., 0.]) {
         state.toggle_brush();
     }
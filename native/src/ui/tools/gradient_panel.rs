@@ -0,0 +1,72 @@
+use crate::prelude::*;
+use std::rc::Rc;
+use xprite::algorithms::floodfill::FloodFillDegrees;
+use xprite::algorithms::gradient::GradientShape;
+use xprite::tools::gradient::{GradientColors, GradientRegion};
+
+pub fn draw(state: &mut State, ui: &Ui) {
+    let tool = Rc::clone(&state.xpr_mut().toolbox.gradient);
+    let mut tool = tool.borrow_mut();
+
+    ui.tree_node(&im_str!("Shape")).default_open(true).build(|| {
+        for shape in GradientShape::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{}", shape.as_str()))
+                .selected(&tool.shape == shape)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                tool.shape = *shape;
+            }
+        }
+    });
+
+    ui.tree_node(&im_str!("Colors")).default_open(true).build(|| {
+        for source in GradientColors::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{}", source.as_str()))
+                .selected(&tool.source == source)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                tool.source = *source;
+            }
+            if ui.is_item_hovered() && *source == GradientColors::Ramp {
+                ui.tooltip_text("from the current color along its palette ramp, up to the other color if it is on the same ramp");
+            }
+        }
+    });
+    Slider::new(&im_str!("Dither"), (0.)..=(1.)).build(&ui, &mut tool.dither);
+    if ui.is_item_hovered() {
+        ui.tooltip_text("share of each band blended into the next with ordered dithering");
+    }
+
+    ui.tree_node(&im_str!("Region")).default_open(true).build(|| {
+        for region in GradientRegion::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{}", region.as_str()))
+                .selected(&tool.region == region)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                tool.region = *region;
+            }
+        }
+        if tool.region == GradientRegion::FloodFill {
+            for degrees in FloodFillDegrees::VARIANTS.iter() {
+                if Selectable::new(&im_str!("{}", degrees.as_str()))
+                    .selected(&tool.degrees == degrees)
+                    .flags(SelectableFlags::empty())
+                    .size([0., 0.])
+                    .build(&ui)
+                {
+                    tool.degrees = *degrees;
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(degrees.description());
+                }
+            }
+        }
+    });
+    ui.text("shift: snap to 45 degrees");
+}
@@ -0,0 +1,30 @@
+use crate::prelude::*;
+use std::rc::Rc;
+
+pub fn draw(state: &mut State, ui: &Ui) {
+    let tool = Rc::clone(&state.xpr_mut().toolbox.iso_box);
+    let mut tool = tool.borrow_mut();
+
+    ui.checkbox(&im_str!("filled"), &mut tool.filled);
+    ui.checkbox(&im_str!("outlined"), &mut tool.outlined);
+
+    ui.tree_node(&im_str!("Shades")).default_open(true).build(|| {
+        let faces = ["top", "left", "right"];
+        for (face, shade) in faces.iter().zip(tool.shades.iter_mut()) {
+            let mut steps = *shade as i32;
+            if Slider::new(&im_str!("{}", face), (-4)..=(4)).build(&ui, &mut steps) {
+                *shade = steps as isize;
+            }
+        }
+        let mut steps = tool.outline_shade as i32;
+        if Slider::new(&im_str!("outline"), (-4)..=(4)).build(&ui, &mut steps) {
+            tool.outline_shade = steps as isize;
+        }
+    });
+    if ui.is_item_hovered() {
+        ui.tooltip_text("steps along the palette ramp of the current color");
+    }
+
+    super::draw_tile_width(state, ui);
+    ui.text("shift: snap to tiles");
+}
@@ -1,3 +1,38 @@
 use crate::prelude::*;
+use std::rc::Rc;
+use xprite::tools::line::{Line, LineMode};
 
-pub fn draw(_state: &mut State, _ui: &Ui) {}
+pub fn draw(state: &mut State, ui: &Ui) {
+    let tool = Rc::clone(&state.xpr_mut().toolbox.line);
+    let mut tool = tool.borrow_mut();
+
+    ui.tree_node(&im_str!("Mode")).default_open(true).build(|| {
+        for mode in LineMode::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{}", mode.as_str()))
+                .selected(&tool.mode == mode)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                tool.mode = *mode;
+            }
+        }
+    });
+
+    if tool.mode == LineMode::Free {
+        ui.tree_node(&im_str!("Shift snaps to")).default_open(true).build(|| {
+            for ratio in Line::RATIO_CHOICES.iter() {
+                let mut on = tool.ratios.contains(ratio);
+                if ui.checkbox(&im_str!("{}:{}", ratio.0, ratio.1), &mut on) {
+                    if on {
+                        tool.ratios.push(*ratio);
+                    } else {
+                        tool.ratios.retain(|r| r != ratio);
+                    }
+                }
+            }
+        });
+    }
+
+    super::draw_stroke_pipeline(state, ui, &mut tool.pipeline);
+}
@@ -1,3 +1,38 @@
 use crate::prelude::*;
+use std::rc::Rc;
 
-pub fn draw(_rdr: &mut dyn Renderer, _state: &mut State, _ui: &Ui) {}
+pub fn draw(_rdr: &mut dyn Renderer, state: &mut State, ui: &Ui) {
+    ui.tree_node(&im_str!("Upscale")).default_open(true).build(|| {
+        let marquee = Rc::clone(&state.xpr().toolbox.marquee);
+        for filter in UpscaleFilter::VARIANTS.iter() {
+            let is_sel = &marquee.borrow().upscale_filter == filter;
+            if Selectable::new(&im_str!("{}", filter.as_str()))
+                .selected(is_sel)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                marquee.borrow_mut().upscale_filter = *filter;
+            }
+        }
+
+        let mut scale = marquee.borrow().upscale_scale as i32;
+        if ui.drag_int(&im_str!("scale"), &mut scale).min(2).max(8).build() {
+            marquee.borrow_mut().upscale_scale = scale as u32;
+        }
+
+        if ui.button(&im_str!("Upscale"), [0., 0.]) {
+            let (filter, scale) = {
+                let m = marquee.borrow();
+                (m.upscale_filter, m.upscale_scale)
+            };
+            let selection = state.xpr().selection();
+            if let Err(e) = state.xpr_mut().upscale_layer(filter, scale, selection) {
+                error!("{}", e);
+            }
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text("upscale the selection, or the whole layer if nothing is selected");
+        }
+    });
+}
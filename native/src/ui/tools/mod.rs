@@ -1,15 +1,22 @@
 use crate::prelude::*;
+use xprite::algorithms::dither::MAX_TILE_SIZE;
+use xprite::algorithms::iso::tile_width;
 
 pub mod autoshade_panel;
 pub mod colorpicker_panel;
 pub mod ellipse_panel;
 pub mod eraser_panel;
+pub mod gradient_panel;
+pub mod iso_box_panel;
 pub mod line_panel;
 pub mod marquee_panel;
 pub mod paintbucket_panel;
 pub mod pencil_panel;
+pub mod polygon_panel;
 pub mod rect_panel;
 pub mod settings_panel;
+pub mod shape_panel;
+pub mod spray_panel;
 pub mod texture_panel;
 pub mod vector_panel;
 
@@ -28,5 +35,135 @@ pub fn draw(selected: ToolType, rdr: &mut dyn Renderer, state: &mut State, ui: &
         ToolType::Settings => settings_panel::draw(rdr, state, ui),
         ToolType::Symmetry => (),
         ToolType::AutoShade => autoshade_panel::draw(rdr, state, ui),
+        ToolType::Spray => spray_panel::draw(state, ui),
+        ToolType::Polygon => polygon_panel::draw(state, ui),
+        ToolType::IsoBox => iso_box_panel::draw(state, ui),
+        ToolType::Shape => shape_panel::draw(state, ui),
+        ToolType::Gradient => gradient_panel::draw(state, ui),
+    }
+}
+
+/// width of the isometric tiles, shared by the grid and the isometric tools
+pub fn draw_tile_width(state: &mut State, ui: &Ui) {
+    let mut tile_w = state.xpr().canvas.tile_w as i32;
+    if Slider::new(&im_str!("Tile width"), (4)..=(128)).build(&ui, &mut tile_w) {
+        state.xpr_mut().canvas.tile_w = tile_width(tile_w.max(0) as u32);
+    }
+}
+
+/// pattern the tool paints through, anchored to the canvas
+pub fn draw_pattern_picker(ui: &Ui, pattern: &mut DitherPattern) {
+    ui.tree_node(&im_str!("Pattern")).default_open(true).build(|| {
+        for variant in DitherPattern::VARIANTS.iter() {
+            let is_sel = pattern.as_str() == variant.as_str();
+            if Selectable::new(&im_str!("{}##pattern", variant.as_str()))
+                .selected(is_sel)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+                && !is_sel
+            {
+                *pattern = variant.as_str().parse().unwrap();
+            }
+        }
+        match pattern {
+            DitherPattern::Bayer(level) => {
+                let mut n = i32::from(*level);
+                if ui.drag_int(&im_str!("level"), &mut n).min(0).max(16).build() {
+                    *level = n.max(0).min(16) as u8;
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("pixels painted out of every 16");
+                }
+            }
+            DitherPattern::Custom(tile) => {
+                let mut w = tile.first().map_or(0, Vec::len) as i32;
+                let mut h = tile.len() as i32;
+                let max = MAX_TILE_SIZE as i32;
+                let mut resized = ui.drag_int(&im_str!("width##tile"), &mut w).min(1).max(max).build();
+                resized |= ui.drag_int(&im_str!("height##tile"), &mut h).min(1).max(max).build();
+                if resized {
+                    DitherPattern::resize_tile(tile, w.max(1) as usize, h.max(1) as usize);
+                }
+                for (y, row) in tile.iter_mut().enumerate() {
+                    for (x, cell) in row.iter_mut().enumerate() {
+                        if x > 0 {
+                            ui.same_line(0.);
+                        }
+                        ui.checkbox(&im_str!("##tile{},{}", x, y), cell);
+                    }
+                }
+            }
+            _ => (),
+        }
+    });
+}
+
+/// pixel perfect, sorting and anti-aliasing options shared by the drawing tools
+pub fn draw_stroke_pipeline(state: &mut State, ui: &Ui, p: &mut StrokePipeline) {
+    let mut pp = p.run_pixel_perfect == Some(true);
+    let mut pap = p.run_pixel_perfect == Some(false);
+
+    let disabled = p.sorted_monotonic || p.selective_anti_aliasing;
+    if ui.checkbox(&im_str!("Pixel Perfect"), &mut pp) && !disabled {
+        if !pp && !pap {
+            p.run_pixel_perfect = None;
+        } else {
+            p.run_pixel_perfect = Some(true);
+        }
+    }
+    if ui.checkbox(&im_str!("Pixel AntiPerfect"), &mut pap) && !disabled {
+        if !pp && !pap {
+            p.run_pixel_perfect = None;
+        } else {
+            p.run_pixel_perfect = Some(false);
+        }
+    }
+
+    if ui.checkbox(&im_str!("Sorted Monotonic"), &mut p.sorted_monotonic) && p.sorted_monotonic {
+        p.run_pixel_perfect = Some(true);
+    }
+
+    if ui.checkbox(&im_str!("Selective Anti-Aliasing"), &mut p.selective_anti_aliasing) && p.selective_anti_aliasing {
+        p.run_pixel_perfect = Some(true);
+    }
+
+    if p.selective_anti_aliasing {
+        ui.tree_node(&im_str!("Selective Anti Aliasing Options")).default_open(true).build(|| {
+            Slider::new(&im_str!("Threshold"), (0.)..=(1.)).build(&ui, &mut p.aa_threshold);
+            Slider::new(&im_str!("Min Segment"), (1)..=(100)).build(&ui, &mut p.min_segment_length);
+
+            let mut sel: [f32; 4] = p.aa_alt_color.unwrap_or_else(Color::black).to_rgba(Some(state.xpr())).unwrap().into();
+            let id = im_str!("##{}", "background");
+            let misc_flags = {
+                let mut f = ColorEditFlags::empty();
+                f.set(ColorEditFlags::HDR, true);
+                f.set(ColorEditFlags::ALPHA_PREVIEW, true);
+                f.set(ColorEditFlags::NO_OPTIONS, false);
+                f.set(ColorEditFlags::NO_INPUTS, true);
+                f.set(ColorEditFlags::NO_LABEL, true);
+                f.set(ColorEditFlags::NO_PICKER, false);
+                f
+            };
+            let b = ColorEdit::new(&id, &mut sel).flags(misc_flags).alpha(false);
+            if b.build(&ui) {
+                let color = sel.into();
+                match p.aa_alt_color {
+                    None => {
+                        let idx = state.xpr_mut().palette.find_color(color).unwrap_or(0);
+                        p.aa_alt_color = Some(Color::Indexed(idx));
+                    }
+                    Some(c) => {
+                        let idx = unsafe { c.as_index() };
+                        state.xpr_mut().palette.modify_color(idx, color);
+                        state.xpr_mut().set_redraw(true);
+                    }
+                }
+            }
+
+            if p.aa_alt_color.is_none() && ui.is_item_hovered() {
+                ui.tooltip_text("Drag and drop from your palette");
+            }
+        });
     }
 }
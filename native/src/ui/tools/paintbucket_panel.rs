@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use std::rc::Rc;
 use xprite::algorithms::floodfill;
 use xprite::tools::paint_bucket;
 
@@ -35,4 +36,7 @@ pub fn draw(state: &mut State, ui: &Ui) {
             }
         }
     });
+
+    let bucket = Rc::clone(&state.xpr_mut().toolbox.paint_bucket);
+    super::draw_pattern_picker(ui, &mut bucket.borrow_mut().pattern);
 }
@@ -0,0 +1,46 @@
+use crate::prelude::*;
+use std::rc::Rc;
+use xprite::tools::polygon::{PolygonMode, PolygonSnap};
+
+pub fn draw(state: &mut State, ui: &Ui) {
+    let tool = Rc::clone(&state.xpr_mut().toolbox.polygon);
+    let mut tool = tool.borrow_mut();
+
+    ui.tree_node(&im_str!("Mode")).default_open(true).build(|| {
+        for mode in PolygonMode::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{}", mode.as_str()))
+                .selected(&tool.mode == mode)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                tool.mode = *mode;
+            }
+        }
+    });
+    if tool.mode == PolygonMode::Polygon {
+        ui.checkbox(&im_str!("filled"), &mut tool.filled);
+    }
+
+    ui.tree_node(&im_str!("Snap")).default_open(true).build(|| {
+        for snap in PolygonSnap::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{}", snap.as_str()))
+                .selected(&tool.snap == snap)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                tool.snap = *snap;
+            }
+        }
+    });
+    ui.text("shift: snap");
+    ui.text("enter or double click: finish");
+    ui.text("escape: cancel");
+
+    super::draw_stroke_pipeline(state, ui, &mut tool.pipeline);
+
+    if ui.button(&im_str!("toggle brush"), [0., 0.]) {
+        state.toggle_brush();
+    }
+}
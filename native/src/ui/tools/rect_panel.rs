@@ -1,9 +1,21 @@
 use crate::prelude::*;
 use std::f64::consts::PI;
+use std::rc::Rc;
 
 pub fn draw(state: &mut State, ui: &Ui) {
-    let mut tool = state.xpr_mut().toolbox.rect.borrow_mut();
+    let tool = Rc::clone(&state.xpr_mut().toolbox.rect);
+    let mut tool = tool.borrow_mut();
     if ui.checkbox(&im_str!("filled"), &mut tool.filled) {}
+    let mut radius = tool.corner_radius as i32;
+    if Slider::new(&im_str!("Corner radius"), (0)..=(32)).build(&ui, &mut radius) {
+        tool.corner_radius = radius as u32;
+    }
+    super::draw_pattern_picker(ui, &mut tool.pattern);
+    if !tool.filled {
+        ui.tree_node(&im_str!("Outline")).build(|| {
+            super::draw_stroke_pipeline(state, ui, &mut tool.pipeline);
+        });
+    }
 
     if let Some(info) = tool.get_info() {
         ui.tree_node(&im_str!("Status")).default_open(true).build(|| {
@@ -70,6 +70,20 @@ pub fn draw(_rdr: &mut dyn Renderer, state: &mut State, ui: &Ui) {
     ui.tree_node(&im_str!("Show grid")).default_open(true).build(|| {
         // checkbox for show grid
         ui.checkbox(&im_str!("grid"), &mut state.xpr_mut().canvas.show_grid);
+        for mode in GridMode::VARIANTS.iter() {
+            let is_sel = &state.xpr().canvas.grid_mode == mode;
+            if Selectable::new(&im_str!("{}##grid", mode.as_str()))
+                .selected(is_sel)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                state.xpr_mut().canvas.grid_mode = *mode;
+            }
+        }
+        if state.xpr().canvas.grid_mode == GridMode::Isometric {
+            super::draw_tile_width(state, ui);
+        }
         // ui.text(&im_str!("{}, {}", state.xpr().last_mouse_pos.y, state.xpr().last_mouse_pos.x));
     });
 }
@@ -0,0 +1,49 @@
+use crate::prelude::*;
+use std::rc::Rc;
+use xprite::tools::shape::ShapeKind;
+
+pub fn draw(state: &mut State, ui: &Ui) {
+    let tool = Rc::clone(&state.xpr_mut().toolbox.shape);
+    let mut tool = tool.borrow_mut();
+
+    ui.tree_node(&im_str!("Shape")).default_open(true).build(|| {
+        for kind in ShapeKind::VARIANTS.iter() {
+            if Selectable::new(&im_str!("{}", kind.as_str()))
+                .selected(&tool.kind == kind)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                tool.kind = *kind;
+            }
+        }
+    });
+    ui.checkbox(&im_str!("filled"), &mut tool.filled);
+
+    match tool.kind {
+        ShapeKind::RegularPolygon | ShapeKind::Star => {
+            let label = if tool.kind == ShapeKind::Star { "Points" } else { "Sides" };
+            let mut sides = tool.sides as i32;
+            if Slider::new(&im_str!("{}", label), (3)..=(16)).build(&ui, &mut sides) {
+                tool.sides = sides.max(3) as usize;
+            }
+            Slider::new(&im_str!("Rotation"), (0.)..=(360.)).build(&ui, &mut tool.rotation);
+            if tool.kind == ShapeKind::Star {
+                Slider::new(&im_str!("Inner radius"), (0.)..=(1.)).build(&ui, &mut tool.inner_radius);
+            }
+        }
+        ShapeKind::Arc | ShapeKind::Pie => {
+            Slider::new(&im_str!("Start"), (0.)..=(360.)).build(&ui, &mut tool.arc_start);
+            Slider::new(&im_str!("Sweep"), (0.)..=(360.)).build(&ui, &mut tool.arc_sweep);
+        }
+    }
+
+    super::draw_pattern_picker(ui, &mut tool.pattern);
+    let traced = tool.kind == ShapeKind::RegularPolygon || tool.kind == ShapeKind::Star;
+    if !tool.filled && traced {
+        ui.tree_node(&im_str!("Outline")).build(|| {
+            super::draw_stroke_pipeline(state, ui, &mut tool.pipeline);
+        });
+    }
+    ui.text("shift: square, ctrl: from the center");
+}
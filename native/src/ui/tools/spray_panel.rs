@@ -0,0 +1,41 @@
+use crate::prelude::*;
+use std::rc::Rc;
+use xprite::algorithms::spray::SprayDistribution;
+
+pub fn draw(state: &mut State, ui: &Ui) {
+    let spray = Rc::clone(&state.xpr_mut().toolbox.spray);
+    let mut spray = spray.borrow_mut();
+
+    Slider::new(&im_str!("Radius"), (1.)..=(32.)).build(&ui, &mut spray.radius);
+    Slider::new(&im_str!("Density"), (0.)..=(1.)).build(&ui, &mut spray.density);
+    if ui.is_item_hovered() {
+        ui.tooltip_text("share of the pixels under the spray painted each time the cursor moves");
+    }
+
+    ui.tree_node(&im_str!("Distribution")).default_open(true).build(|| {
+        for distribution in SprayDistribution::VARIANTS.iter() {
+            let is_sel = &spray.distribution == distribution;
+            if Selectable::new(&im_str!("{}", distribution.as_str()))
+                .selected(is_sel)
+                .flags(SelectableFlags::empty())
+                .size([0., 0.])
+                .build(&ui)
+            {
+                spray.distribution = *distribution;
+            }
+        }
+    });
+
+    let mut spread = spray.ramp_spread as i32;
+    if Slider::new(&im_str!("Ramp spread"), (0)..=(8)).build(&ui, &mut spread) {
+        spray.ramp_spread = spread.max(0) as usize;
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("also spray the palette ramp entries this many steps around the current color");
+    }
+
+    let mut seed = spray.seed as i32;
+    if ui.input_int(&im_str!("Seed"), &mut seed).build() {
+        spray.seed = seed.max(0) as u64;
+    }
+}
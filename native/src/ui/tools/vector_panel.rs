@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use std::rc::Rc;
 use xprite::tools::vector;
 
 pub fn draw(state: &mut State, ui: &Ui) {
@@ -12,6 +13,9 @@ pub fn draw(state: &mut State, ui: &Ui) {
 
     draw_mode(state, ui);
 
+    let tool = Rc::clone(&state.xpr_mut().toolbox.vector);
+    super::draw_stroke_pipeline(state, ui, &mut tool.borrow_mut().pipeline);
+
     if ui.button(&im_str!("toggle brush"), [0., 0.]) {
         state.toggle_brush();
     }
@@ -0,0 +1,174 @@
+//! Color adjustments applied to every pixel independently
+use crate::prelude::*;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Adjustment {
+    /// hue shift in degrees, saturation and value offsets in [-1, 1]
+    Hsv {
+        hue: f64,
+        saturation: f64,
+        value: f64,
+    },
+    /// both in [-1, 1]
+    BrightnessContrast {
+        brightness: f64,
+        contrast: f64,
+    },
+    /// input black and white points in [0, 1]
+    Levels {
+        black: f64,
+        white: f64,
+        gamma: f64,
+    },
+    Posterize {
+        levels: u8,
+    },
+    Invert,
+    Desaturate,
+    /// keeps the lightness, replaces hue and saturation
+    Colorize {
+        hue: f64,
+        saturation: f64,
+    },
+}
+
+impl Adjustment {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Adjustment::Hsv { .. } => "Hue/Saturation",
+            Adjustment::BrightnessContrast { .. } => "Brightness/Contrast",
+            Adjustment::Levels { .. } => "Levels",
+            Adjustment::Posterize { .. } => "Posterize",
+            Adjustment::Invert => "Invert",
+            Adjustment::Desaturate => "Desaturate",
+            Adjustment::Colorize { .. } => "Colorize",
+        }
+    }
+
+    /// every kind of adjustment, with neutral parameters
+    pub const VARIANTS: [Adjustment; 7] = [
+        Adjustment::Hsv {
+            hue: 0.,
+            saturation: 0.,
+            value: 0.,
+        },
+        Adjustment::BrightnessContrast { brightness: 0., contrast: 0. },
+        Adjustment::Levels {
+            black: 0.,
+            white: 1.,
+            gamma: 1.,
+        },
+        Adjustment::Posterize { levels: 4 },
+        Adjustment::Invert,
+        Adjustment::Desaturate,
+        Adjustment::Colorize { hue: 0., saturation: 0.5 },
+    ];
+
+    /// adjusted color, alpha is kept
+    pub fn apply(&self, col: XpriteRgba) -> XpriteRgba {
+        let clamp = |i: f64| i.max(0.).min(1.);
+        match *self {
+            Adjustment::Hsv { hue, saturation, value } => {
+                let (h, s, v) = col.to_hsv();
+                XpriteRgba::from_hsv(h + hue, clamp(s + saturation), clamp(v + value), col.a)
+            }
+            Adjustment::BrightnessContrast { brightness, contrast } => {
+                let factor = (1. + contrast) / (1. - contrast).max(0.01);
+                map_channels(col, |c| (c - 0.5) * factor + 0.5 + brightness)
+            }
+            Adjustment::Levels { black, white, gamma } => {
+                let range = (white - black).max(0.001);
+                map_channels(col, |c| clamp((c - black) / range).powf(1. / gamma.max(0.01)))
+            }
+            Adjustment::Posterize { levels } => {
+                let n = f64::from(levels.max(2) - 1);
+                map_channels(col, |c| (c * n).round() / n)
+            }
+            Adjustment::Invert => map_channels(col, |c| 1. - c),
+            Adjustment::Desaturate => {
+                let l = luma(col);
+                map_channels(col, |_| l)
+            }
+            Adjustment::Colorize { hue, saturation } => XpriteRgba::from_hsv(hue, clamp(saturation), luma(col), col.a),
+        }
+    }
+}
+
+impl Default for Adjustment {
+    fn default() -> Self {
+        Adjustment::VARIANTS[0]
+    }
+}
+
+fn map_channels<F: Fn(f64) -> f64>(col: XpriteRgba, f: F) -> XpriteRgba {
+    let channel = |c: u8| (f(f64::from(c) / 255.) * 255.).round().max(0.).min(255.) as u8;
+    XpriteRgba {
+        r: channel(col.r),
+        g: channel(col.g),
+        b: channel(col.b),
+        a: col.a,
+    }
+}
+
+/// rec. 709 luma in [0, 1]
+fn luma(col: XpriteRgba) -> f64 {
+    (0.2126 * f64::from(col.r) + 0.7152 * f64::from(col.g) + 0.0722 * f64::from(col.b)) / 255.
+}
+
+/// adjusted copies of `pixs` as rgba, None if a color cannot be resolved
+pub fn adjust_pixels(pixs: &Pixels, adj: Adjustment, xpr: Option<&Xprite>) -> Option<Pixels> {
+    pixs.iter()
+        .map(|p| {
+            let col = p.color.to_rgba(xpr)?;
+            Some(Pixel {
+                color: Color::Rgba(adj.apply(col)),
+                ..*p
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_adjustments() {
+        use super::*;
+        let col = XpriteRgba { r: 200, g: 100, b: 50, a: 128 };
+        for adj in Adjustment::VARIANTS.iter() {
+            assert_eq!(adj.apply(col).a, 128);
+        }
+        let identity = [Adjustment::VARIANTS[0], Adjustment::VARIANTS[1], Adjustment::VARIANTS[2]];
+        for adj in identity.iter() {
+            let out = adj.apply(col);
+            assert!((i16::from(out.r) - 200).abs() <= 1 && (i16::from(out.g) - 100).abs() <= 1 && (i16::from(out.b) - 50).abs() <= 1);
+        }
+
+        let inv = Adjustment::Invert.apply(col);
+        assert_eq!((inv.r, inv.g, inv.b, inv.a), (55, 155, 205, 128));
+
+        let grey = Adjustment::Desaturate.apply(col);
+        assert_eq!(grey.r, grey.g);
+        assert_eq!(grey.g, grey.b);
+
+        let post = Adjustment::Posterize { levels: 2 }.apply(col);
+        assert_eq!((post.r, post.g, post.b), (255, 0, 0));
+
+        let shifted = Adjustment::Hsv {
+            hue: 120.,
+            saturation: 0.,
+            value: 0.,
+        }
+        .apply(XpriteRgba::red());
+        assert_eq!((shifted.r, shifted.g, shifted.b), (0, 255, 0));
+    }
+
+    #[test]
+    fn test_adjust_pixels() {
+        use super::*;
+        let pixs = pixels!(pixel!(0, 0, Color::rgba(0, 0, 0, 255)), pixel!(0, 1, Color::rgba(255, 255, 255, 255)));
+        let out = adjust_pixels(&pixs, Adjustment::Invert, None).unwrap();
+        assert_eq!(out.get_pixel(0, 0).unwrap().color, Color::rgba(255, 255, 255, 255));
+        assert_eq!(out.get_pixel(0, 1).unwrap().color, Color::rgba(0, 0, 0, 255));
+        assert!(adjust_pixels(&pixels!(pixel!(0, 0, Color::Indexed(0))), Adjustment::Invert, None).is_none());
+    }
+}
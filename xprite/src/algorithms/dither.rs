@@ -0,0 +1,189 @@
+//! Dither patterns painted through by tools
+//!
+//! Patterns are anchored to canvas coordinates, so separate strokes with the
+//! same pattern line up.
+use crate::algorithms::quantize::bayer;
+use crate::prelude::*;
+use std::str::FromStr;
+
+/// size of the ordered dither matrix used by `DitherPattern::Bayer`
+pub const BAYER_SIZE: usize = 4;
+
+/// largest width and height of a custom tile
+pub const MAX_TILE_SIZE: usize = 16;
+
+/// ordered dither threshold of the pixel at canvas position `x`, `y`,
+/// from 0 up to `BAYER_SIZE * BAYER_SIZE`
+pub fn bayer_threshold(x: isize, y: isize) -> usize {
+    let n = BAYER_SIZE as isize;
+    bayer(BAYER_SIZE, x.rem_euclid(n) as usize, y.rem_euclid(n) as usize)
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum DitherPattern {
+    /// every pixel
+    Solid,
+    /// ordered dither, paints `level` of every 16 pixels
+    Bayer(u8),
+    Checker,
+    HorizontalLines,
+    VerticalLines,
+    DiagonalLines,
+    /// tile repeated over the canvas, indexed by row then column
+    Custom(Vec<Vec<bool>>),
+}
+
+impl DitherPattern {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DitherPattern::Solid => "Solid",
+            DitherPattern::Bayer(_) => "Bayer",
+            DitherPattern::Checker => "Checker",
+            DitherPattern::HorizontalLines => "Horizontal lines",
+            DitherPattern::VerticalLines => "Vertical lines",
+            DitherPattern::DiagonalLines => "Diagonal lines",
+            DitherPattern::Custom(_) => "Custom",
+        }
+    }
+
+    /// every kind of pattern, `Custom` with an empty tile
+    pub const VARIANTS: [DitherPattern; 7] = [
+        DitherPattern::Solid,
+        DitherPattern::Bayer(8),
+        DitherPattern::Checker,
+        DitherPattern::HorizontalLines,
+        DitherPattern::VerticalLines,
+        DitherPattern::DiagonalLines,
+        DitherPattern::Custom(Vec::new()),
+    ];
+
+    /// 4x4 tile a new custom pattern starts from
+    pub fn default_tile() -> Vec<Vec<bool>> {
+        (0..4).map(|y| (0..4).map(|x| x == y).collect()).collect()
+    }
+
+    /// Resizes a custom tile to `w` by `h`, clamped to 1..=`MAX_TILE_SIZE`.
+    /// Cells inside both sizes are kept, new cells are not painted.
+    pub fn resize_tile(tile: &mut Vec<Vec<bool>>, w: usize, h: usize) {
+        let w = w.max(1).min(MAX_TILE_SIZE);
+        let h = h.max(1).min(MAX_TILE_SIZE);
+        tile.resize(h, vec![]);
+        for row in tile.iter_mut() {
+            row.resize(w, false);
+        }
+    }
+
+    /// whether the pixel at canvas position `x`, `y` is painted
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        match self {
+            DitherPattern::Solid => true,
+            DitherPattern::Bayer(level) => bayer_threshold(x, y) < usize::from(*level),
+            DitherPattern::Checker => (x + y).rem_euclid(2) == 0,
+            DitherPattern::HorizontalLines => y.rem_euclid(2) == 0,
+            DitherPattern::VerticalLines => x.rem_euclid(2) == 0,
+            DitherPattern::DiagonalLines => (x + y).rem_euclid(4) == 0,
+            DitherPattern::Custom(tile) => {
+                if tile.is_empty() || tile[0].is_empty() {
+                    return true;
+                }
+                let row = &tile[y.rem_euclid(tile.len() as isize) as usize];
+                row.get(x.rem_euclid(tile[0].len() as isize) as usize).cloned().unwrap_or(false)
+            }
+        }
+    }
+
+    /// pixels of `pixs` that fall on the pattern
+    pub fn mask(&self, pixs: &Pixels) -> Pixels {
+        if *self == DitherPattern::Solid {
+            return pixs.clone();
+        }
+        pixs.iter()
+            .filter(|p| self.contains(p.point.x.floor() as isize, p.point.y.floor() as isize))
+            .cloned()
+            .collect()
+    }
+}
+
+impl FromStr for DitherPattern {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Solid" => Ok(DitherPattern::Solid),
+            "Bayer" => Ok(DitherPattern::Bayer(8)),
+            "Checker" => Ok(DitherPattern::Checker),
+            "Horizontal lines" => Ok(DitherPattern::HorizontalLines),
+            "Vertical lines" => Ok(DitherPattern::VerticalLines),
+            "Diagonal lines" => Ok(DitherPattern::DiagonalLines),
+            "Custom" => Ok(DitherPattern::Custom(DitherPattern::default_tile())),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for DitherPattern {
+    fn default() -> Self {
+        DitherPattern::Solid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_bayer_levels() {
+        use super::*;
+        for level in 0..=16 {
+            let pattern = DitherPattern::Bayer(level);
+            // any 4x4 window holds every threshold once
+            let count = (3..7)
+                .flat_map(|y| (-2..2).map(move |x| (x, y)))
+                .filter(|&(x, y)| pattern.contains(x, y))
+                .count();
+            assert_eq!(count, usize::from(level));
+        }
+        // higher levels only add pixels
+        for (x, y) in (0..4).flat_map(|y| (0..4).map(move |x| (x, y))) {
+            assert!(!DitherPattern::Bayer(5).contains(x, y) || DitherPattern::Bayer(6).contains(x, y));
+        }
+    }
+
+    #[test]
+    fn test_mask_is_anchored() {
+        use super::*;
+        let stroke: Pixels = (-3..3).map(|x| pixel!(1, x, Color::red())).collect();
+        let checker = DitherPattern::Checker.mask(&stroke);
+        assert_eq!(checker.len(), 3);
+        assert!(checker.iter().all(|p| (p.point.x as isize + 1).rem_euclid(2) == 0));
+        // a shifted stroke keeps the canvas alignment
+        let shifted = DitherPattern::Checker.mask(&stroke.shifted(vec2f!(0, 1)));
+        assert_eq!(shifted.len(), 3);
+        assert!(shifted.iter().all(|p| (p.point.x as isize + 1).rem_euclid(2) == 0));
+
+        assert_eq!(DitherPattern::Solid.mask(&stroke).len(), 6);
+        assert_eq!(DitherPattern::HorizontalLines.mask(&stroke).len(), 0);
+    }
+
+    #[test]
+    fn test_custom_tile() {
+        use super::*;
+        let pattern = DitherPattern::Custom(vec![vec![true, false, false], vec![false, false, true]]);
+        assert!(pattern.contains(0, 0));
+        assert!(pattern.contains(3, 2));
+        assert!(pattern.contains(-1, -1));
+        assert!(!pattern.contains(1, 0));
+        assert!(DitherPattern::Custom(vec![]).contains(5, 5));
+        assert_eq!("Custom".parse(), Ok(DitherPattern::Custom(DitherPattern::default_tile())));
+    }
+
+    #[test]
+    fn test_resize_tile() {
+        use super::*;
+        let mut tile = DitherPattern::default_tile();
+        DitherPattern::resize_tile(&mut tile, 2, 6);
+        assert_eq!(tile.len(), 6);
+        assert!(tile.iter().all(|row| row.len() == 2));
+        assert!(tile[1][1] && !tile[1][0] && !tile[5][1]);
+        DitherPattern::resize_tile(&mut tile, 0, 100);
+        assert_eq!((tile[0].len(), tile.len()), (1, MAX_TILE_SIZE));
+        assert!(tile[0][0]);
+    }
+}
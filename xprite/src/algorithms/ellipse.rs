@@ -1,5 +1,6 @@
-use crate::algorithms::line::pixel_perfect_line;
+use crate::algorithms::line::{continuous_line, pixel_perfect_line};
 use crate::prelude::*;
+use std::collections::BTreeMap;
 use std::i32;
 
 pub fn get_ellipse(start: Option<Vec2f>, stop: Option<Vec2f>, filled: bool, color: Color) -> Result<Pixels, String> {
@@ -22,6 +23,18 @@ pub fn get_ellipse(start: Option<Vec2f>, stop: Option<Vec2f>, filled: bool, colo
     Ok(ret)
 }
 
+/// ellipse spanned by `start` and `stop` turned `angle` radians about its center
+pub fn get_rotated_ellipse(start: Option<Vec2f>, stop: Option<Vec2f>, angle: f64, filled: bool, color: Color) -> Result<Pixels, String> {
+    let start = start.ok_or_else(|| "start is none".to_owned())?;
+    let stop = stop.ok_or_else(|| "stop is none".to_owned())?;
+    let (x0, y0, x1, y1) = (start.x as i32, start.y as i32, stop.x as i32, stop.y as i32);
+    if (x1 - x0).abs() < 1 || (y1 - y0).abs() < 1 {
+        return Err("ellipse needs a width and a height of at least one pixel".to_owned());
+    }
+    let (left, top, right, bottom) = (i32::min(x0, x1), i32::min(y0, y1), i32::max(x0, x1), i32::max(y0, y1));
+    Ok(algo_rotated_ellipse(left, top, right, bottom, angle, filled, color))
+}
+
 fn bresenham_ellipse_error(rx: i32, ry: i32, x: i32, y: i32) -> i32 {
     x * x * ry * ry + y * y * rx * rx - rx * rx * ry * ry
 }
@@ -256,3 +269,111 @@ pub fn algo_ellipsefill(x1: i32, y1: i32, x2: i32, y2: i32, color: Color) -> Pix
 
     ret
 }
+
+/// Ellipse fitted into the box from (x1, y1) to (x2, y2), then turned `angle`
+/// radians counter-clockwise about its center. Half of the outline is traced
+/// and the other half is its mirror through the center, so the shape stays
+/// symmetric at any angle.
+pub fn algo_rotated_ellipse(x1: i32, y1: i32, x2: i32, y2: i32, angle: f64, filled: bool, color: Color) -> Pixels {
+    let (cx, cy) = (f64::from(x1 + x2) / 2., f64::from(y1 + y2) / 2.);
+    let (a, b) = (f64::from((x2 - x1).abs()) / 2., f64::from((y2 - y1).abs()) / 2.);
+    let (sin, cos) = angle.sin_cos();
+    let at = |t: f64| {
+        let (u, v) = (a * t.cos(), b * t.sin());
+        // adding 0 turns -0 into 0 so the same pixel always hashes the same
+        vec2f_xy!((cx + u * cos - v * sin).round() + 0., (cy - u * sin - v * cos).round() + 0.)
+    };
+    let mirror = |p: Vec2f| vec2f_xy!(f64::from(x1 + x2) - p.x, f64::from(y1 + y2) - p.y);
+
+    // samples less than a pixel apart, joined by lines in case rounding skips one
+    let n = ((a.max(b) + 1.) * 2. * PI).ceil() as usize;
+    let mut half = Pixels::new();
+    let mut prev = at(0.);
+    half.push(pixel!(prev, color));
+    for i in 1..=n {
+        let p = if i == n { mirror(at(0.)) } else { at(PI * i as f64 / n as f64) };
+        if p != prev {
+            half.extend(&continuous_line(prev, p, color));
+            prev = p;
+        }
+    }
+    half.pixel_perfect();
+
+    let len = half.len();
+    let mut ret = half.clone();
+    for p in half.iter() {
+        ret.push(pixel!(mirror(p.point), color));
+    }
+    if len > 3 {
+        // where the halves meet the seam pixel may be a doubled corner or a spike
+        let (next, before) = (half[1].point, mirror(half[len - 2].point));
+        if (next.x - before.x).abs() <= 1. && (next.y - before.y).abs() <= 1. {
+            ret.0.swap_remove(&half[0]);
+            ret.0.swap_remove(&half[len - 1]);
+        }
+    }
+
+    if filled {
+        // the outline is convex, fill each row between its ends
+        let mut rows: BTreeMap<i64, (f64, f64)> = BTreeMap::new();
+        for p in ret.iter() {
+            let row = rows.entry(p.point.y as i64).or_insert((p.point.x, p.point.x));
+            *row = (row.0.min(p.point.x), row.1.max(p.point.x));
+        }
+        for (y, (from, to)) in rows {
+            for x in from as i64..=to as i64 {
+                ret.push(pixel!(y as f64, x as f64, color));
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_rotated_ellipse() {
+        use super::*;
+        for &(x2, y2) in &[(20, 8), (21, 8), (15, 0)] {
+            for i in 0..12 {
+                let angle = f64::from(i) * PI / 12.;
+                let outline = algo_rotated_ellipse(0, 0, x2, y2, angle, false, Color::red());
+                let filled = algo_rotated_ellipse(0, 0, x2, y2, angle, true, Color::red());
+                assert!(outline.iter().all(|p| filled.0.contains(p)));
+                for p in outline.iter() {
+                    // mirrored through the center
+                    let mirror = pixel!(f64::from(y2) - p.point.y, f64::from(x2) - p.point.x, Color::red());
+                    assert!(outline.0.contains(&mirror));
+                }
+                // the outline is one connected loop
+                let mut seen = vec![*outline.iter().next().unwrap()];
+                let mut i = 0;
+                while i < seen.len() {
+                    let p = seen[i].point;
+                    for q in outline.iter() {
+                        if (q.point.x - p.x).abs() <= 1. && (q.point.y - p.y).abs() <= 1. && !seen.contains(q) {
+                            seen.push(*q);
+                        }
+                    }
+                    i += 1;
+                }
+                assert_eq!(seen.len(), outline.len());
+                // no doubled corners
+                if y2 > 0 {
+                    let has = |x: f64, y: f64| outline.0.contains(&pixel!(y, x, Color::red()));
+                    for p in outline.iter() {
+                        let Vec2f { x, y } = p.point;
+                        let corner = (has(x - 1., y) || has(x + 1., y)) && (has(x, y - 1.) || has(x, y + 1.));
+                        assert!(!corner, "{:?} at {}", p.point, angle);
+                    }
+                }
+            }
+        }
+        // turning by a quarter swaps the axes
+        let wide = algo_rotated_ellipse(0, 0, 20, 8, PI / 2., true, Color::red());
+        let tall = algo_rotated_ellipse(6, -6, 14, 14, 0., true, Color::red());
+        assert_eq!(wide.len(), tall.len());
+
+        assert!(get_rotated_ellipse(Some(vec2f!(0, 0)), Some(vec2f!(0, 10)), 1., false, Color::red()).is_err());
+    }
+}
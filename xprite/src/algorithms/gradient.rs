@@ -0,0 +1,163 @@
+//! Banded gradients
+//!
+//! A gradient paints a list of colors as discrete bands between a start and
+//! an end point. Neighbouring bands can be blended with ordered dithering, so
+//! the result only ever uses the given colors.
+use crate::algorithms::dither::{bayer_threshold, BAYER_SIZE};
+use crate::prelude::*;
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum GradientShape {
+    /// bands across the line from start to end
+    Linear,
+    /// rings around the start, the last one through the end
+    Radial,
+    /// wedges around the start, beginning toward the end
+    Angular,
+}
+
+impl GradientShape {
+    pub fn as_str(&self) -> &str {
+        match self {
+            GradientShape::Linear => "Linear",
+            GradientShape::Radial => "Radial",
+            GradientShape::Angular => "Angular",
+        }
+    }
+
+    pub const VARIANTS: [GradientShape; 3] = [GradientShape::Linear, GradientShape::Radial, GradientShape::Angular];
+}
+
+impl FromStr for GradientShape {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Linear" => Ok(GradientShape::Linear),
+            "Radial" => Ok(GradientShape::Radial),
+            "Angular" => Ok(GradientShape::Angular),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for GradientShape {
+    fn default() -> Self {
+        GradientShape::Linear
+    }
+}
+
+/// position of `p` along the gradient from `start` to `end`, in [0, 1]
+pub fn gradient_position(shape: GradientShape, start: Vec2f, end: Vec2f, p: Vec2f) -> f64 {
+    let d = end - start;
+    let len2 = d.x * d.x + d.y * d.y;
+    if len2 == 0. {
+        return 0.;
+    }
+    let v = p - start;
+    let t = match shape {
+        GradientShape::Linear => (v.x * d.x + v.y * d.y) / len2,
+        GradientShape::Radial => ((v.x * v.x + v.y * v.y) / len2).sqrt(),
+        GradientShape::Angular => {
+            if v.x == 0. && v.y == 0. {
+                return 0.;
+            }
+            (v.y.atan2(v.x) - d.y.atan2(d.x)).rem_euclid(2. * PI) / (2. * PI)
+        }
+    };
+    t.max(0.).min(1.)
+}
+
+/// Index into `n` bands at position `t` for the pixel at `x`, `y`. Bands are
+/// equally wide, `dither` is the share of each band blended into its
+/// neighbours, hard edges at 0.
+pub fn gradient_band(t: f64, n: usize, dither: f64, x: isize, y: isize) -> usize {
+    if n < 2 {
+        return 0;
+    }
+    // band centers sit on whole numbers
+    let u = (t * n as f64 - 0.5).max(0.).min((n - 1) as f64);
+    let lo = (u.floor() as usize).min(n - 2);
+    let frac = u - lo as f64;
+    let dither = dither.max(0.).min(1.);
+    let level = if dither == 0. {
+        if frac < 0.5 {
+            0.
+        } else {
+            1.
+        }
+    } else {
+        ((frac - 0.5) / dither + 0.5).max(0.).min(1.)
+    };
+    let threshold = bayer_threshold(x, y) as f64 + 0.5;
+    if threshold < level * (BAYER_SIZE * BAYER_SIZE) as f64 {
+        lo + 1
+    } else {
+        lo
+    }
+}
+
+/// `region` recolored with the bands of `colors` from `start` to `end`
+pub fn gradient(region: &Pixels, shape: GradientShape, start: Vec2f, end: Vec2f, colors: &[Color], dither: f64) -> Pixels {
+    if colors.is_empty() {
+        return Pixels::new();
+    }
+    region
+        .iter()
+        .map(|p| {
+            let t = gradient_position(shape, start, end, p.point);
+            let band = gradient_band(t, colors.len(), dither, p.point.x as isize, p.point.y as isize);
+            Pixel { color: colors[band], ..*p }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_gradient_position() {
+        use super::*;
+        let (start, end) = (vec2f!(0, 0), vec2f!(0, 10));
+        let linear = |p| gradient_position(GradientShape::Linear, start, end, p);
+        assert_eq!(linear(vec2f!(0, 5)), 0.5);
+        assert_eq!(linear(vec2f!(7, 5)), 0.5);
+        assert_eq!(linear(vec2f!(0, -3)), 0.);
+        assert_eq!(linear(vec2f!(0, 30)), 1.);
+        let radial = |p| gradient_position(GradientShape::Radial, start, end, p);
+        assert_eq!(radial(vec2f!(-5, 0)), 0.5);
+        assert_eq!(radial(vec2f!(0, 20)), 1.);
+        let angular = |p| gradient_position(GradientShape::Angular, start, end, p);
+        assert_eq!(angular(vec2f!(0, 4)), 0.);
+        assert_eq!(angular(vec2f!(0, -4)), 0.5);
+        // a quarter turn past the end, which is down on screen
+        assert_eq!(angular(vec2f!(4, 0)), 0.25);
+        assert_eq!(gradient_position(GradientShape::Linear, start, start, vec2f!(3, 3)), 0.);
+    }
+
+    #[test]
+    fn test_gradient_bands() {
+        use super::*;
+        let colors = [Color::Indexed(3), Color::Indexed(4), Color::Indexed(5)];
+        let region: Pixels = (0..12).flat_map(|x| (0..4).map(move |y| pixel!(y, x, Color::red()))).collect();
+        let (start, end) = (vec2f!(0, 0), vec2f!(0, 11));
+        let hard = gradient(&region, GradientShape::Linear, start, end, &colors, 0.);
+        assert_eq!(hard.len(), region.len());
+        // three equal bands of four columns
+        for p in hard.iter() {
+            let band = (p.point.x / 4.).floor() as usize;
+            assert_eq!(p.color, colors[band], "{:?}", p.point);
+        }
+        // dithered bands only use the given colors and keep solid centers
+        let soft = gradient(&region, GradientShape::Linear, start, end, &colors, 1.);
+        assert!(soft.iter().all(|p| colors.contains(&p.color)));
+        assert!(soft.iter().filter(|p| p.point.x == 0.).all(|p| p.color == colors[0]));
+        assert!(soft.iter().filter(|p| p.point.x == 11.).all(|p| p.color == colors[2]));
+        let mixed = soft.iter().filter(|p| p.point.x == 4.).map(|p| p.color).collect::<Vec<_>>();
+        assert!(mixed.contains(&colors[0]) && mixed.contains(&colors[1]));
+        // the share of the next band grows along the gradient
+        let count = |x: f64| soft.iter().filter(|p| p.point.x == x && p.color == colors[1]).count();
+        assert!((0..5).all(|x| count(f64::from(x)) <= count(f64::from(x + 1))));
+        assert!(gradient(&region, GradientShape::Linear, start, end, &[], 1.).is_empty());
+    }
+}
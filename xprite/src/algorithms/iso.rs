@@ -0,0 +1,153 @@
+//! Isometric (2:1) tiles
+//!
+//! A tile `w` pixels wide is `w / 2` tall. Tiles sit on a lattice of half
+//! tiles: the bounding box of a tile starts at `(i * w / 2, j * w / 4)` with
+//! `i + j` even.
+use crate::prelude::*;
+
+/// width of the smallest usable tile, tile widths are multiples of it
+pub const MIN_TILE_W: u32 = 4;
+
+/// `w` rounded to a usable tile width
+pub fn tile_width(w: u32) -> u32 {
+    ((w + MIN_TILE_W / 2) / MIN_TILE_W).max(1) * MIN_TILE_W
+}
+
+/// grid lines of tiles `tile_w` wide clipped to a `w` by `h` image
+pub fn iso_grid_lines(w: f64, h: f64, tile_w: f64) -> Vec<(Vec2f, Vec2f)> {
+    let tile_h = tile_w / 2.;
+    let mut ret = vec![];
+    if tile_h <= 0. {
+        return ret;
+    }
+    let first = (-w / 2. / tile_h).floor() as i64 - 1;
+    let last = ((h + w / 2.) / tile_h).ceil() as i64 + 1;
+    for &slope in &[0.5, -0.5] {
+        for k in first..=last {
+            // y = slope * x + c, through the left and right corners of the tiles
+            let c = (k as f64 + 0.5) * tile_h;
+            let (xa, xb) = ((0. - c) / slope, (h - c) / slope);
+            let lo = xa.min(xb).max(0.);
+            let hi = xa.max(xb).min(w);
+            if lo < hi {
+                ret.push((vec2f_xy!(lo, slope * lo + c), vec2f_xy!(hi, slope * hi + c)));
+            }
+        }
+    }
+    ret
+}
+
+/// `p` moved to the closest bounding box corner of a tile
+pub fn snap_to_tile(p: Vec2f, tile_w: u32) -> Vec2f {
+    let half_w = f64::from(tile_w) / 2.;
+    let half_h = f64::from(tile_w) / 4.;
+    let j = (p.y / half_h).round();
+    let i = ((p.x / half_w - j) / 2.).round() * 2. + j;
+    vec2f_xy!(i * half_w, j * half_h)
+}
+
+/// Pixels of the three visible faces of a box, as `[top, left, right]`. The
+/// box is `4 * n` pixels wide, the top face is `2 * n` tall and the sides
+/// `h` tall. Edges run 2:1 and the two halves mirror each other.
+pub fn iso_box_faces(origin: Vec2f, n: usize, h: usize) -> [Pixels; 3] {
+    let mut faces = [Pixels::new(), Pixels::new(), Pixels::new()];
+    for (c, top, side) in iso_box_columns(n, h) {
+        for (i, x) in mirrored(n, c).iter().enumerate() {
+            for y in top.clone() {
+                faces[0].push(pixel!(origin.y + y as f64, origin.x + *x as f64, Color::red()));
+            }
+            for y in side.clone() {
+                faces[1 + i].push(pixel!(origin.y + y as f64, origin.x + *x as f64, Color::red()));
+            }
+        }
+    }
+    faces
+}
+
+/// outline of the box in [`iso_box_faces`] and the edges between its faces
+pub fn iso_box_edges(origin: Vec2f, n: usize, h: usize, color: Color) -> Pixels {
+    let mut ret = Pixels::new();
+    for (c, top, side) in iso_box_columns(n, h) {
+        let mut rows = vec![*top.start(), *top.end()];
+        if h > 0 {
+            if c == 0 || c == 2 * n - 1 {
+                // the outer and the front vertical edges
+                rows.extend(side);
+            } else {
+                rows.push(*side.end());
+            }
+        }
+        for x in mirrored(n, c).iter() {
+            for &y in &rows {
+                ret.push(pixel!(origin.y + y as f64, origin.x + *x as f64, color));
+            }
+        }
+    }
+    ret
+}
+
+/// rows of the top face and of the side below it in each column of the left half
+fn iso_box_columns(n: usize, h: usize) -> Vec<(usize, std::ops::RangeInclusive<usize>, std::ops::RangeInclusive<usize>)> {
+    (0..2 * n)
+        .map(|c| {
+            let k = c / 2;
+            (c, (n - 1 - k)..=(n + k), (n + k + 1)..=(n + k + h))
+        })
+        .collect()
+}
+
+/// column `c` of the left half and its mirror in the right half
+fn mirrored(n: usize, c: usize) -> [usize; 2] {
+    [c, 4 * n - 1 - c]
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_iso_box() {
+        use super::*;
+        let origin = vec2f!(10, 20);
+        let [top, left, right] = iso_box_faces(origin, 2, 3);
+        // a 2:1 diamond, 8 wide and 4 tall
+        assert_eq!(top.len(), 2 * (2 + 2 + 4 + 4));
+        assert_eq!(left.len(), 4 * 3);
+        assert_eq!(right.len(), 4 * 3);
+        assert!(top.get_pixel(10, 22).is_some());
+        assert!(top.get_pixel(10, 25).is_some());
+        assert!(top.get_pixel(10, 21).is_none());
+        assert!(top.get_pixel(11, 20).is_some());
+        // the sides mirror each other
+        for p in left.iter() {
+            assert!(right.get_pixel(p.point.y as isize, 47 - p.point.x as isize).is_some());
+        }
+        let edges = iso_box_edges(origin, 2, 3, Color::red());
+        let all = top.len() + left.len() + right.len();
+        assert!(edges.len() < all);
+        // a flat tile has no sides
+        let [_, left, right] = iso_box_faces(origin, 2, 0);
+        assert!(left.is_empty() && right.is_empty());
+    }
+
+    #[test]
+    fn test_iso_grid() {
+        use super::*;
+        assert_eq!(tile_width(0), 4);
+        assert_eq!(tile_width(31), 32);
+        assert_eq!(snap_to_tile(vec2f_xy!(17, 1), 32), vec2f_xy!(32, 0));
+        // tiles in odd rows are offset by half a tile
+        assert_eq!(snap_to_tile(vec2f_xy!(14, 9), 32), vec2f_xy!(16, 8));
+        assert_eq!(snap_to_tile(vec2f_xy!(40, 9), 32), vec2f_xy!(48, 8));
+
+        let lines = iso_grid_lines(64., 32., 32.);
+        for (a, b) in &lines {
+            assert!(a.x >= 0. && b.x <= 64. && a.y >= 0. && a.y <= 32. && b.y >= 0. && b.y <= 32.);
+            assert_eq!((b.y - a.y).abs() * 2., b.x - a.x);
+        }
+        // through the corners of the tile at the origin
+        let through = |p: Vec2f| lines.iter().any(|(a, b)| ((p.y - a.y) * (b.x - a.x) - (p.x - a.x) * (b.y - a.y)).abs() < 1e-9);
+        assert!(through(vec2f_xy!(0, 8)));
+        assert!(through(vec2f_xy!(16, 0)));
+        assert!(through(vec2f_xy!(32, 8)));
+        assert!(through(vec2f_xy!(16, 16)));
+    }
+}
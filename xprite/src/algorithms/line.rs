@@ -232,6 +232,72 @@ pub fn continuous_line(start: Vec2f, stop: Vec2f, color: Color) -> Pixels {
     ret
 }
 
+/// `stop` moved onto the closest ray from `start` at a multiple of `step` radians
+pub fn snap_angle(start: Vec2f, stop: Vec2f, step: f64) -> Vec2f {
+    let d = stop - start;
+    let theta = (f64::atan2(d.y, d.x) / step).round() * step;
+    let len = d.mag();
+    vec2f_xy!(start.x + (len * theta.cos()).round(), start.y + (len * theta.sin()).round())
+}
+
+/// `stop` moved onto the closest line from `start` made of whole steps of
+/// `a` pixels along one axis for `b` along the other, for every `(a, b)` in
+/// `ratios`. Horizontal and vertical lines are always allowed.
+pub fn snap_ratio(start: Vec2f, stop: Vec2f, ratios: &[(u32, u32)]) -> Vec2f {
+    let (step, n) = snap_to_steps(start, stop, &ratio_steps(ratios));
+    start + step * n as f64
+}
+
+/// both orientations of every ratio, and the two axes
+pub fn ratio_steps(ratios: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut steps = vec![(1, 0), (0, 1)];
+    for &(a, b) in ratios {
+        steps.push((a, b));
+        steps.push((b, a));
+    }
+    steps
+}
+
+/// The `(x, y)` step out of `steps`, mirrored into any direction, and the
+/// number of whole steps from `start` that land closest to `stop`.
+pub fn snap_to_steps(start: Vec2f, stop: Vec2f, steps: &[(u32, u32)]) -> (Vec2f, usize) {
+    let d = stop - start;
+    let mut best = (f64::INFINITY, vec2f_xy!(1, 0), 0);
+    for &(a, b) in steps {
+        for &(sx, sy) in &[(1., 1.), (1., -1.), (-1., 1.), (-1., -1.)] {
+            let step = vec2f_xy!(f64::from(a) * sx, f64::from(b) * sy);
+            let len2 = step.x * step.x + step.y * step.y;
+            if len2 == 0. {
+                continue;
+            }
+            let n = ((d.x * step.x + d.y * step.y) / len2).round().max(0.);
+            let dist = (step * n - d).mag();
+            if dist < best.0 {
+                best = (dist, step, n as usize);
+            }
+        }
+    }
+    (best.1, best.2)
+}
+
+/// `n` repetitions of `step` from `start`, each drawn the same way so the runs
+/// of pixels stay even, followed by the end point `start + step * n`.
+pub fn stepped_line(start: Vec2f, step: Vec2f, n: usize, color: Color) -> Pixels {
+    let mut ret = Pixels::new();
+    let (a, b) = (step.x.abs() as i64, step.y.abs() as i64);
+    let (sx, sy) = (step.x.signum() as i64, step.y.signum() as i64);
+    let len = a.max(b);
+    for k in 0..n {
+        let origin = start + step * k as f64;
+        for i in 0..len {
+            let (x, y) = if a >= b { (i, i * b / a) } else { (i * a / b, i) };
+            ret.push(pixel!(origin.y + (sy * y) as f64, origin.x + (sx * x) as f64, color));
+        }
+    }
+    ret.push(pixel!(start + step * n as f64, color));
+    ret
+}
+
 #[deprecated]
 pub fn bresenham(start: Vec2f, stop: Vec2f, color: Color) -> Pixels {
     let mut ret = Pixels::new();
@@ -283,4 +349,45 @@ mod tests {
         ];
         assert_eq!(expected, adjusted_end);
     }
+
+    #[test]
+    fn test_snap_angle() {
+        let start = vec2f!(0, 0);
+        assert_eq!(snap_angle(start, vec2f!(9, 10), PI / 4.), vec2f!(10, 10));
+        assert_eq!(snap_angle(start, vec2f!(1, 10), PI / 4.), vec2f!(0, 10));
+        assert_eq!(snap_angle(start, vec2f!(-6, 10), PI / 12.), vec2f!(-6, 10));
+        assert_eq!(snap_angle(start, vec2f!(-5, 10), PI / 12.), vec2f!(-6, 10));
+    }
+
+    #[test]
+    fn test_snap_ratio() {
+        let start = vec2f!(0, 0);
+        let ratios = [(1, 1), (1, 2), (1, 3)];
+        // whole steps of the closest ratio
+        assert_eq!(snap_ratio(start, vec2f!(5, 11), &ratios), vec2f!(5, 10));
+        assert_eq!(snap_ratio(start, vec2f!(-4, 13), &ratios), vec2f!(-4, 12));
+        assert_eq!(snap_ratio(start, vec2f!(-1, 9), &ratios), vec2f!(0, 9));
+        assert_eq!(snap_ratio(start, vec2f!(-8, -7), &ratios), vec2f!(-8, -8));
+        assert_eq!(snap_ratio(start, vec2f!(-8, -7), &[]), vec2f!(-8, 0));
+    }
+
+    #[test]
+    fn test_stepped_line() {
+        // runs of two for 2:1
+        let line = stepped_line(vec2f!(0, 0), vec2f_xy!(2, 1), 3, Color::red());
+        let xs: Vec<_> = line.iter().map(|p| (p.point.x as i32, p.point.y as i32)).collect();
+        assert_eq!(xs, vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 2), (5, 2), (6, 3)]);
+        // mirrored steps keep the same runs
+        let line = stepped_line(vec2f!(0, 0), vec2f_xy!(-1, -3), 2, Color::red());
+        let xs: Vec<_> = line.iter().map(|p| (p.point.x as i32, p.point.y as i32)).collect();
+        assert_eq!(xs, vec![(0, 0), (0, -1), (0, -2), (-1, -3), (-1, -4), (-1, -5), (-2, -6)]);
+        // 3:2 repeats a run of two and a run of one
+        let line = stepped_line(vec2f!(0, 0), vec2f_xy!(3, 2), 2, Color::red());
+        let ys: Vec<_> = line.iter().map(|p| p.point.y as i32).collect();
+        assert_eq!(ys, vec![0, 0, 1, 2, 2, 3, 4]);
+        assert_eq!(stepped_line(vec2f!(1, 1), vec2f_xy!(1, 0), 0, Color::red()).len(), 1);
+
+        assert_eq!(snap_to_steps(vec2f!(0, 0), vec2f!(5, 11), &[(2, 1), (0, 1)]), (vec2f_xy!(2, 1), 5));
+        assert_eq!(snap_to_steps(vec2f!(0, 0), vec2f!(-9, 1), &[(2, 1), (0, 1)]), (vec2f_xy!(0, -1), 9));
+    }
 }
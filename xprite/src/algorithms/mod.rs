@@ -1,15 +1,27 @@
+pub mod adjust;
 pub mod autoshade;
 pub mod connected_components;
+pub mod dither;
 pub mod ellipse;
 pub mod floodfill;
+pub mod gradient;
+pub mod iso;
 pub mod line;
 pub mod path;
 pub mod perimeter;
 pub mod pixel_perfect;
 pub mod polygon;
 pub mod polyline;
+pub mod quantize;
 pub mod rect;
+pub mod replace;
 pub mod rotsprite;
 pub mod selective_antialias;
+pub mod shapes;
 pub mod sorter;
+pub mod spray;
+pub mod stabilizer;
+pub mod stroke;
 pub mod symmetry;
+pub mod upscale;
+pub mod vision;
@@ -1,5 +1,38 @@
+use crate::algorithms::line::continuous_line;
 use crate::prelude::*;
 
+/// segments between consecutive `points`, back to the first one if `closed`
+pub fn polygon_edges(points: &[Vec2f], closed: bool) -> Vec<(Vec2f, Vec2f)> {
+    let mut edges: Vec<_> = points.windows(2).map(|edge| (edge[0], edge[1])).collect();
+    if closed && points.len() > 2 {
+        edges.push((points[points.len() - 1], points[0]));
+    }
+    edges
+}
+
+/// outline through `points`, back to the first one if `closed`
+pub fn polygon_outline(points: &[Vec2f], closed: bool, color: Color) -> Pixels {
+    let mut ret = Pixels::new();
+    if let Some(&first) = points.first() {
+        ret.push(pixel!(first, color));
+    }
+    for (from, to) in polygon_edges(points, closed) {
+        ret.extend(&continuous_line(from, to, color));
+    }
+    ret
+}
+
+/// closed polygon with its interior
+pub fn filled_polygon(points: &[Vec2f], color: Color) -> Pixels {
+    let mut ret = polygon_outline(points, true, color);
+    if points.len() > 2 {
+        let mut fill = polygon(points);
+        fill.set_color(color);
+        ret.extend(&fill);
+    }
+    ret
+}
+
 pub fn polygon(points: &[Vec2f]) -> Pixels {
     let mut ret = Pixels::new();
 
@@ -109,4 +142,23 @@ mod tests {
 
         assert_eq!(polygon(&vec![vec2f!(0, 0), vec2f!(0, 2), vec2f!(2, 2), vec2f!(2, 0),]).len(), 3 * 3);
     }
+
+    #[test]
+    fn test_polygon_outline() {
+        use super::*;
+        let square = [vec2f!(0, 0), vec2f!(0, 4), vec2f!(4, 4), vec2f!(4, 0)];
+        assert_eq!(polygon_outline(&square, true, Color::red()).len(), 16);
+        // the left edge is missing when open
+        assert_eq!(polygon_outline(&square, false, Color::red()).len(), 13);
+        assert_eq!(filled_polygon(&square, Color::blue()).len(), 25);
+        assert!(filled_polygon(&square, Color::blue()).iter().all(|p| p.color == Color::blue()));
+
+        let triangle = [vec2f!(0, 0), vec2f!(6, 6), vec2f!(6, 0)];
+        let outline = polygon_outline(&triangle, true, Color::red());
+        let filled = filled_polygon(&triangle, Color::red());
+        assert!(outline.iter().all(|p| filled.0.contains(p)));
+        assert_eq!(filled.len(), 28);
+        assert_eq!(polygon_outline(&triangle[..1], true, Color::red()).len(), 1);
+        assert!(filled_polygon(&[], Color::red()).is_empty());
+    }
 }
@@ -23,41 +23,26 @@ impl Polyline {
     /// line simplification algorithm
     pub fn reumann_witkam(&self, tol: f64) -> Result<Polyline, String> {
         if self.pos.len() < 3 {
-            return Err("polyline has fewer than 10 points".to_owned());
+            return Err("polyline has fewer than 3 points".to_owned());
         }
 
         let mut ret = Polyline::new();
-        let mut first = 0;
-        let mut second = 1;
-        let mut third = 2;
+        // the strip runs along key--key+1
+        let mut key = 0;
 
         // first point
-        ret.push(Vec2f {
-            x: self.pos[first].x,
-            y: self.pos[first].y,
-        });
+        ret.push(self.pos[key]);
 
-        for _ in 0..(self.pos.len() - 2) {
-            let dist = point_line_distance(self.pos[third], self.pos[first], self.pos[second]);
-
-            if dist <= tol {
-                third += 1;
-            } else {
-                ret.push(Vec2f {
-                    x: self.pos[third].x,
-                    y: self.pos[third].y,
-                });
-                first = second;
-                second = third;
-                third += third;
+        for i in 2..self.pos.len() {
+            let dist = point_line_distance(self.pos[i], self.pos[key], self.pos[key + 1]);
+            if dist > tol {
+                key = i - 1;
+                ret.push(self.pos[key]);
             }
         }
 
         // last point
-        ret.push(Vec2f {
-            x: self.pos[self.pos.len() - 1].x,
-            y: self.pos[self.pos.len() - 1].y,
-        });
+        ret.push(self.pos[self.pos.len() - 1]);
 
         Ok(ret)
     }
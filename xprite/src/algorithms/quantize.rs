@@ -0,0 +1,501 @@
+//! Palette extraction and color reduction
+//!
+//! Picks a small set of representative colors from an arbitrary number of
+//! pixels, and maps pixels onto such a palette, optionally dithered.
+//! Fully transparent pixels are ignored.
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const KMEANS_ITERATIONS: usize = 20;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum PaletteExtraction {
+    MedianCut,
+    KMeans,
+    Unique,
+}
+
+impl PaletteExtraction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PaletteExtraction::MedianCut => "Median cut",
+            PaletteExtraction::KMeans => "K-means",
+            PaletteExtraction::Unique => "Unique colors",
+        }
+    }
+
+    pub const VARIANTS: [PaletteExtraction; 3] = [PaletteExtraction::MedianCut, PaletteExtraction::KMeans, PaletteExtraction::Unique];
+}
+
+impl FromStr for PaletteExtraction {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Median cut" => Ok(PaletteExtraction::MedianCut),
+            "K-means" => Ok(PaletteExtraction::KMeans),
+            "Unique colors" => Ok(PaletteExtraction::Unique),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for PaletteExtraction {
+    fn default() -> Self {
+        PaletteExtraction::MedianCut
+    }
+}
+
+/// color space used to decide which palette entry is nearest
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum ColorSpace {
+    Rgb,
+    Lab,
+}
+
+impl ColorSpace {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ColorSpace::Rgb => "RGB",
+            ColorSpace::Lab => "Lab",
+        }
+    }
+
+    pub const VARIANTS: [ColorSpace; 2] = [ColorSpace::Rgb, ColorSpace::Lab];
+
+    fn coords(self, c: XpriteRgba) -> [f64; 3] {
+        match self {
+            ColorSpace::Rgb => [f64::from(c.r), f64::from(c.g), f64::from(c.b)],
+            ColorSpace::Lab => c.to_lab(),
+        }
+    }
+}
+
+impl FromStr for ColorSpace {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "RGB" => Ok(ColorSpace::Rgb),
+            "Lab" => Ok(ColorSpace::Lab),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        ColorSpace::Lab
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum Dithering {
+    None,
+    Bayer2,
+    Bayer4,
+    Bayer8,
+    FloydSteinberg,
+    Atkinson,
+}
+
+impl Dithering {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Dithering::None => "None",
+            Dithering::Bayer2 => "Bayer 2x2",
+            Dithering::Bayer4 => "Bayer 4x4",
+            Dithering::Bayer8 => "Bayer 8x8",
+            Dithering::FloydSteinberg => "Floyd-Steinberg",
+            Dithering::Atkinson => "Atkinson",
+        }
+    }
+
+    pub const VARIANTS: [Dithering; 6] = [
+        Dithering::None,
+        Dithering::Bayer2,
+        Dithering::Bayer4,
+        Dithering::Bayer8,
+        Dithering::FloydSteinberg,
+        Dithering::Atkinson,
+    ];
+
+    fn bayer_size(self) -> Option<usize> {
+        match self {
+            Dithering::Bayer2 => Some(2),
+            Dithering::Bayer4 => Some(4),
+            Dithering::Bayer8 => Some(8),
+            _ => None,
+        }
+    }
+
+    /// error diffusion kernel as (dx, dy, weight)
+    fn kernel(self) -> &'static [(isize, isize, f64)] {
+        match self {
+            Dithering::FloydSteinberg => &[(1, 0, 7. / 16.), (-1, 1, 3. / 16.), (0, 1, 5. / 16.), (1, 1, 1. / 16.)],
+            Dithering::Atkinson => &[
+                (1, 0, 1. / 8.),
+                (2, 0, 1. / 8.),
+                (-1, 1, 1. / 8.),
+                (0, 1, 1. / 8.),
+                (1, 1, 1. / 8.),
+                (0, 2, 1. / 8.),
+            ],
+            _ => &[],
+        }
+    }
+}
+
+impl FromStr for Dithering {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "None" => Ok(Dithering::None),
+            "Bayer 2x2" => Ok(Dithering::Bayer2),
+            "Bayer 4x4" => Ok(Dithering::Bayer4),
+            "Bayer 8x8" => Ok(Dithering::Bayer8),
+            "Floyd-Steinberg" => Ok(Dithering::FloydSteinberg),
+            "Atkinson" => Ok(Dithering::Atkinson),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for Dithering {
+    fn default() -> Self {
+        Dithering::None
+    }
+}
+
+/// returns at most `n` colors representing `colors`
+pub fn extract_palette(colors: &[XpriteRgba], n: usize, mode: PaletteExtraction) -> Vec<XpriteRgba> {
+    let hist = histogram(colors);
+    if n == 0 || hist.is_empty() {
+        return vec![];
+    }
+    match mode {
+        PaletteExtraction::Unique => unique(&hist, n),
+        PaletteExtraction::MedianCut => median_cut(&hist, n),
+        PaletteExtraction::KMeans => kmeans(&hist, n),
+    }
+}
+
+/// distinct opaque colors and how often they occur, in order of first appearance
+fn histogram(colors: &[XpriteRgba]) -> Vec<(XpriteRgba, usize)> {
+    let mut idx = HashMap::new();
+    let mut hist: Vec<(XpriteRgba, usize)> = vec![];
+    for c in colors.iter().filter(|c| c.a != 0) {
+        let c = XpriteRgba { a: 255, ..*c };
+        let i = *idx.entry(c).or_insert_with(|| {
+            hist.push((c, 0));
+            hist.len() - 1
+        });
+        hist[i].1 += 1;
+    }
+    hist
+}
+
+/// the `n` most frequent colors, kept in order of first appearance
+fn unique(hist: &[(XpriteRgba, usize)], n: usize) -> Vec<XpriteRgba> {
+    let mut order: Vec<usize> = (0..hist.len()).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(hist[*i].1));
+    order.truncate(n);
+    order.sort();
+    order.into_iter().map(|i| hist[i].0).collect()
+}
+
+fn channel(c: XpriteRgba, ch: usize) -> u8 {
+    match ch {
+        0 => c.r,
+        1 => c.g,
+        _ => c.b,
+    }
+}
+
+/// channel with the largest spread and its range
+fn widest_channel(colors: &[(XpriteRgba, usize)]) -> (usize, u8) {
+    (0..3)
+        .map(|ch| {
+            let min = colors.iter().map(|(c, _)| channel(*c, ch)).min().unwrap_or(0);
+            let max = colors.iter().map(|(c, _)| channel(*c, ch)).max().unwrap_or(0);
+            (ch, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap()
+}
+
+fn average(colors: &[(XpriteRgba, usize)]) -> XpriteRgba {
+    let total = colors.iter().map(|(_, w)| *w as f64).sum::<f64>();
+    let mean = |ch| (colors.iter().map(|(c, w)| f64::from(channel(*c, ch)) * *w as f64).sum::<f64>() / total).round() as u8;
+    XpriteRgba {
+        r: mean(0),
+        g: mean(1),
+        b: mean(2),
+        a: 255,
+    }
+}
+
+/// Heckbert's median cut: keep splitting the box with the widest
+/// channel at the pixel median until there are `n` boxes
+fn median_cut(hist: &[(XpriteRgba, usize)], n: usize) -> Vec<XpriteRgba> {
+    let mut boxes = vec![hist.to_vec()];
+    while boxes.len() < n {
+        let (i, (ch, range)) = boxes
+            .iter()
+            .map(|b| widest_channel(b))
+            .enumerate()
+            .max_by_key(|(_, (_, range))| *range)
+            .unwrap();
+        if range == 0 {
+            // every box is down to a single color
+            break;
+        }
+        let mut b = boxes.remove(i);
+        b.sort_by_key(|(c, _)| channel(*c, ch));
+        let total: usize = b.iter().map(|(_, w)| w).sum();
+        let mut acc = 0;
+        let mut split = b.len() - 1;
+        for (j, (_, w)) in b.iter().enumerate() {
+            acc += w;
+            if acc * 2 >= total {
+                split = j + 1;
+                break;
+            }
+        }
+        let rest = b.split_off(split.max(1).min(b.len() - 1));
+        boxes.insert(i, rest);
+        boxes.insert(i, b);
+    }
+    boxes.iter().map(|b| average(b)).collect()
+}
+
+fn dist2(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+/// weighted k-means in Lab, seeded with the median cut result
+fn kmeans(hist: &[(XpriteRgba, usize)], n: usize) -> Vec<XpriteRgba> {
+    let points: Vec<([f64; 3], f64)> = hist.iter().map(|(c, w)| (c.to_lab(), *w as f64)).collect();
+    let mut centroids: Vec<[f64; 3]> = median_cut(hist, n).into_iter().map(XpriteRgba::to_lab).collect();
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![([0.; 3], 0.); centroids.len()];
+        for (p, w) in &points {
+            let (k, _) = centroids
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| dist2(a, p).partial_cmp(&dist2(b, p)).unwrap())
+                .unwrap();
+            for i in 0..3 {
+                (sums[k].0)[i] += p[i] * w;
+            }
+            sums[k].1 += w;
+        }
+        let mut moved = false;
+        for (c, (sum, w)) in centroids.iter_mut().zip(sums) {
+            if w == 0. {
+                continue;
+            }
+            let next = [sum[0] / w, sum[1] / w, sum[2] / w];
+            moved |= dist2(c, &next) > 1e-6;
+            *c = next;
+        }
+        if !moved {
+            break;
+        }
+    }
+    let mut ret: Vec<XpriteRgba> = vec![];
+    for c in centroids.into_iter().map(|lab| XpriteRgba::from_lab(lab, 255)) {
+        if !ret.contains(&c) {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+/// Maps every pixel to the nearest palette entry and returns `Color::Indexed` pixels.
+/// `palette` holds the color of each palette index, transparent entries are never
+/// picked and transparent pixels are left as they are.
+/// Returns None if the palette has no opaque entry or a pixel color cannot be resolved.
+pub fn remap_to_palette(pixs: &Pixels, palette: &[XpriteRgba], space: ColorSpace, dithering: Dithering, xpr: Option<&Xprite>) -> Option<Pixels> {
+    let candidates: Vec<(usize, [f64; 3])> = palette
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.a != 0)
+        .map(|(i, c)| (i, space.coords(*c)))
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let nearest = |rgb: [f64; 3]| -> usize {
+        let c = XpriteRgba {
+            r: rgb[0].max(0.).min(255.).round() as u8,
+            g: rgb[1].max(0.).min(255.).round() as u8,
+            b: rgb[2].max(0.).min(255.).round() as u8,
+            a: 255,
+        };
+        let p = space.coords(c);
+        candidates
+            .iter()
+            .min_by(|(_, a), (_, b)| dist2(a, &p).partial_cmp(&dist2(b, &p)).unwrap())
+            .unwrap()
+            .0
+    };
+
+    // working colors in rgb, error is only diffused onto existing pixels
+    let mut work: HashMap<(isize, isize), [f64; 3]> = HashMap::new();
+    for p in pixs.iter() {
+        let c = p.color.to_rgba(xpr)?;
+        if c.a != 0 {
+            work.insert((p.point.x as isize, p.point.y as isize), ColorSpace::Rgb.coords(c));
+        }
+    }
+    let mut order: Vec<(isize, isize)> = work.keys().cloned().collect();
+    order.sort_by_key(|&(x, y)| (y, x));
+
+    // ordered dithering nudges colors by about one palette step
+    let spread = 255. / (candidates.len() as f64).cbrt();
+    let mut chosen = HashMap::new();
+    for (x, y) in order {
+        let mut rgb = work[&(x, y)];
+        if let Some(n) = dithering.bayer_size() {
+            let threshold = (bayer(n, x.rem_euclid(n as isize) as usize, y.rem_euclid(n as isize) as usize) as f64 + 0.5) / (n * n) as f64 - 0.5;
+            for ch in rgb.iter_mut() {
+                *ch += threshold * spread;
+            }
+        }
+        let idx = nearest(rgb);
+        let target = ColorSpace::Rgb.coords(palette[idx]);
+        for &(dx, dy, w) in dithering.kernel() {
+            if let Some(neighbor) = work.get_mut(&(x + dx, y + dy)) {
+                for ch in 0..3 {
+                    neighbor[ch] += (rgb[ch] - target[ch]) * w;
+                }
+            }
+        }
+        chosen.insert((x, y), idx);
+    }
+
+    let mut ret = Pixels::new();
+    for p in pixs.iter() {
+        let key = (p.point.x as isize, p.point.y as isize);
+        let mut p = *p;
+        if let Some(&i) = chosen.get(&key) {
+            p.color = Color::Indexed(i);
+        }
+        ret.push(p);
+    }
+    Some(ret)
+}
+
+/// threshold of the recursive n x n Bayer matrix, in 0..n*n
+pub(crate) fn bayer(n: usize, x: usize, y: usize) -> usize {
+    if n == 1 {
+        return 0;
+    }
+    let half = n / 2;
+    let quadrant = match (x / half, y / half) {
+        (0, 0) => 0,
+        (1, 0) => 2,
+        (0, 1) => 3,
+        _ => 1,
+    };
+    4 * bayer(half, x % half, y % half) + quadrant
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_unique() {
+        use super::*;
+        let red = XpriteRgba::red();
+        let blue = XpriteRgba::blue();
+        let green = XpriteRgba::green();
+        let colors = vec![red, blue, blue, green, green, green, XpriteRgba::transparent()];
+        assert_eq!(vec![red, blue, green], extract_palette(&colors, 8, PaletteExtraction::Unique));
+        assert_eq!(vec![blue, green], extract_palette(&colors, 2, PaletteExtraction::Unique));
+    }
+
+    #[test]
+    fn test_median_cut() {
+        use super::*;
+        let mut colors = vec![];
+        for i in 0..10 {
+            colors.push(XpriteRgba {
+                r: 200 + i,
+                g: 0,
+                b: 0,
+                a: 255,
+            });
+            colors.push(XpriteRgba {
+                r: 0,
+                g: 0,
+                b: 100 + i,
+                a: 255,
+            });
+        }
+        let mut pal = extract_palette(&colors, 2, PaletteExtraction::MedianCut);
+        pal.sort();
+        assert_eq!(pal, vec![XpriteRgba { r: 0, g: 0, b: 105, a: 255 }, XpriteRgba { r: 205, g: 0, b: 0, a: 255 }]);
+        assert_eq!(20, extract_palette(&colors, 64, PaletteExtraction::MedianCut).len());
+    }
+
+    #[test]
+    fn test_kmeans() {
+        use super::*;
+        let mut colors = vec![XpriteRgba::white(); 50];
+        colors.extend(vec![XpriteRgba::black(); 50]);
+        colors.push(XpriteRgba { r: 10, g: 10, b: 10, a: 255 });
+        let mut pal = extract_palette(&colors, 2, PaletteExtraction::KMeans);
+        pal.sort();
+        assert_eq!(2, pal.len());
+        assert!(pal[0].r < 5);
+        assert_eq!(pal[1], XpriteRgba::white());
+    }
+
+    #[test]
+    fn test_bayer() {
+        use super::*;
+        assert_eq!(vec![0, 2, 3, 1], vec![bayer(2, 0, 0), bayer(2, 1, 0), bayer(2, 0, 1), bayer(2, 1, 1)]);
+        let mut values: Vec<_> = (0..64).map(|i| bayer(8, i % 8, i / 8)).collect();
+        values.sort();
+        assert_eq!(values, (0..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_remap_nearest() {
+        use super::*;
+        let palette = [XpriteRgba::transparent(), XpriteRgba::black(), XpriteRgba::white()];
+        let pixs = pixels!(
+            pixel!(0, 0, Color::rgba(30, 30, 30, 255)),
+            pixel!(0, 1, Color::rgba(220, 220, 220, 255)),
+            pixel!(0, 2, Color::Rgba(XpriteRgba::transparent()))
+        );
+        for space in ColorSpace::VARIANTS.iter() {
+            let ret = remap_to_palette(&pixs, &palette, *space, Dithering::None, None).unwrap();
+            assert_eq!(ret.get_pixel(0, 0).unwrap().color, Color::Indexed(1));
+            assert_eq!(ret.get_pixel(0, 1).unwrap().color, Color::Indexed(2));
+            assert_eq!(ret.get_pixel(0, 2).unwrap().color, Color::Rgba(XpriteRgba::transparent()));
+        }
+        assert!(remap_to_palette(&pixs, &palette[..1], ColorSpace::Rgb, Dithering::None, None).is_none());
+    }
+
+    #[test]
+    fn test_remap_dithered() {
+        use super::*;
+        let palette = [XpriteRgba::black(), XpriteRgba::white()];
+        let mut pixs = Pixels::new();
+        for y in 0..8 {
+            for x in 0..8 {
+                pixs.push(pixel!(y, x, Color::rgba(128, 128, 128, 255)));
+            }
+        }
+        for dithering in Dithering::VARIANTS.iter() {
+            let ret = remap_to_palette(&pixs, &palette, ColorSpace::Rgb, *dithering, None).unwrap();
+            let whites = ret.iter().filter(|p| p.color == Color::Indexed(1)).count();
+            if *dithering == Dithering::None {
+                assert_eq!(whites, 64);
+            } else {
+                assert!(whites > 16 && whites < 48, "{:?} {}", dithering, whites);
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use crate::algorithms::ellipse::algo_ellipse;
 use crate::prelude::*;
+use std::collections::BTreeMap;
 use std::i32;
 
 /// draw the outline of a rectangle
@@ -40,6 +42,59 @@ pub fn get_rect(start: Option<Vec2f>, stop: Option<Vec2f>, filled: bool, color:
     f(i32::min(x0, x1), i32::min(y0, y1), i32::max(x0, x1), i32::max(y0, y1), color)
 }
 
+pub fn get_rounded_rect(start: Option<Vec2f>, stop: Option<Vec2f>, radius: i32, filled: bool, color: Color) -> Result<Pixels, String> {
+    let start = start.ok_or_else(|| "start is none".to_owned())?;
+    let stop = stop.ok_or_else(|| "stop is none".to_owned())?;
+    let (x0, y0, x1, y1) = (start.x as i32, start.y as i32, stop.x as i32, stop.y as i32);
+    rounded_rect(i32::min(x0, x1), i32::min(y0, y1), i32::max(x0, x1), i32::max(y0, y1), radius, filled, color)
+}
+
+/// A rectangle with its corners cut by quarter circles of `radius`. The
+/// corners are copied from one circle so all four mirror each other.
+pub fn rounded_rect(x1: i32, y1: i32, x2: i32, y2: i32, radius: i32, filled: bool, col: Color) -> Result<Pixels, String> {
+    let r = radius.min((x2 - x1 - 1) / 2).min((y2 - y1 - 1) / 2);
+    if r <= 0 {
+        let f = if filled { filled_rect } else { rect };
+        return f(x1, y1, x2, y2, col);
+    }
+    let mut ret = Pixels::new();
+    // centers of the corner circles
+    let (left, right, top, bottom) = (x1 + r, x2 - 1 - r, y1 + r, y2 - 1 - r);
+    for p in algo_ellipse(0, 0, 2 * r, 2 * r, col).iter() {
+        let (px, py) = (p.point.x as i32, p.point.y as i32);
+        if px > r || py > r {
+            continue;
+        }
+        let (dx, dy) = (r - px, r - py);
+        ret.push(pixel!(top - dy, left - dx, col));
+        ret.push(pixel!(top - dy, right + dx, col));
+        ret.push(pixel!(bottom + dy, left - dx, col));
+        ret.push(pixel!(bottom + dy, right + dx, col));
+    }
+    for i in left..=right {
+        ret.push(pixel!(y1, i, col));
+        ret.push(pixel!(y2 - 1, i, col));
+    }
+    for j in top..=bottom {
+        ret.push(pixel!(j, x1, col));
+        ret.push(pixel!(j, x2 - 1, col));
+    }
+    if filled {
+        // the outline is convex, fill each row between its ends
+        let mut rows: BTreeMap<i32, (i32, i32)> = BTreeMap::new();
+        for p in ret.iter() {
+            let row = rows.entry(p.point.y as i32).or_insert((i32::MAX, i32::MIN));
+            *row = (row.0.min(p.point.x as i32), row.1.max(p.point.x as i32));
+        }
+        for (y, (from, to)) in rows {
+            for x in from..=to {
+                ret.push(pixel!(y, x, col));
+            }
+        }
+    }
+    Ok(ret)
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -74,4 +129,37 @@ mod tests {
         exp.push(pixel!(2, 1, Color::red()));
         assert_eq!(Ok(exp), rect);
     }
+
+    #[test]
+    fn test_rounded() {
+        use super::*;
+        let outline = rounded_rect(0, 0, 12, 9, 3, false, Color::red()).unwrap();
+        let filled = rounded_rect(0, 0, 12, 9, 3, true, Color::red()).unwrap();
+        for p in outline.iter() {
+            let (x, y) = (p.point.x as isize, p.point.y as isize);
+            // every corner mirrors the others
+            assert!(outline.get_pixel(y, 11 - x).is_some());
+            assert!(outline.get_pixel(8 - y, x).is_some());
+            assert!(filled.0.contains(p));
+            // each pixel touches exactly two others, without gaps or doubled corners
+            let mut neighbours = 0;
+            for (dy, dx) in &[(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)] {
+                if outline.get_pixel(y + dy, x + dx).is_some() {
+                    neighbours += 1;
+                }
+            }
+            assert_eq!(neighbours, 2);
+        }
+        assert!(outline.get_pixel(0, 0).is_none());
+        assert!(outline.get_pixel(0, 3).is_some());
+        assert!(filled.get_pixel(0, 0).is_none());
+        assert!(filled.get_pixel(4, 0).is_some());
+
+        // the radius is limited by the shorter side
+        assert_eq!(
+            rounded_rect(0, 0, 12, 3, 5, true, Color::red()),
+            rounded_rect(0, 0, 12, 3, 1, true, Color::red())
+        );
+        assert_eq!(rounded_rect(0, 0, 3, 3, 0, false, Color::red()), rect(0, 0, 3, 3, Color::red()));
+    }
 }
@@ -0,0 +1,94 @@
+//! Replace every occurrence of a color
+use crate::prelude::*;
+use std::str::FromStr;
+
+/// which pixels a replace or a color adjustment reaches
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum ReplaceScope {
+    Selection,
+    Layer,
+    Frame,
+    AllFrames,
+}
+
+impl ReplaceScope {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ReplaceScope::Selection => "Selection",
+            ReplaceScope::Layer => "Layer",
+            ReplaceScope::Frame => "Frame",
+            ReplaceScope::AllFrames => "All frames",
+        }
+    }
+
+    pub const VARIANTS: [ReplaceScope; 4] = [ReplaceScope::Selection, ReplaceScope::Layer, ReplaceScope::Frame, ReplaceScope::AllFrames];
+}
+
+impl FromStr for ReplaceScope {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Selection" => Ok(ReplaceScope::Selection),
+            "Layer" => Ok(ReplaceScope::Layer),
+            "Frame" => Ok(ReplaceScope::Frame),
+            "All frames" => Ok(ReplaceScope::AllFrames),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for ReplaceScope {
+    fn default() -> Self {
+        ReplaceScope::Layer
+    }
+}
+
+/// Whether `color` counts as an occurrence of `target`.
+/// Two indexed colors match by index only. Otherwise both are resolved to rgba
+/// and match if no channel differs by more than `tolerance`.
+pub fn color_matches(color: Color, target: Color, tolerance: u8, xpr: Option<&Xprite>) -> bool {
+    if color == target {
+        return true;
+    }
+    if color.is_indexed() && target.is_indexed() {
+        return false;
+    }
+    match (color.to_rgba(xpr), target.to_rgba(xpr)) {
+        (Some(a), Some(b)) => {
+            let d = |x: u8, y: u8| (i16::from(x) - i16::from(y)).abs() <= i16::from(tolerance);
+            d(a.r, b.r) && d(a.g, b.g) && d(a.b, b.b) && d(a.a, b.a)
+        }
+        _ => false,
+    }
+}
+
+/// recolored copies of the pixels matching `from`
+pub fn replace_color(pixs: &Pixels, from: Color, to: Color, tolerance: u8, xpr: Option<&Xprite>) -> Pixels {
+    pixs.iter()
+        .filter(|p| color_matches(p.color, from, tolerance, xpr))
+        .map(|p| Pixel { color: to, ..*p })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_replace_color() {
+        use super::*;
+        let pixs = pixels!(
+            pixel!(0, 0, Color::rgba(100, 100, 100, 255)),
+            pixel!(0, 1, Color::rgba(104, 98, 100, 255)),
+            pixel!(0, 2, Color::rgba(110, 100, 100, 255)),
+            pixel!(0, 3, Color::Indexed(3))
+        );
+        let from = Color::rgba(100, 100, 100, 255);
+        assert_eq!(1, replace_color(&pixs, from, Color::red(), 0, None).len());
+        let replaced = replace_color(&pixs, from, Color::red(), 5, None);
+        assert_eq!(2, replaced.len());
+        assert!(replaced.iter().all(|p| p.color == Color::red()));
+
+        let replaced = replace_color(&pixs, Color::Indexed(3), Color::Indexed(4), 255, None);
+        assert_eq!(1, replaced.len());
+        assert_eq!(replaced.get_pixel(0, 3).unwrap().color, Color::Indexed(4));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::algorithms::upscale::scale2x;
 use crate::prelude::*;
 
 /// pixel rotator based on rotsprite a algorithm
@@ -16,9 +17,10 @@ pub fn rotsprite(pixs: &Pixels, angle: f64, pivot: Vec2f) -> Pixels {
         let pivot = pivot - bb.0;
         // dbg!(pivot);
         let pixs = pixs.shifted(-bb.0);
-        let pixs = scale2x(&pixs, bb);
-        let pixs = scale2x(&pixs, Rect(bb.0, bb.1 * 2.));
-        let pixs = scale2x(&pixs, Rect(bb.0, bb.1 * 4.));
+        let size = bb.1 - bb.0;
+        let pixs = scale2x(&pixs, Rect(vec2f!(0, 0), size));
+        let pixs = scale2x(&pixs, Rect(vec2f!(0, 0), size * 2. + vec2f!(1, 1)));
+        let pixs = scale2x(&pixs, Rect(vec2f!(0, 0), size * 4. + vec2f!(3, 3)));
         // pixs.save("scaled.png");
         let rotated = rotate_and_reduce(&pixs, Rect(bb.0, bb.1 * 8.), angle, pivot);
 
@@ -26,60 +28,6 @@ pub fn rotsprite(pixs: &Pixels, angle: f64, pivot: Vec2f) -> Pixels {
     }
 }
 
-/// Doubles the size of the given image using the scale2x Algorithm
-fn scale2x(original: &Pixels, bb: Rect) -> Pixels {
-    let w = bb.w() as usize;
-    let h = bb.h() as usize;
-    let mut scaled = Pixels::new();
-    for x in 0..h {
-        for y in 0..w {
-            let x = x as isize;
-            let y = y as isize;
-            let p = original.get_pixel(x, y).map(|i| i.color);
-            let a = if y > 0 { original.get_pixel(x, y - 1).map(|i| i.color) } else { p };
-            let b = if x < w as isize - 1 {
-                original.get_pixel(x + 1, y).map(|i| i.color)
-            } else {
-                p
-            };
-            let c = if x > 0 { original.get_pixel(x - 1, y).map(|i| i.color) } else { p };
-            let d = if y < h as isize - 1 {
-                original.get_pixel(x, y + 1).map(|i| i.color)
-            } else {
-                p
-            };
-            macro_rules! set_pix {
-                ($x:expr, $y:expr, $col:expr) => {
-                    if let Some(col) = $col {
-                        scaled.push(pixel!(vec2f!($x, $y), col));
-                    }
-                };
-            }
-            if c == a && c != d && a != b {
-                set_pix!((x << 1), (y << 1), a);
-            } else {
-                set_pix!((x << 1), (y << 1), p);
-            }
-            if a == b && a != c && b != d {
-                set_pix!((x << 1) + 1, (y << 1), b);
-            } else {
-                set_pix!((x << 1) + 1, (y << 1), p);
-            }
-            if d == c && d != b && c != a {
-                set_pix!((x << 1), (y << 1) + 1, c);
-            } else {
-                set_pix!((x << 1), (y << 1) + 1, p);
-            }
-            if b == d && b != a && d != c {
-                set_pix!((x << 1) + 1, (y << 1) + 1, d);
-            } else {
-                set_pix!((x << 1) + 1, (y << 1) + 1, p);
-            }
-        }
-    }
-    scaled
-}
-
 ///Rotates the image while also scaling it down by a factor of 8
 /// bb is the bounding rect of pixs
 fn rotate_and_reduce(scaled: &Pixels, bb: Rect, angle: f64, pivot: Vec2f) -> Pixels {
@@ -124,56 +72,6 @@ fn rotate_and_reduce(scaled: &Pixels, bb: Rect, angle: f64, pivot: Vec2f) -> Pix
 
 #[cfg(test)]
 mod tests {
-    #[test]
-    fn test_scale2x() {
-        use super::*;
-        let pixs = pixels!(pixel!(0, 0, Color::orange()), pixel!(1, 1, Color::blue()));
-        let ret = scale2x(&pixs, pixs.bounding_rect());
-        assert_eq!(
-            pixels! {
-                pixel!(0,0,Color::void()),
-                pixel!(1,0,Color::void()),
-                pixel!(0,1,Color::void()),
-                pixel!(3,2,Color::void()),
-                pixel!(2,3,Color::void()),
-                pixel!(3,3,Color::void())
-            },
-            ret
-        );
-    }
-
-    #[test]
-    fn test_scale2x_1() {
-        use super::*;
-        let pixs = pixels!(pixel!(0, 0, Color::orange()), pixel!(0, 2, Color::white()), pixel!(1, 1, Color::blue()));
-        let ret = scale2x(&pixs, pixs.bounding_rect());
-        assert_eq!(
-            pixels! {
-                pixel!(0,0,Color::void()),
-                pixel!(1,0,Color::void()),
-                pixel!(0,1,Color::void()),
-                pixel!(0,4,Color::void()),
-                pixel!(0,5,Color::void()),
-                pixel!(1,5,Color::void()),
-                pixel!(2,2,Color::void()),
-                pixel!(3,2,Color::void()),
-                pixel!(2,3,Color::void()),
-                pixel!(3,3,Color::void())
-            },
-            ret
-        );
-        let _img = ret.as_image(ret.bounding_rect(), None).unwrap();
-        // img.save("scale2x.png").unwrap();
-    }
-
-    #[test]
-    fn test_scale2x_2() {
-        use super::*;
-        let pixs = pixels!(pixel!(0, 0, Color::orange()), pixel!(0, 1, Color::white()), pixel!(1, 1, Color::blue()));
-        let ret = scale2x(&pixs, pixs.bounding_rect());
-        assert_eq!(12, ret.len());
-    }
-
     #[test]
     fn test_rotsprite() {
         use super::*;
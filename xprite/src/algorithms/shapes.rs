@@ -0,0 +1,182 @@
+//! Shapes inscribed in the ellipse of a bounding box
+//!
+//! The box spans pixels `p0` to `p1` inclusive. Angles are in radians,
+//! counter-clockwise from the right as seen on screen.
+use crate::algorithms::ellipse::{algo_ellipse, algo_ellipsefill};
+use crate::algorithms::line::continuous_line;
+use crate::prelude::*;
+
+/// center and radii of the ellipse in the box, measured between pixel indices
+fn ellipse_in(p0: Vec2f, p1: Vec2f) -> (Vec2f, f64, f64) {
+    let (x0, x1) = (p0.x.min(p1.x), p0.x.max(p1.x));
+    let (y0, y1) = (p0.y.min(p1.y), p0.y.max(p1.y));
+    (vec2f_xy!((x0 + x1) / 2., (y0 + y1) / 2.), (x1 - x0) / 2., (y1 - y0) / 2.)
+}
+
+/// top left and bottom right pixels of the box
+fn corners(p0: Vec2f, p1: Vec2f) -> (i32, i32, i32, i32) {
+    (p0.x.min(p1.x) as i32, p0.y.min(p1.y) as i32, p0.x.max(p1.x) as i32, p0.y.max(p1.y) as i32)
+}
+
+/// pixel `d` away from `mid`, mirrored offsets land on mirrored pixels
+fn offset_pixel(mid: f64, d: f64) -> f64 {
+    let h = mid - mid.floor();
+    if d.abs() < 1e-9 {
+        mid.floor()
+    } else if d > 0. {
+        mid.ceil() + (d - h).round().max(0.)
+    } else {
+        mid.floor() - (-d - h).round().max(0.)
+    }
+}
+
+/// pixel on the ellipse of the box at `angle`, `scale` times the radius away
+fn point_at(p0: Vec2f, p1: Vec2f, angle: f64, scale: f64) -> Vec2f {
+    let (c, rx, ry) = ellipse_in(p0, p1);
+    vec2f_xy!(offset_pixel(c.x, scale * rx * angle.cos()), offset_pixel(c.y, -scale * ry * angle.sin()))
+}
+
+/// vertices of a regular polygon whose first vertex points up, turned by `rotation`
+pub fn regular_polygon(p0: Vec2f, p1: Vec2f, sides: usize, rotation: f64) -> Vec<Vec2f> {
+    let sides = sides.max(3);
+    (0..sides)
+        .map(|i| point_at(p0, p1, PI / 2. + rotation + 2. * PI * i as f64 / sides as f64, 1.))
+        .collect()
+}
+
+/// vertices of a star with `points` tips, the inner vertices `inner` times as far out
+pub fn star(p0: Vec2f, p1: Vec2f, points: usize, inner: f64, rotation: f64) -> Vec<Vec2f> {
+    let points = points.max(2);
+    (0..2 * points)
+        .map(|i| {
+            let scale = if i % 2 == 0 { 1. } else { inner.max(0.).min(1.) };
+            point_at(p0, p1, PI / 2. + rotation + PI * i as f64 / points as f64, scale)
+        })
+        .collect()
+}
+
+/// whether `p` lies on the part of the ellipse swept from `start` by `sweep`
+fn in_sweep(c: Vec2f, rx: f64, ry: f64, p: Vec2f, start: f64, sweep: f64) -> bool {
+    let angle = f64::atan2(-(p.y - c.y) / ry.max(0.5), (p.x - c.x) / rx.max(0.5));
+    (angle - start).rem_euclid(2. * PI) <= sweep
+}
+
+/// ends of the arc swept from `start` by `sweep`
+pub fn arc_ends(p0: Vec2f, p1: Vec2f, start: f64, sweep: f64) -> (Vec2f, Vec2f) {
+    (point_at(p0, p1, start, 1.), point_at(p0, p1, start + sweep, 1.))
+}
+
+/// outline of the ellipse in the box from `start` through `sweep`
+pub fn arc(p0: Vec2f, p1: Vec2f, start: f64, sweep: f64, color: Color) -> Pixels {
+    let (c, rx, ry) = ellipse_in(p0, p1);
+    let sweep = sweep.max(0.).min(2. * PI);
+    let (x0, y0, x1, y1) = corners(p0, p1);
+    let full = algo_ellipse(x0, y0, x1, y1, color);
+    let mut ret = Pixels::new();
+    for p in full.iter() {
+        if in_sweep(c, rx, ry, p.point, start, sweep) {
+            ret.push(*p);
+        }
+    }
+    let (a, b) = arc_ends(p0, p1, start, sweep);
+    ret.push(pixel!(a, color));
+    ret.push(pixel!(b, color));
+    ret
+}
+
+/// the arc and, when `filled`, the area between it and its chord
+pub fn chord(p0: Vec2f, p1: Vec2f, start: f64, sweep: f64, filled: bool, color: Color) -> Pixels {
+    let mut ret = arc(p0, p1, start, sweep, color);
+    if !filled {
+        return ret;
+    }
+    let (a, b) = arc_ends(p0, p1, start, sweep);
+    let side = |p: Vec2f| (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x);
+    // the middle of the arc is on the filled side of the chord
+    let mid = point_at(p0, p1, start + sweep / 2., 1.);
+    let (x0, y0, x1, y1) = corners(p0, p1);
+    let fill = algo_ellipsefill(x0, y0, x1, y1, color);
+    for p in fill.iter() {
+        if side(p.point) * side(mid) >= 0. {
+            ret.push(*p);
+        }
+    }
+    ret.extend(&continuous_line(a, b, color));
+    ret
+}
+
+/// the arc closed by the radii to its ends, with the inside when `filled`
+pub fn pie(p0: Vec2f, p1: Vec2f, start: f64, sweep: f64, filled: bool, color: Color) -> Pixels {
+    let (c, rx, ry) = ellipse_in(p0, p1);
+    let sweep = sweep.max(0.).min(2. * PI);
+    let mut ret = arc(p0, p1, start, sweep, color);
+    let center = vec2f_xy!(offset_pixel(c.x, 0.), offset_pixel(c.y, 0.));
+    let (a, b) = arc_ends(p0, p1, start, sweep);
+    ret.extend(&continuous_line(center, a, color));
+    ret.extend(&continuous_line(center, b, color));
+    if filled {
+        let (x0, y0, x1, y1) = corners(p0, p1);
+        for p in algo_ellipsefill(x0, y0, x1, y1, color).iter() {
+            if in_sweep(c, rx, ry, p.point, start, sweep) {
+                ret.push(*p);
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_regular_polygon() {
+        use super::*;
+        let (p0, p1) = (vec2f!(0, 0), vec2f!(10, 10));
+        let square = regular_polygon(p0, p1, 4, 0.);
+        assert_eq!(square, vec![vec2f!(0, 5), vec2f!(5, 0), vec2f!(10, 5), vec2f!(5, 10)]);
+        let turned = regular_polygon(p0, p1, 4, PI / 4.);
+        assert!(turned.iter().all(|p| p.x != 5. && p.y != 5.));
+        // mirrored vertices land on mirrored pixels in an even sized box
+        let hexagon = regular_polygon(vec2f!(0, 0), vec2f!(9, 9), 6, 0.);
+        for p in &hexagon {
+            assert!(hexagon.contains(&vec2f_xy!(9. - p.x, p.y)) || p.x == 4.);
+        }
+        assert_eq!(regular_polygon(p0, p1, 1, 0.).len(), 3);
+
+        let tips = star(p0, p1, 5, 0.5, 0.);
+        assert_eq!(tips.len(), 10);
+        assert_eq!(tips[0], vec2f!(0, 5));
+        let (c, _, _) = ellipse_in(p0, p1);
+        for (i, p) in tips.iter().enumerate() {
+            let r = (*p - c).mag();
+            assert!(if i % 2 == 0 { r > 4. } else { r < 3.5 });
+        }
+    }
+
+    #[test]
+    fn test_arc() {
+        use super::*;
+        let (p0, p1) = (vec2f!(0, 0), vec2f!(10, 10));
+        let circle = algo_ellipse(0, 0, 10, 10, Color::red());
+        let full = arc(p0, p1, 0., 2. * PI, Color::red());
+        assert_eq!(full.len(), circle.len());
+        // the upper half
+        let half = arc(p0, p1, 0., PI, Color::red());
+        assert!(half.iter().all(|p| p.point.y <= 5.));
+        assert!(half.get_pixel(0, 5).is_some());
+        assert!(half.get_pixel(10, 5).is_none());
+
+        let quarter = pie(p0, p1, 0., PI / 2., false, Color::red());
+        assert!(quarter.get_pixel(5, 5).is_some());
+        assert!(quarter.get_pixel(5, 8).is_some());
+        assert!(quarter.get_pixel(2, 5).is_some());
+        let filled = pie(p0, p1, 0., PI / 2., true, Color::red());
+        assert!(filled.get_pixel(3, 7).is_some());
+        assert!(filled.get_pixel(7, 3).is_none());
+        assert!(quarter.iter().all(|p| filled.0.contains(p)));
+
+        let segment = chord(p0, p1, 0., PI, true, Color::red());
+        assert!(segment.get_pixel(5, 1).is_some());
+        assert!(segment.get_pixel(3, 5).is_some());
+        assert!(segment.get_pixel(7, 5).is_none());
+    }
+}
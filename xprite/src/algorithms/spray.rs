@@ -0,0 +1,124 @@
+//! Seeded scatter for the spray tool
+//!
+//! The generator is implemented here instead of pulled from a crate so the
+//! same seed scatters the same pixels on every platform and version.
+use crate::prelude::*;
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+/// splitmix64
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct SprayRng {
+    state: u64,
+}
+
+impl SprayRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// uniform in 0..n, n must be positive
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize % n
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum SprayDistribution {
+    /// evenly over the disc
+    Uniform,
+    /// dense in the middle, standard deviation of half the radius
+    Gaussian,
+}
+
+impl SprayDistribution {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SprayDistribution::Uniform => "Uniform",
+            SprayDistribution::Gaussian => "Gaussian",
+        }
+    }
+
+    pub const VARIANTS: [SprayDistribution; 2] = [SprayDistribution::Uniform, SprayDistribution::Gaussian];
+}
+
+impl FromStr for SprayDistribution {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Uniform" => Ok(SprayDistribution::Uniform),
+            "Gaussian" => Ok(SprayDistribution::Gaussian),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for SprayDistribution {
+    fn default() -> Self {
+        SprayDistribution::Uniform
+    }
+}
+
+/// `count` points scattered within `radius` of `center`, snapped to pixels
+pub fn scatter(rng: &mut SprayRng, center: Vec2f, radius: f64, count: usize, distribution: SprayDistribution) -> Vec<Vec2f> {
+    let radius = radius.max(0.);
+    let mut ret = Vec::with_capacity(count);
+    while ret.len() < count {
+        let angle = 2. * PI * rng.next_f64();
+        let dist = match distribution {
+            SprayDistribution::Uniform => radius * rng.next_f64().sqrt(),
+            SprayDistribution::Gaussian => {
+                // Box-Muller, redrawn when it lands outside the disc
+                let u = 1. - rng.next_f64();
+                let d = radius / 2. * (-2. * u.ln()).sqrt();
+                if d > radius {
+                    continue;
+                }
+                d
+            }
+        };
+        // offset from the middle of the center pixel
+        let offset = vec2f!(0.5 + dist * angle.sin(), 0.5 + dist * angle.cos());
+        ret.push((center + offset).floor());
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_scatter() {
+        use super::*;
+        let center = vec2f!(10, 10);
+        for dist in SprayDistribution::VARIANTS.iter() {
+            let a = scatter(&mut SprayRng::new(7), center, 5., 200, *dist);
+            // the same seed scatters the same points
+            assert_eq!(a, scatter(&mut SprayRng::new(7), center, 5., 200, *dist));
+            assert_ne!(a, scatter(&mut SprayRng::new(8), center, 5., 200, *dist));
+            assert_eq!(a.len(), 200);
+            assert!(a.iter().all(|p| ((p.x - 10.).powi(2) + (p.y - 10.).powi(2)).sqrt() <= 5.75));
+        }
+        // gaussian scatter gathers in the middle
+        let inner = |pts: Vec<Vec2f>| pts.iter().filter(|p| (p.x - 10.).abs() <= 2. && (p.y - 10.).abs() <= 2.).count();
+        let uniform = inner(scatter(&mut SprayRng::new(1), center, 8., 500, SprayDistribution::Uniform));
+        let gaussian = inner(scatter(&mut SprayRng::new(1), center, 8., 500, SprayDistribution::Gaussian));
+        assert!(gaussian > uniform);
+
+        assert!(scatter(&mut SprayRng::new(1), center, 0., 3, SprayDistribution::Gaussian)
+            .iter()
+            .all(|p| *p == center));
+    }
+}
@@ -0,0 +1,163 @@
+//! Stroke stabilization for freehand input
+use crate::prelude::*;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum StabilizerMode {
+    None,
+    /// the stroke is pulled along by a string of fixed length
+    LazyRope,
+    /// average of the last few input points
+    MovingAverage,
+}
+
+impl StabilizerMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            StabilizerMode::None => "None",
+            StabilizerMode::LazyRope => "Lazy rope",
+            StabilizerMode::MovingAverage => "Moving average",
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            StabilizerMode::None => "raw input",
+            StabilizerMode::LazyRope => "the stroke only follows once the cursor is further than the strength in pixels",
+            StabilizerMode::MovingAverage => "averages the cursor over as many points as the strength",
+        }
+    }
+
+    pub const VARIANTS: [StabilizerMode; 3] = [StabilizerMode::None, StabilizerMode::LazyRope, StabilizerMode::MovingAverage];
+}
+
+impl FromStr for StabilizerMode {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "None" => Ok(StabilizerMode::None),
+            "Lazy rope" => Ok(StabilizerMode::LazyRope),
+            "Moving average" => Ok(StabilizerMode::MovingAverage),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for StabilizerMode {
+    fn default() -> Self {
+        StabilizerMode::None
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Stabilizer {
+    pub mode: StabilizerMode,
+    /// rope length in pixels or number of averaged points
+    pub strength: f32,
+    /// end of the rope
+    anchor: Option<Vec2f>,
+    window: VecDeque<Vec2f>,
+}
+
+impl Stabilizer {
+    pub fn new() -> Self {
+        Self {
+            mode: StabilizerMode::None,
+            strength: 4.,
+            anchor: None,
+            window: VecDeque::new(),
+        }
+    }
+
+    /// forget the current stroke
+    pub fn clear(&mut self) {
+        self.anchor = None;
+        self.window.clear();
+    }
+
+    /// Stabilized position for input `p`, None if the stroke should not move.
+    pub fn stabilize(&mut self, p: Vec2f) -> Option<Vec2f> {
+        match self.mode {
+            StabilizerMode::None => Some(p),
+            StabilizerMode::LazyRope => {
+                let anchor = match self.anchor {
+                    None => {
+                        self.anchor = Some(p);
+                        return Some(p);
+                    }
+                    Some(anchor) => anchor,
+                };
+                let len = f64::from(self.strength.max(0.));
+                let d = p - anchor;
+                let dist = (d.x * d.x + d.y * d.y).sqrt();
+                if dist <= len {
+                    return None;
+                }
+                let anchor = anchor + d * ((dist - len) / dist);
+                self.anchor = Some(anchor);
+                Some(anchor)
+            }
+            StabilizerMode::MovingAverage => {
+                let n = (self.strength.max(1.) as usize).max(1);
+                self.window.push_back(p);
+                while self.window.len() > n {
+                    self.window.pop_front();
+                }
+                let sum = self.window.iter().fold(Vec2f { x: 0., y: 0. }, |acc, &q| acc + q);
+                Some(sum / self.window.len() as f64)
+            }
+        }
+    }
+
+    /// Where the stroke ends when the input stops at `p`, None if it already
+    /// ends there. The lazy rope is pulled through to the cursor.
+    pub fn release(&mut self, p: Vec2f) -> Option<Vec2f> {
+        match self.mode {
+            StabilizerMode::LazyRope if self.anchor.is_some() && self.anchor != Some(p) => {
+                self.anchor = Some(p);
+                Some(p)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_lazy_rope() {
+        use super::*;
+        let mut stab = Stabilizer::new();
+        stab.mode = StabilizerMode::LazyRope;
+        stab.strength = 5.;
+        assert_eq!(stab.stabilize(vec2f!(0, 0)), Some(vec2f!(0, 0)));
+        // jitter within the rope is ignored
+        assert_eq!(stab.stabilize(vec2f!(3, 4)), None);
+        assert_eq!(stab.stabilize(vec2f!(-2, 1)), None);
+        // the stroke trails the cursor by the rope length
+        assert_eq!(stab.stabilize(vec2f!(0, 8)), Some(vec2f!(0, 3)));
+        assert_eq!(stab.stabilize(vec2f!(0, 10)), Some(vec2f!(0, 5)));
+        // letting go pulls the stroke to the cursor
+        assert_eq!(stab.release(vec2f!(0, 12)), Some(vec2f!(0, 12)));
+        assert_eq!(stab.release(vec2f!(0, 12)), None);
+        stab.clear();
+        assert_eq!(stab.stabilize(vec2f!(0, 10)), Some(vec2f!(0, 10)));
+        stab.mode = StabilizerMode::MovingAverage;
+        assert_eq!(stab.release(vec2f!(0, 12)), None);
+    }
+
+    #[test]
+    fn test_moving_average() {
+        use super::*;
+        let mut stab = Stabilizer::new();
+        stab.mode = StabilizerMode::MovingAverage;
+        stab.strength = 2.;
+        assert_eq!(stab.stabilize(vec2f!(0, 0)), Some(vec2f!(0, 0)));
+        assert_eq!(stab.stabilize(vec2f!(2, 4)), Some(vec2f!(1, 2)));
+        assert_eq!(stab.stabilize(vec2f!(4, 4)), Some(vec2f!(3, 4)));
+
+        stab.mode = StabilizerMode::None;
+        assert_eq!(stab.stabilize(vec2f!(7, 7)), Some(vec2f!(7, 7)));
+    }
+}
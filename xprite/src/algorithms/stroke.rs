@@ -0,0 +1,156 @@
+//! Stroke processing shared by the drawing tools
+//!
+//! A stroke is rasterized to a one pixel wide path first, then runs through:
+//!
+//! 1. pixel_perfect? or pixel_antiperfect?
+//! 2. sorted_monotonic?
+//! 3. anti_alias?
+//!
+//! Sorting and anti-aliasing only run on pixel perfect strokes.
+use crate::prelude::*;
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct StrokePipeline {
+    /// pixel perfect - nothing else
+    ///
+    /// None -> None
+    /// true -> pixel perfect
+    /// false -> pixel anti-perfect
+    pub run_pixel_perfect: Option<bool>,
+    /// sort each monotonic segment
+    pub sorted_monotonic: bool,
+    /// Anti-aliasing with background color for each segment
+    pub selective_anti_aliasing: bool,
+    /// aa color
+    pub aa_alt_color: Option<Color>,
+    /// aa threshold
+    pub aa_threshold: f32,
+    /// aa min_segment_length
+    pub min_segment_length: i32,
+}
+
+impl Default for StrokePipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StrokePipeline {
+    /// every step turned off
+    pub fn new() -> Self {
+        Self {
+            run_pixel_perfect: None,
+            sorted_monotonic: false,
+            selective_anti_aliasing: false,
+            aa_alt_color: None,
+            aa_threshold: 0.5,
+            min_segment_length: 2,
+        }
+    }
+
+    pub fn pixel_perfect() -> Self {
+        Self {
+            run_pixel_perfect: Some(true),
+            ..Self::new()
+        }
+    }
+
+    fn run_pixel_perfect(&self, points: &mut Pixels) {
+        match self.run_pixel_perfect {
+            None => (),
+            Some(true) => points.pixel_perfect(),
+            Some(false) => points.pixel_antiperfect(),
+        };
+    }
+
+    /// runs every enabled step over a one pixel wide stroke
+    pub fn run(&self, mut points: Pixels) -> Pixels {
+        self.run_pixel_perfect(&mut points);
+        if self.run_pixel_perfect != Some(true) || points.len() < 2 {
+            return points;
+        }
+        if self.sorted_monotonic {
+            points.monotonic_sort();
+        }
+        if self.selective_anti_aliasing {
+            points.selective_antialias(
+                f64::from(self.aa_threshold),
+                self.aa_alt_color.unwrap_or_else(|| Color::Indexed(0)),
+                self.min_segment_length as usize,
+            );
+        }
+        points
+    }
+
+    /// only the pixel perfect step, cheap enough to run while drawing
+    pub fn preview(&self, mut points: Pixels) -> Pixels {
+        self.run_pixel_perfect(&mut points);
+        points
+    }
+
+    /// handles the pipeline options of a tool's `set`,
+    /// returns false when `option` is not one of them
+    pub fn set(&mut self, option: &str, value: &str) -> Result<bool, String> {
+        let invalid = || format!("invalid value for {}: {}", option, value);
+        match option {
+            "pixel_perfect" => {
+                self.run_pixel_perfect = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid())?),
+                }
+            }
+            "sorted_monotonic" => self.sorted_monotonic = value.parse().map_err(|_| invalid())?,
+            "selective_anti_aliasing" => self.selective_anti_aliasing = value.parse().map_err(|_| invalid())?,
+            "aa_threshold" => self.aa_threshold = value.parse().map_err(|_| invalid())?,
+            "min_segment_length" => self.min_segment_length = value.parse().map_err(|_| invalid())?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_stroke_pipeline() {
+        use super::*;
+        // an L corner loses its elbow when pixel perfect
+        let corner = pixels!(pixel!(0, 0, Color::red()), pixel!(0, 1, Color::red()), pixel!(1, 1, Color::red()));
+        assert_eq!(StrokePipeline::new().run(corner.clone()).len(), 3);
+        assert_eq!(StrokePipeline::pixel_perfect().run(corner.clone()).len(), 2);
+        assert_eq!(StrokePipeline::pixel_perfect().preview(corner.clone()).len(), 2);
+
+        // anti-aliasing recolors part of each long segment
+        let mut pipeline = StrokePipeline::pixel_perfect();
+        pipeline.selective_anti_aliasing = true;
+        pipeline.aa_alt_color = Some(Color::blue());
+        let stair: Pixels = (0..8).map(|x| pixel!(x / 4, x, Color::red())).collect();
+        let out = pipeline.run(stair.clone());
+        assert_eq!(out.len(), 8);
+        assert!(out.iter().any(|p| p.color == Color::blue()));
+        assert!(pipeline.preview(stair).iter().all(|p| p.color == Color::red()));
+
+        // sorting and anti-aliasing are skipped without pixel perfect
+        pipeline.run_pixel_perfect = None;
+        assert!(pipeline
+            .run(pixels!(pixel!(0, 0, Color::red()), pixel!(1, 1, Color::red())))
+            .iter()
+            .all(|p| p.color == Color::red()));
+        assert!(pipeline.run(Pixels::new()).is_empty());
+    }
+
+    #[test]
+    fn test_stroke_pipeline_set() {
+        use super::*;
+        let mut pipeline = StrokePipeline::new();
+        assert_eq!(pipeline.set("pixel_perfect", "false"), Ok(true));
+        assert_eq!(pipeline.run_pixel_perfect, Some(false));
+        assert_eq!(pipeline.set("pixel_perfect", "none"), Ok(true));
+        assert_eq!(pipeline.run_pixel_perfect, None);
+        assert_eq!(pipeline.set("aa_threshold", "0.25"), Ok(true));
+        assert_eq!(pipeline.aa_threshold, 0.25);
+        assert_eq!(pipeline.set("brush", "pixel"), Ok(false));
+        assert!(pipeline.set("sorted_monotonic", "maybe").is_err());
+        assert!(!pipeline.sorted_monotonic);
+    }
+}
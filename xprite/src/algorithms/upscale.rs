@@ -0,0 +1,442 @@
+//! Pixel art upscalers
+//!
+//! Unlike a plain nearest neighbor resize these look at the neighborhood of
+//! each pixel to keep diagonal edges smooth. None of them blend colors so the
+//! output only ever contains colors that were in the input.
+//!
+//! hqx is left out on purpose: it interpolates between neighbors, which would
+//! add colors outside the palette and turn indexed pixels into RGBA.
+use crate::prelude::*;
+use img::GenericImageView;
+use std::str::FromStr;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum UpscaleFilter {
+    Nearest,
+    Scale2x,
+    Scale3x,
+    EPX,
+    XBR,
+}
+
+impl UpscaleFilter {
+    pub fn as_str(&self) -> &str {
+        match self {
+            UpscaleFilter::Nearest => "Nearest",
+            UpscaleFilter::Scale2x => "Scale2x",
+            UpscaleFilter::Scale3x => "Scale3x",
+            UpscaleFilter::EPX => "EPX",
+            UpscaleFilter::XBR => "xBR",
+        }
+    }
+
+    /// integer factor of a single pass of the filter
+    pub fn factor(self) -> u32 {
+        match self {
+            UpscaleFilter::Nearest => 1,
+            UpscaleFilter::Scale2x | UpscaleFilter::EPX | UpscaleFilter::XBR => 2,
+            UpscaleFilter::Scale3x => 3,
+        }
+    }
+
+    pub const VARIANTS: [UpscaleFilter; 5] = [
+        UpscaleFilter::Nearest,
+        UpscaleFilter::Scale2x,
+        UpscaleFilter::Scale3x,
+        UpscaleFilter::EPX,
+        UpscaleFilter::XBR,
+    ];
+}
+
+impl FromStr for UpscaleFilter {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Nearest" => Ok(UpscaleFilter::Nearest),
+            "Scale2x" => Ok(UpscaleFilter::Scale2x),
+            "Scale3x" => Ok(UpscaleFilter::Scale3x),
+            "EPX" => Ok(UpscaleFilter::EPX),
+            "xBR" => Ok(UpscaleFilter::XBR),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for UpscaleFilter {
+    fn default() -> Self {
+        UpscaleFilter::Nearest
+    }
+}
+
+/// Scales pixels up by `scale` around the top left corner of their bounding box.
+///
+/// The filter is applied as many times as its factor divides `scale`
+/// (Scale2x with scale 4 is two passes), whatever is left over is done with
+/// nearest neighbor.
+pub fn upscale(pixs: &Pixels, filter: UpscaleFilter, scale: u32, xpr: Option<&Xprite>) -> Pixels {
+    if pixs.is_empty() || scale <= 1 {
+        return pixs.clone();
+    }
+    let origin = pixs.bounding_rect().0;
+    let mut ret = pixs.shifted(-origin);
+    let mut remaining = scale;
+    let factor = filter.factor();
+    while factor > 1 && remaining % factor == 0 {
+        let bb = ret.bounding_rect();
+        let bb = Rect(vec2f!(0, 0), bb.1);
+        ret = match filter {
+            UpscaleFilter::Scale2x => scale2x(&ret, bb),
+            UpscaleFilter::Scale3x => scale3x(&ret, bb),
+            UpscaleFilter::EPX => epx(&ret, bb),
+            UpscaleFilter::XBR => xbr(&ret, bb, xpr),
+            UpscaleFilter::Nearest => unreachable!(),
+        };
+        remaining /= factor;
+    }
+    if remaining > 1 {
+        ret = nearest(&ret, remaining);
+    }
+    ret.shifted(origin)
+}
+
+/// same as `upscale` on an image, transparent pixels are treated like any other color
+pub fn upscale_image(im: &img::DynamicImage, filter: UpscaleFilter, scale: u32) -> img::RgbaImage {
+    let (w, h) = (im.width() * scale, im.height() * scale);
+    if filter == UpscaleFilter::Nearest {
+        return img::imageops::resize(im, w, h, img::FilterType::Nearest);
+    }
+    let scaled = upscale(&im.clone().into(), filter, scale, None);
+    img::ImageBuffer::from_fn(w, h, |x, y| {
+        let col = scaled
+            .get_pixel(y as isize, x as isize)
+            .and_then(|p| p.color.to_rgba(None))
+            .unwrap_or_default();
+        img::Rgba([col.r, col.g, col.b, col.a])
+    })
+}
+
+fn nearest(pixs: &Pixels, scale: u32) -> Pixels {
+    let scale = scale as i32;
+    let mut scaled = Pixels::new();
+    for Pixel { point, color } in pixs.iter() {
+        let (x, y) = (point.x as i32, point.y as i32);
+        for dy in 0..scale {
+            for dx in 0..scale {
+                scaled.push(pixel_xy!(x * scale + dx, y * scale + dy, *color));
+            }
+        }
+    }
+    scaled
+}
+
+/// dense lookup of the pixels inside a bounding box,
+/// neighbors outside of the box are clamped to the edge
+struct Grid {
+    w: isize,
+    h: isize,
+    cells: Vec<Option<Color>>,
+}
+
+impl Grid {
+    fn new(pixs: &Pixels, bb: Rect) -> Self {
+        let w = bb.w() as isize;
+        let h = bb.h() as isize;
+        let mut cells = vec![None; (w * h) as usize];
+        for Pixel { point, color } in pixs.iter() {
+            let x = (point.x - bb.0.x) as isize;
+            let y = (point.y - bb.0.y) as isize;
+            if x < 0 || y < 0 || x >= w || y >= h {
+                continue;
+            }
+            cells[(y * w + x) as usize] = Some(*color);
+        }
+        Grid { w, h, cells }
+    }
+
+    fn idx(&self, x: isize, y: isize) -> usize {
+        let x = x.max(0).min(self.w - 1);
+        let y = y.max(0).min(self.h - 1);
+        (y * self.w + x) as usize
+    }
+
+    fn get(&self, x: isize, y: isize) -> Option<Color> {
+        self.cells[self.idx(x, y)]
+    }
+}
+
+/// pushes the scaled pixel if the cell is not empty
+fn set_pix(scaled: &mut Pixels, x: isize, y: isize, col: Option<Color>) {
+    if let Some(col) = col {
+        scaled.push(pixel_xy!(x, y, col));
+    }
+}
+
+/// Doubles the size of the given image using the scale2x (AdvMAME2x) algorithm
+/// bb is the bounding rect of pixs
+pub fn scale2x(original: &Pixels, bb: Rect) -> Pixels {
+    let grid = Grid::new(original, bb);
+    let mut scaled = Pixels::new();
+    for y in 0..grid.h {
+        for x in 0..grid.w {
+            let p = grid.get(x, y);
+            let a = grid.get(x, y - 1);
+            let b = grid.get(x + 1, y);
+            let c = grid.get(x - 1, y);
+            let d = grid.get(x, y + 1);
+            let (x_, y_) = (x << 1, y << 1);
+            let e0 = if c == a && c != d && a != b { a } else { p };
+            let e1 = if a == b && a != c && b != d { b } else { p };
+            let e2 = if d == c && d != b && c != a { c } else { p };
+            let e3 = if b == d && b != a && d != c { d } else { p };
+            set_pix(&mut scaled, x_, y_, e0);
+            set_pix(&mut scaled, x_ + 1, y_, e1);
+            set_pix(&mut scaled, x_, y_ + 1, e2);
+            set_pix(&mut scaled, x_ + 1, y_ + 1, e3);
+        }
+    }
+    scaled.shifted(bb.0 * 2.)
+}
+
+/// Triples the size of the given image using the scale3x (AdvMAME3x) algorithm
+pub fn scale3x(original: &Pixels, bb: Rect) -> Pixels {
+    let grid = Grid::new(original, bb);
+    let mut scaled = Pixels::new();
+    for y in 0..grid.h {
+        for x in 0..grid.w {
+            // A B C
+            // D E F
+            // G H I
+            let a = grid.get(x - 1, y - 1);
+            let b = grid.get(x, y - 1);
+            let c = grid.get(x + 1, y - 1);
+            let d = grid.get(x - 1, y);
+            let e = grid.get(x, y);
+            let f = grid.get(x + 1, y);
+            let g = grid.get(x - 1, y + 1);
+            let h = grid.get(x, y + 1);
+            let i = grid.get(x + 1, y + 1);
+
+            let out = if b != h && d != f {
+                [
+                    if d == b { d } else { e },
+                    if (d == b && e != c) || (b == f && e != a) { b } else { e },
+                    if b == f { f } else { e },
+                    if (d == b && e != g) || (d == h && e != a) { d } else { e },
+                    e,
+                    if (b == f && e != i) || (h == f && e != c) { f } else { e },
+                    if d == h { d } else { e },
+                    if (d == h && e != i) || (h == f && e != g) { h } else { e },
+                    if h == f { f } else { e },
+                ]
+            } else {
+                [e; 9]
+            };
+            for (n, col) in out.iter().enumerate() {
+                let n = n as isize;
+                set_pix(&mut scaled, x * 3 + n % 3, y * 3 + n / 3, *col);
+            }
+        }
+    }
+    scaled.shifted(bb.0 * 3.)
+}
+
+/// Doubles the size of the given image using Eric's Pixel Expansion
+pub fn epx(original: &Pixels, bb: Rect) -> Pixels {
+    let grid = Grid::new(original, bb);
+    let mut scaled = Pixels::new();
+    for y in 0..grid.h {
+        for x in 0..grid.w {
+            let p = grid.get(x, y);
+            let a = grid.get(x, y - 1);
+            let b = grid.get(x + 1, y);
+            let c = grid.get(x - 1, y);
+            let d = grid.get(x, y + 1);
+            let mut out = [p; 4];
+            let identical = [a == b, a == c, a == d, b == c, b == d, c == d].iter().filter(|i| **i).count();
+            // three or more identical neighbors means there is no edge to follow
+            if identical < 3 {
+                if c == a {
+                    out[0] = a;
+                }
+                if a == b {
+                    out[1] = b;
+                }
+                if d == c {
+                    out[2] = c;
+                }
+                if b == d {
+                    out[3] = d;
+                }
+            }
+            let (x_, y_) = (x << 1, y << 1);
+            set_pix(&mut scaled, x_, y_, out[0]);
+            set_pix(&mut scaled, x_ + 1, y_, out[1]);
+            set_pix(&mut scaled, x_, y_ + 1, out[2]);
+            set_pix(&mut scaled, x_ + 1, y_ + 1, out[3]);
+        }
+    }
+    scaled.shifted(bb.0 * 2.)
+}
+
+/// perceptual distance between two cells, empty cells are fully transparent
+fn yuv_dist(a: Option<[f64; 4]>, b: Option<[f64; 4]>) -> f64 {
+    let a = a.unwrap_or([0.; 4]);
+    let b = b.unwrap_or([0.; 4]);
+    48. * (a[0] - b[0]).abs() + 7. * (a[1] - b[1]).abs() + 6. * (a[2] - b[2]).abs() + 48. * (a[3] - b[3]).abs()
+}
+
+fn yuva(col: Color, xpr: Option<&Xprite>) -> Option<[f64; 4]> {
+    let XpriteRgba { r, g, b, a } = col.to_rgba(xpr)?;
+    let (r, g, b) = (f64::from(r), f64::from(g), f64::from(b));
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let u = 0.492 * (b - y);
+    let v = 0.877 * (r - y);
+    Some([y, u, v, f64::from(a)])
+}
+
+/// Doubles the size of the given image using the 2xBR edge detection.
+/// Each of the four output pixels is replaced by the closer of its two
+/// neighbors when the edge through that corner is weaker than the one across it.
+pub fn xbr(original: &Pixels, bb: Rect, xpr: Option<&Xprite>) -> Pixels {
+    let grid = Grid::new(original, bb);
+    let yuv: Vec<_> = grid
+        .cells
+        .iter()
+        .map(|c| match c {
+            Some(c) => yuva(*c, xpr).or_else(|| match c {
+                // indexed colors without a palette only compare by index
+                Color::Indexed(i) => Some([*i as f64 * 255., 0., 0., 255.]),
+                Color::Rgba(_) => None,
+            }),
+            None => None,
+        })
+        .collect();
+    let mut scaled = Pixels::new();
+    for y in 0..grid.h {
+        for x in 0..grid.w {
+            let e = grid.get(x, y);
+            let mut out = [e; 4];
+            // corner directions in the same order as the output pixels
+            for (n, &(sx, sy)) in [(-1, -1), (1, -1), (-1, 1), (1, 1)].iter().enumerate() {
+                // offsets are written for the bottom right corner and mirrored into the others
+                let at = |dx: isize, dy: isize| yuv[grid.idx(x + dx * sx, y + dy * sy)];
+                let col = |dx: isize, dy: isize| grid.get(x + dx * sx, y + dy * sy);
+                let d = |a: (isize, isize), b: (isize, isize)| yuv_dist(at(a.0, a.1), at(b.0, b.1));
+
+                let (e_, f_, h_) = (col(0, 0), col(1, 0), col(0, 1));
+                if e_ == f_ || e_ == h_ {
+                    continue;
+                }
+                let wd_across = d((0, 0), (1, -1)) + d((0, 0), (-1, 1)) + d((1, 1), (2, 0)) + d((1, 1), (0, 2)) + 4. * d((0, 1), (1, 0));
+                let wd_along = d((0, 1), (-1, 0)) + d((0, 1), (1, 2)) + d((1, 0), (2, 1)) + d((1, 0), (0, -1)) + 4. * d((0, 0), (1, 1));
+                if wd_across < wd_along {
+                    out[n] = if d((0, 0), (1, 0)) <= d((0, 0), (0, 1)) { f_ } else { h_ };
+                }
+            }
+            let (x_, y_) = (x << 1, y << 1);
+            set_pix(&mut scaled, x_, y_, out[0]);
+            set_pix(&mut scaled, x_ + 1, y_, out[1]);
+            set_pix(&mut scaled, x_, y_ + 1, out[2]);
+            set_pix(&mut scaled, x_ + 1, y_ + 1, out[3]);
+        }
+    }
+    scaled.shifted(bb.0 * 2.)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_scale2x() {
+        use super::*;
+        let pixs = pixels!(pixel!(0, 0, Color::orange()), pixel!(1, 1, Color::blue()));
+        let ret = scale2x(&pixs, pixs.bounding_rect());
+        assert_eq!(
+            pixels! {
+                pixel!(0,0,Color::void()),
+                pixel!(1,0,Color::void()),
+                pixel!(0,1,Color::void()),
+                pixel!(3,2,Color::void()),
+                pixel!(2,3,Color::void()),
+                pixel!(3,3,Color::void())
+            },
+            ret
+        );
+    }
+
+    #[test]
+    fn test_scale2x_1() {
+        use super::*;
+        let pixs = pixels!(pixel!(0, 0, Color::orange()), pixel!(0, 2, Color::white()), pixel!(1, 1, Color::blue()));
+        let ret = scale2x(&pixs, pixs.bounding_rect());
+        assert_eq!(
+            pixels! {
+                pixel!(0,0,Color::void()),
+                pixel!(1,0,Color::void()),
+                pixel!(0,1,Color::void()),
+                pixel!(0,4,Color::void()),
+                pixel!(0,5,Color::void()),
+                pixel!(1,5,Color::void()),
+                pixel!(2,2,Color::void()),
+                pixel!(3,2,Color::void()),
+                pixel!(2,3,Color::void()),
+                pixel!(3,3,Color::void())
+            },
+            ret
+        );
+        let _img = ret.as_image(ret.bounding_rect(), None).unwrap();
+        // img.save("scale2x.png").unwrap();
+    }
+
+    #[test]
+    fn test_scale2x_2() {
+        use super::*;
+        let pixs = pixels!(pixel!(0, 0, Color::orange()), pixel!(0, 1, Color::white()), pixel!(1, 1, Color::blue()));
+        let ret = scale2x(&pixs, pixs.bounding_rect());
+        assert_eq!(12, ret.len());
+    }
+
+    #[test]
+    fn test_scale3x_diagonal() {
+        use super::*;
+        let mut pixs = crate::algorithms::rect::filled_rect(0, 0, 3, 3, Color::white()).unwrap();
+        pixs.extend(&pixels!(pixel!(0, 0, Color::red()), pixel!(1, 1, Color::red()), pixel!(2, 2, Color::red())));
+        let ret = scale3x(&pixs, pixs.bounding_rect());
+        assert_eq!(81, ret.len());
+        // the corners between the diagonal pixels get filled in to join them
+        assert_eq!(ret.get_pixel(2, 3).map(|p| p.color), Some(Color::red()));
+        assert_eq!(ret.get_pixel(3, 2).map(|p| p.color), Some(Color::red()));
+        assert_eq!(ret.get_pixel(0, 3).map(|p| p.color), Some(Color::white()));
+    }
+
+    #[test]
+    fn test_epx_flat() {
+        use super::*;
+        let pixs = crate::algorithms::rect::filled_rect(0, 0, 3, 3, Color::red()).unwrap();
+        let ret = epx(&pixs, pixs.bounding_rect());
+        assert_eq!(pixs.len() * 4, ret.len());
+    }
+
+    #[test]
+    fn test_upscale_keeps_colors() {
+        use super::*;
+        let pixs = pixels!(
+            pixel!(5, 5, Color::red()),
+            pixel!(5, 6, Color::blue()),
+            pixel!(6, 6, Color::red()),
+            pixel!(6, 5, Color::green())
+        );
+        for filter in UpscaleFilter::VARIANTS.iter() {
+            let ret = upscale(&pixs, *filter, 6, None);
+            assert_eq!(ret.bounding_rect(), Rect(vec2f!(5, 5), vec2f!(16, 16)));
+            assert!(ret.iter().all(|p| pixs.iter().any(|q| q.color == p.color)));
+        }
+    }
+
+    #[test]
+    fn test_upscale_filter_from_str() {
+        use super::*;
+        for filter in UpscaleFilter::VARIANTS.iter() {
+            assert_eq!(Ok(*filter), filter.as_str().parse());
+        }
+    }
+}
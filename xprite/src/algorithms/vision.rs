@@ -0,0 +1,117 @@
+//! Color vision deficiency simulation
+//!
+//! Dichromacy uses the full severity matrices of Machado, Oliveira and
+//! Fernandes (2009), applied in linear rgb.
+use crate::prelude::*;
+use std::str::FromStr;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum VisionSimulation {
+    Normal,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+    Grayscale,
+}
+
+impl VisionSimulation {
+    pub fn as_str(&self) -> &str {
+        match self {
+            VisionSimulation::Normal => "Normal",
+            VisionSimulation::Protanopia => "Protanopia",
+            VisionSimulation::Deuteranopia => "Deuteranopia",
+            VisionSimulation::Tritanopia => "Tritanopia",
+            VisionSimulation::Grayscale => "Grayscale",
+        }
+    }
+
+    pub const VARIANTS: [VisionSimulation; 5] = [
+        VisionSimulation::Normal,
+        VisionSimulation::Protanopia,
+        VisionSimulation::Deuteranopia,
+        VisionSimulation::Tritanopia,
+        VisionSimulation::Grayscale,
+    ];
+
+    fn matrix(self) -> [[f64; 3]; 3] {
+        match self {
+            VisionSimulation::Normal => [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            VisionSimulation::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            VisionSimulation::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            VisionSimulation::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+            VisionSimulation::Grayscale => [[0.2126, 0.7152, 0.0722], [0.2126, 0.7152, 0.0722], [0.2126, 0.7152, 0.0722]],
+        }
+    }
+
+    /// the color as seen with this kind of vision, alpha is kept
+    pub fn apply(self, col: XpriteRgba) -> XpriteRgba {
+        if self == VisionSimulation::Normal {
+            return col;
+        }
+        let rgb = col.to_linear();
+        let m = self.matrix();
+        let row = |i: usize| m[i][0] * rgb[0] + m[i][1] * rgb[1] + m[i][2] * rgb[2];
+        XpriteRgba::from_linear([row(0), row(1), row(2)], col.a)
+    }
+}
+
+impl FromStr for VisionSimulation {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Normal" => Ok(VisionSimulation::Normal),
+            "Protanopia" => Ok(VisionSimulation::Protanopia),
+            "Deuteranopia" => Ok(VisionSimulation::Deuteranopia),
+            "Tritanopia" => Ok(VisionSimulation::Tritanopia),
+            "Grayscale" => Ok(VisionSimulation::Grayscale),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for VisionSimulation {
+    fn default() -> Self {
+        VisionSimulation::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_vision_simulation() {
+        use super::*;
+        let col = XpriteRgba { r: 200, g: 60, b: 90, a: 7 };
+        assert_eq!(VisionSimulation::Normal.apply(col), col);
+        let grey = VisionSimulation::Grayscale.apply(col);
+        assert_eq!((grey.r, grey.r, grey.a), (grey.g, grey.b, 7));
+        // neutral colors look the same to everyone
+        for sim in VisionSimulation::VARIANTS.iter() {
+            for &c in &[XpriteRgba::white(), XpriteRgba::black()] {
+                let out = sim.apply(c);
+                assert!((i16::from(out.r) - i16::from(c.r)).abs() <= 1);
+                assert!((i16::from(out.b) - i16::from(c.b)).abs() <= 1);
+            }
+        }
+        // red and green collapse for red-green dichromats
+        let red = XpriteRgba::red();
+        let green = XpriteRgba { r: 0, g: 110, b: 0, a: 255 };
+        let dist = |a: XpriteRgba, b: XpriteRgba| {
+            let (a, b) = (a.to_lab(), b.to_lab());
+            ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+        };
+        let sim = VisionSimulation::Deuteranopia;
+        assert!(dist(sim.apply(red), sim.apply(green)) < dist(red, green) / 2.);
+    }
+}
@@ -1,7 +1,50 @@
 use crate::algorithms::{ellipse, line, rect};
 use crate::prelude::*;
+use image::{self, GenericImageView};
+use indexmap::IndexMap;
+use natord;
 use std::f64::consts::PI;
 use std::str::FromStr;
+use std::{fs, io, path};
+
+/// where captured brushes are kept
+pub const BRUSH_DIR: &str = "config/brushes";
+
+/// how a pattern brush paints
+#[derive(PartialEq, Eq, Clone, Debug, Copy)]
+pub enum PatternMode {
+    /// keeps the captured colors
+    Color,
+    /// paints the captured shape with the stroke color
+    Mask,
+}
+
+impl PatternMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PatternMode::Color => "Color",
+            PatternMode::Mask => "Mask",
+        }
+    }
+    pub const VARIANTS: [PatternMode; 2] = [PatternMode::Color, PatternMode::Mask];
+}
+
+impl FromStr for PatternMode {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Color" => Ok(PatternMode::Color),
+            "Mask" => Ok(PatternMode::Mask),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for PatternMode {
+    fn default() -> Self {
+        PatternMode::Color
+    }
+}
 
 #[derive(PartialEq, Eq, Clone, Debug, Copy)]
 pub enum BrushType {
@@ -11,6 +54,8 @@ pub enum BrushType {
     Square(u32),
     /// size, angle
     Line(u32, u32),
+    /// index in the brush library, mode
+    Pattern(usize, PatternMode),
 }
 
 impl BrushType {
@@ -19,6 +64,7 @@ impl BrushType {
             BrushType::Circle(_) => "o",
             BrushType::Square(_) => "s",
             BrushType::Line(_, _) => "/",
+            BrushType::Pattern(_, _) => "p",
         }
     }
     pub const VARIANTS: [BrushType; 3] = [BrushType::Circle(8), BrushType::Square(4), BrushType::Line(2, 0)];
@@ -154,6 +200,16 @@ impl Brush {
         Self { shape, bb, offset, brush_type }
     }
 
+    /// brush painting `pixs`, centered like the other shapes
+    pub fn pattern(pixs: &Pixels, idx: usize, mode: PatternMode) -> Self {
+        let rect = pixs.bounding_rect();
+        let shape = pixs.shifted(-rect.0);
+        let bb = (rect.w(), rect.h());
+        let offset = (-(bb.0 / 2.).floor(), -(bb.1 / 2.).floor());
+        let brush_type = BrushType::Pattern(idx, mode);
+        Self { shape, bb, offset, brush_type }
+    }
+
     #[must_use]
     pub fn follow_stroke(&self, stroke: &Pixels) -> Option<Pixels> {
         let mut ret = Pixels::new();
@@ -169,21 +225,136 @@ impl Brush {
     pub fn to_canvas_pixels(&self, cursor: Vec2f, color: Color) -> Option<Pixels> {
         let Vec2f { x, y } = cursor;
         let (offset_x, offset_y) = self.offset;
+        let keep_colors = match self.brush_type {
+            BrushType::Pattern(_, mode) => mode == PatternMode::Color,
+            _ => false,
+        };
         let ret: Vec<Pixel> = self
             .shape
             .iter()
-            .map(|Pixel { point, .. }| Pixel {
+            .map(|p| Pixel {
                 point: Vec2f {
-                    x: point.x + x + offset_x,
-                    y: point.y + y + offset_y,
+                    x: p.point.x + x + offset_x,
+                    y: p.point.y + y + offset_y,
                 },
-                color,
+                color: if keep_colors { p.color } else { color },
             })
             .collect();
         Some(Pixels::from_slice(&ret))
     }
 }
 
+/// Brushes captured from the canvas, loaded from `BRUSH_DIR` on startup.
+#[derive(Debug, Default)]
+pub struct BrushLibrary {
+    /// rgba shapes by name, moved to the origin
+    pub brushes: IndexMap<String, Pixels>,
+}
+
+impl BrushLibrary {
+    /// Unreadable brushes are skipped, without any the built-in brushes
+    /// are all there is.
+    pub fn new() -> Self {
+        if cfg!(not(wasm32)) {
+            Self::load(BRUSH_DIR)
+        } else {
+            Default::default()
+        }
+    }
+
+    /// every png brush in `dir`
+    fn load<P: AsRef<path::Path>>(dir: P) -> Self {
+        let mut brushes = IndexMap::new();
+        let dir_entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return Self { brushes },
+        };
+        let mut entries: Vec<_> = dir_entries.filter_map(Result::ok).collect();
+        entries.sort_by(|dir1, dir2| natord::compare(&dir1.path().to_string_lossy(), &dir2.path().to_string_lossy()));
+        for entry in &entries {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("png") {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|i| i.to_str()) {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+            match fs::read(&path).and_then(|bytes| read_brush(&bytes)) {
+                Ok(pixs) => {
+                    brushes.insert(name, pixs);
+                }
+                Err(e) => error!("cannot load brush {:?}: {}", path, e),
+            }
+        }
+        Self { brushes }
+    }
+
+    /// Adds a brush called `name`, numbered if the name is taken so no brush or
+    /// brush file is replaced. Returns its index.
+    pub fn insert(&mut self, name: &str, pixs: &Pixels) -> Result<usize, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("brush name is empty".to_owned());
+        }
+        let mut key = name.to_owned();
+        let mut n = 2;
+        while self.brushes.contains_key(&key) {
+            key = format!("{} {}", name, n);
+            n += 1;
+        }
+        let origin = pixs.bounding_rect().0;
+        let (idx, _) = self.brushes.insert_full(key, pixs.shifted(-origin));
+        Ok(idx)
+    }
+
+    pub fn brush(&self, idx: usize, mode: PatternMode) -> Option<Brush> {
+        let (_, pixs) = self.brushes.get_index(idx)?;
+        Some(Brush::pattern(pixs, idx, mode))
+    }
+
+    /// writes the brush at `idx` to `BRUSH_DIR` as a png
+    pub fn save(&self, idx: usize) -> io::Result<()> {
+        let (name, pixs) = self.brushes.get_index(idx).ok_or_else(|| invalid("no such brush"))?;
+        fs::create_dir_all(BRUSH_DIR)?;
+        let p = path::Path::new(BRUSH_DIR).join(format!("{}.png", name));
+        info!("saving brush {} to {:?}", name, p.as_os_str());
+        fs::write(p, write_brush(pixs)?)
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// every pixel that is not fully transparent
+fn read_brush(bytes: &[u8]) -> io::Result<Pixels> {
+    let img = image::load_from_memory(bytes).map_err(invalid)?;
+    let pixs: Pixels = img
+        .pixels()
+        .filter(|(_, _, c)| c[3] != 0)
+        .map(|(x, y, c)| pixel!(y, x, Color::rgba(c[0], c[1], c[2], c[3])))
+        .collect();
+    if pixs.is_empty() {
+        return Err(invalid("brush image is empty"));
+    }
+    Ok(pixs)
+}
+
+fn write_brush(pixs: &Pixels) -> io::Result<Vec<u8>> {
+    let bb = pixs.bounding_rect();
+    let (w, h) = (bb.w() as usize, bb.h() as usize);
+    let mut data = vec![0; w * h * 4];
+    for p in pixs.iter() {
+        let rgba = p.color.to_rgba(None).ok_or_else(|| invalid("brush color is not rgba"))?;
+        let i = ((p.point.y - bb.0.y) as usize * w + (p.point.x - bb.0.x) as usize) * 4;
+        data[i..i + 4].copy_from_slice(&[rgba.r, rgba.g, rgba.b, rgba.a]);
+    }
+    let mut bytes = vec![];
+    image::png::PNGEncoder::new(&mut bytes).encode(&data, w as u32, h as u32, image::ColorType::RGBA(8))?;
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -250,4 +421,52 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_pattern_brush() {
+        use super::*;
+        let pixs = pixels!(
+            pixel!(5, 7, Color::red()),
+            pixel!(6, 8, Color::rgba(0, 0, 255, 255)),
+            pixel!(7, 9, Color::red())
+        );
+        let mut lib = BrushLibrary::default();
+        let idx = lib.insert("diag", &pixs).unwrap();
+        assert!(lib.brush(idx + 1, PatternMode::Color).is_none());
+
+        let brush = lib.brush(idx, PatternMode::Color).unwrap();
+        assert_eq!(brush.bb, (3., 3.));
+        assert_eq!(brush.offset, (-1., -1.));
+        let painted = brush.to_canvas_pixels(vec2f!(10, 10), Color::Indexed(2)).unwrap();
+        assert_eq!(painted.get_pixel(9, 9).unwrap().color, Color::red());
+        assert_eq!(painted.get_pixel(10, 10).unwrap().color, Color::rgba(0, 0, 255, 255));
+
+        let mask = lib.brush(idx, PatternMode::Mask).unwrap();
+        let painted = mask.to_canvas_pixels(vec2f!(10, 10), Color::Indexed(2)).unwrap();
+        assert!(painted.iter().all(|p| p.color == Color::Indexed(2)));
+        assert_eq!(painted.len(), 3);
+
+        let read = read_brush(&write_brush(&lib.brushes["diag"]).unwrap()).unwrap();
+        assert_eq!(read, lib.brushes["diag"]);
+        assert_eq!(read.get_pixel(1, 1).unwrap().color, Color::rgba(0, 0, 255, 255));
+
+        // names are never reused or empty
+        assert_eq!(lib.insert("diag", &pixs), Ok(idx + 1));
+        assert_eq!(lib.brushes.get_index(idx + 1).unwrap().0, "diag 2");
+        assert!(lib.insert(" ", &pixs).is_err());
+    }
+
+    #[test]
+    fn test_brush_library_skips_unreadable() {
+        use super::*;
+        let dir = std::env::temp_dir().join("xprite_brushes");
+        fs::create_dir_all(&dir).unwrap();
+        let pixs = pixels!(pixel!(0, 0, Color::red()), pixel!(1, 1, Color::red()));
+        fs::write(dir.join("good.png"), write_brush(&pixs).unwrap()).unwrap();
+        fs::write(dir.join("broken.png"), b"not a png").unwrap();
+        let lib = BrushLibrary::load(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(lib.brushes.keys().collect::<Vec<_>>(), vec!["good"]);
+        assert!(BrushLibrary::load(&dir).brushes.is_empty());
+    }
 }
@@ -48,6 +48,14 @@ impl Color {
         }
     }
 
+    pub fn is_indexed(&self) -> bool {
+        if let Color::Indexed(_) = self {
+            true
+        } else {
+            false
+        }
+    }
+
     pub unsafe fn as_index(&self) -> usize {
         match self {
             Color::Indexed(idx) => *idx,
@@ -61,8 +69,7 @@ impl Color {
     pub fn to_rgba(&self, xpr: Option<&Xprite>) -> Option<XpriteRgba> {
         match *self {
             Color::Indexed(i) => {
-                let pm = &xpr?.palette;
-                let (_pal_name, pal) = pm.palettes.get_index(pm.selected_palette_idx)?;
+                let pal = xpr?.palette.current_palette();
                 Some(unsafe { pal.colors.borrow().get_index(i)?.1.as_rgba() })
             }
             Color::Rgba(c) => Some(c),
@@ -214,6 +221,100 @@ impl From<XpriteRgba> for [u8; 4] {
 }
 
 impl XpriteRgba {
+    /// CIE L*a*b* coordinates, distances here follow perceived difference
+    pub fn to_lab(self) -> [f64; 3] {
+        let rgb = palette::Srgb::new(f32::from(self.r) / 255., f32::from(self.g) / 255., f32::from(self.b) / 255.);
+        let lab: palette::Lab = rgb.into_linear().into();
+        [f64::from(lab.l), f64::from(lab.a), f64::from(lab.b)]
+    }
+
+    pub fn from_lab(lab: [f64; 3], a: u8) -> Self {
+        let lab = palette::Lab::new(lab[0] as f32, lab[1] as f32, lab[2] as f32);
+        let rgb = palette::Srgb::from_linear(lab.into());
+        let channel = |c: f32| (c.max(0.).min(1.) * 255.).round() as u8;
+        XpriteRgba {
+            r: channel(rgb.red),
+            g: channel(rgb.green),
+            b: channel(rgb.blue),
+            a,
+        }
+    }
+
+    /// linear rgb, channels in [0, 1]
+    pub fn to_linear(self) -> [f64; 3] {
+        let rgb = palette::Srgb::new(f32::from(self.r) / 255., f32::from(self.g) / 255., f32::from(self.b) / 255.).into_linear();
+        [f64::from(rgb.red), f64::from(rgb.green), f64::from(rgb.blue)]
+    }
+
+    pub fn from_linear(rgb: [f64; 3], a: u8) -> Self {
+        let lin = palette::LinSrgb::new(rgb[0] as f32, rgb[1] as f32, rgb[2] as f32);
+        let rgb = palette::Srgb::from_linear(lin);
+        let channel = |c: f32| (c.max(0.).min(1.) * 255.).round() as u8;
+        XpriteRgba {
+            r: channel(rgb.red),
+            g: channel(rgb.green),
+            b: channel(rgb.blue),
+            a,
+        }
+    }
+
+    /// WCAG relative luminance in [0, 1]
+    pub fn relative_luminance(self) -> f64 {
+        let [r, g, b] = self.to_linear();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// WCAG contrast ratio, from 1 for equal luminance up to 21 for black on white
+    pub fn contrast_ratio(self, other: XpriteRgba) -> f64 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// hue in degrees [0, 360), saturation and value in [0, 1]
+    pub fn to_hsv(self) -> (f64, f64, f64) {
+        let r = f64::from(self.r) / 255.;
+        let g = f64::from(self.g) / 255.;
+        let b = f64::from(self.b) / 255.;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let d = max - min;
+        let h = if d == 0. {
+            0.
+        } else if max == r {
+            60. * ((g - b) / d).rem_euclid(6.)
+        } else if max == g {
+            60. * ((b - r) / d + 2.)
+        } else {
+            60. * ((r - g) / d + 4.)
+        };
+        let s = if max == 0. { 0. } else { d / max };
+        (h, s, max)
+    }
+
+    /// inverse of `to_hsv`, hue wraps around
+    pub fn from_hsv(h: f64, s: f64, v: f64, a: u8) -> Self {
+        let h = h.rem_euclid(360.) / 60.;
+        let c = v * s;
+        let x = c * (1. - (h % 2. - 1.).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.),
+            1 => (x, c, 0.),
+            2 => (0., c, x),
+            3 => (0., x, c),
+            4 => (x, 0., c),
+            _ => (c, 0., x),
+        };
+        let m = v - c;
+        let channel = |i: f64| ((i + m) * 255.).round().max(0.).min(255.) as u8;
+        XpriteRgba {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+            a,
+        }
+    }
+
     pub fn from_hex(col: &str) -> Result<Self, hex::FromHexError> {
         let r = hex::decode(&col[..2])?[0];
         let g = hex::decode(&col[2..4])?[0];
@@ -0,0 +1,154 @@
+//! Palette color cycling
+//!
+//! A cycle rotates the colors of a run of palette entries over time. Pixels
+//! keep their index, so everything painted with those entries animates.
+use std::str::FromStr;
+
+/// longest loop `loop_frames` looks for
+pub const MAX_CYCLE_FRAMES: usize = 256;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum CycleDirection {
+    Forward,
+    Backward,
+    PingPong,
+}
+
+impl CycleDirection {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CycleDirection::Forward => "Forward",
+            CycleDirection::Backward => "Backward",
+            CycleDirection::PingPong => "Ping-pong",
+        }
+    }
+
+    pub const VARIANTS: [CycleDirection; 3] = [CycleDirection::Forward, CycleDirection::Backward, CycleDirection::PingPong];
+}
+
+impl FromStr for CycleDirection {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Forward" => Ok(CycleDirection::Forward),
+            "Backward" => Ok(CycleDirection::Backward),
+            "Ping-pong" => Ok(CycleDirection::PingPong),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for CycleDirection {
+    fn default() -> Self {
+        CycleDirection::Forward
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCycle {
+    /// first palette entry of the run
+    pub start: usize,
+    /// last palette entry of the run, inclusive
+    pub end: usize,
+    /// steps per second
+    pub speed: f64,
+    pub direction: CycleDirection,
+}
+
+impl ColorCycle {
+    pub fn new(start: usize, end: usize, speed: f64, direction: CycleDirection) -> Self {
+        Self {
+            start: start.min(end),
+            end: start.max(end),
+            speed,
+            direction,
+        }
+    }
+
+    /// number of entries in the run
+    pub fn span(&self) -> usize {
+        self.end.saturating_sub(self.start) + 1
+    }
+
+    /// steps until the colors are back in place
+    pub fn period(&self) -> usize {
+        match self.direction {
+            CycleDirection::PingPong if self.span() > 1 => 2 * (self.span() - 1),
+            _ => self.span(),
+        }
+    }
+
+    /// steps taken after `t` seconds
+    pub fn step(&self, t: f64) -> usize {
+        (t * self.speed).max(0.).floor() as usize
+    }
+
+    /// entry whose color is shown by `idx` after `t` seconds,
+    /// None if `idx` is not part of the run
+    pub fn apply(&self, idx: usize, t: f64) -> Option<usize> {
+        if idx < self.start || idx > self.end {
+            return None;
+        }
+        let span = self.span();
+        let step = self.step(t) % self.period();
+        // colors travel toward higher indices going forward
+        let offset = match self.direction {
+            CycleDirection::Forward => span - step,
+            CycleDirection::Backward => step,
+            CycleDirection::PingPong if step < span => span - step,
+            CycleDirection::PingPong => step - (span - 2),
+        };
+        Some(self.start + (idx - self.start + offset) % span)
+    }
+}
+
+/// Frames at `fps` after which every cycle is back in place and on a step
+/// boundary, so the frames loop seamlessly. Fails if that takes more than
+/// `MAX_CYCLE_FRAMES`.
+pub fn loop_frames(cycles: &[ColorCycle], fps: f64) -> Result<usize, String> {
+    (1..=MAX_CYCLE_FRAMES)
+        .find(|&n| {
+            cycles.iter().all(|c| {
+                let steps = n as f64 * c.speed / fps;
+                (steps - steps.round()).abs() < 1e-6 && steps.round() as usize % c.period() == 0
+            })
+        })
+        .ok_or_else(|| format!("color cycles do not loop within {} frames at {} fps", MAX_CYCLE_FRAMES, fps))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_color_cycle() {
+        use super::*;
+        let forward = ColorCycle::new(2, 4, 1., CycleDirection::Forward);
+        assert_eq!(forward.apply(1, 0.), None);
+        assert_eq!(forward.apply(5, 0.), None);
+        let at = |c: &ColorCycle, t| (2..=4).map(|i| c.apply(i, t).unwrap()).collect::<Vec<_>>();
+        assert_eq!(at(&forward, 0.), vec![2, 3, 4]);
+        assert_eq!(at(&forward, 1.5), vec![4, 2, 3]);
+        assert_eq!(at(&forward, 3.), vec![2, 3, 4]);
+
+        let backward = ColorCycle::new(4, 2, 1., CycleDirection::Backward);
+        assert_eq!(at(&backward, 1.), vec![3, 4, 2]);
+
+        let pingpong = ColorCycle::new(2, 4, 1., CycleDirection::PingPong);
+        assert_eq!(pingpong.period(), 4);
+        let offsets: Vec<_> = (0..5).map(|t| pingpong.apply(2, f64::from(t)).unwrap()).collect();
+        assert_eq!(offsets, vec![2, 4, 3, 4, 2]);
+    }
+
+    #[test]
+    fn test_loop_frames() {
+        use super::*;
+        assert_eq!(loop_frames(&[], 25.), Ok(1));
+        let a = ColorCycle::new(0, 3, 5., CycleDirection::Forward);
+        assert_eq!(loop_frames(&[a], 5.), Ok(4));
+        assert_eq!(loop_frames(&[a], 10.), Ok(8));
+        let b = ColorCycle::new(0, 2, 5., CycleDirection::Backward);
+        assert_eq!(loop_frames(&[a, b], 5.), Ok(12));
+        // 255 entries at half a step per frame take 510 frames
+        let slow = ColorCycle::new(0, 254, 0.5, CycleDirection::Forward);
+        assert!(loop_frames(&[slow], 1.).is_err());
+    }
+}
@@ -18,10 +18,11 @@ pub enum ExporterFormat {
     PAM,
     BMP,
     ASE,
+    GIF,
 }
 
 impl ExporterFormat {
-    pub const VARIANTS: [ExporterFormat; 9] = [
+    pub const VARIANTS: [ExporterFormat; 10] = [
         ExporterFormat::ICO,
         ExporterFormat::JPG,
         ExporterFormat::PNG,
@@ -31,6 +32,7 @@ impl ExporterFormat {
         ExporterFormat::PAM,
         ExporterFormat::BMP,
         ExporterFormat::ASE,
+        ExporterFormat::GIF,
     ];
 
     pub fn as_file_extension(self) -> &'static str {
@@ -44,6 +46,7 @@ impl ExporterFormat {
             ExporterFormat::PAM => "pam",
             ExporterFormat::BMP => "bmp",
             ExporterFormat::ASE => "ase",
+            ExporterFormat::GIF => "gif",
         }
     }
 }
@@ -51,9 +54,12 @@ impl ExporterFormat {
 pub struct ExporterSpec {
     pub format: ExporterFormat,
     pub rescale: u32,
+    pub filter: UpscaleFilter,
     pub stem: String,
     pub layer: ExportType,
     pub trim: bool,
+    /// gif frame rate for palette color cycling
+    pub fps: u32,
 }
 
 impl Default for ExporterSpec {
@@ -61,21 +67,43 @@ impl Default for ExporterSpec {
         Self {
             format: ExporterFormat::ASE,
             rescale: 1,
+            filter: UpscaleFilter::Nearest,
             stem: String::new(),
             layer: ExportType::All,
             trim: true,
+            fps: 25,
         }
     }
 }
 
 impl ExporterSpec {
-    fn export(&self, xpr: &Xprite, dir: &str) {
+    /// pixels of the exported layer(s) and the area they are written from
+    fn content(&self, xpr: &Xprite) -> (Pixels, Rect) {
+        let canvas_bb = Rect(vec2f!(0, 0), vec2f_xy!(xpr.canvas.art_w - 1., xpr.canvas.art_h - 1.));
+        let (pixs, trim) = match self.layer {
+            ExportType::All => (xpr.flatten_visible(), false),
+            ExportType::Layer(group_idx, layer_idx) => (xpr.get_layer(group_idx, layer_idx).borrow().content.clone(), self.trim),
+            ExportType::Group(group_idx) => {
+                let mut pixs = Pixels::new();
+                for layer in xpr.frame().groups[group_idx].1.iter().rev() {
+                    pixs.extend(&layer.borrow().content);
+                }
+                (pixs, self.trim)
+            }
+        };
+        let bb = if trim && !pixs.is_empty() { pixs.bounding_rect() } else { canvas_bb };
+        (pixs, bb)
+    }
+
+    fn export(&self, xpr: &Xprite, dir: &str) -> Result<(), String> {
         let ExporterSpec {
             format,
             rescale,
+            filter,
             stem,
             layer,
             trim,
+            fps,
         } = self;
         let ext = self.format.as_file_extension();
 
@@ -88,18 +116,29 @@ impl ExporterSpec {
         });
         path.set_extension(ext);
 
+        let failed = || format!("failed to export {:?}", path);
+        let (pixs, bb) = self.content(xpr);
         match format {
             ExporterFormat::ASE => {
-                xpr.save_ase(&path);
+                xpr.save_ase(&path).ok_or_else(failed)?;
+            }
+            ExporterFormat::GIF => {
+                xpr.save_cycle_gif(&pixs, bb, &path, *rescale, *filter, f64::from(*fps))?;
+            }
+            ExporterFormat::PNG if !pixs.is_empty() && pixs.iter().all(|p| p.color.is_indexed()) => {
+                // documents drawn only with palette colors keep their palette
+                xpr.save_indexed_png(&pixs, bb, &path, *rescale, *filter)?;
             }
             _ => {
                 match layer {
-                    ExportType::All => xpr.save_img(path, *rescale),
-                    ExportType::Layer(group_idx, layer_idx) => xpr.save_layer_img(*group_idx, *layer_idx, &path, *rescale, *trim),
-                    ExportType::Group(group_idx) => xpr.save_group_img(*group_idx, path, *rescale, *trim),
-                };
+                    ExportType::All => xpr.save_img(&path, *rescale, *filter),
+                    ExportType::Layer(group_idx, layer_idx) => xpr.save_layer_img(*group_idx, *layer_idx, &path, *rescale, *filter, *trim),
+                    ExportType::Group(group_idx) => xpr.save_group_img(*group_idx, &path, *rescale, *filter, *trim),
+                }
+                .ok_or_else(failed)?;
             }
         }
+        Ok(())
     }
 }
 
@@ -123,6 +162,10 @@ impl Exporter {
         self.specs[id].rescale = rescale;
     }
 
+    pub fn set_filter(&mut self, id: usize, filter: UpscaleFilter) {
+        self.specs[id].filter = filter;
+    }
+
     pub fn set_stem(&mut self, id: usize, stem: String) {
         self.specs[id].stem = stem;
     }
@@ -131,9 +174,10 @@ impl Exporter {
         self.specs[id].format = fmt;
     }
 
-    pub fn run_export(&self, xpr: &Xprite) {
+    pub fn run_export(&self, xpr: &Xprite) -> Result<(), String> {
         for s in &self.specs {
-            s.export(xpr, &self.path);
+            s.export(xpr, &self.path)?;
         }
+        Ok(())
     }
 }
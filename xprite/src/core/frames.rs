@@ -32,10 +32,18 @@ impl Frames {
         self.current_frame_idx = idx;
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Layers> {
+        self.frames.iter()
+    }
+
     pub fn count(&self) -> usize {
         self.frames.len()
     }
 
+    pub fn insert_frame(&mut self, idx: usize, frame: Layers) {
+        self.frames.insert(idx.min(self.frames.len()), frame);
+    }
+
     pub fn add_frame_after_current(&mut self) {
         let idx = self.current_frame_idx;
         let copy = self.frame().clone();
@@ -3,10 +3,13 @@ use crate::prelude::*;
 /// History
 ///     ~> Frames
 ///         ~> Layers
+///
+/// A snapshot may carry a document palette, which undo and redo swap with
+/// the current one.
 #[derive(Debug)]
 pub struct History {
-    stack: Vec<Frames>,
-    redos: Vec<Frames>,
+    stack: Vec<(Frames, Option<PaletteGroup>)>,
+    redos: Vec<(Frames, Option<PaletteGroup>)>,
 }
 
 impl Default for History {
@@ -17,7 +20,7 @@ impl Default for History {
 
 impl History {
     pub fn new() -> Self {
-        let stack = vec![Frames::new()];
+        let stack = vec![(Frames::new(), None)];
         let redos = vec![];
         History { stack, redos }
     }
@@ -25,36 +28,58 @@ impl History {
     pub fn duplicate(&mut self) {
         trace!("duplicate history");
         let latest = self.top().clone();
-        self.stack.push(latest);
+        self.stack.push((latest, None));
+    }
+
+    /// like `duplicate`, undoing the new snapshot brings back `palette`
+    pub fn duplicate_with_palette(&mut self, palette: PaletteGroup) {
+        self.duplicate();
+        self.stack.last_mut().unwrap().1 = Some(palette);
+    }
+
+    /// drops the palette of every snapshot, for edits that reorder the palette
+    pub fn forget_palettes(&mut self) {
+        for (_, palette) in self.stack.iter_mut().chain(self.redos.iter_mut()) {
+            *palette = None;
+        }
     }
 
     pub fn top_mut(&mut self) -> &mut Frames {
-        self.stack.last_mut().unwrap()
+        &mut self.stack.last_mut().unwrap().0
     }
 
     pub fn top(&self) -> &Frames {
-        self.stack.last().unwrap()
+        &self.stack.last().unwrap().0
+    }
+
+    /// every snapshot, undo and redo alike
+    pub fn iter(&self) -> impl Iterator<Item = &Frames> {
+        self.stack.iter().chain(self.redos.iter()).map(|(frames, _)| frames)
     }
 
     pub fn clear_redo(&mut self) {
         self.redos.clear();
     }
 
-    pub fn undo(&mut self) {
+    /// Returns the palette of the undone snapshot, to be swapped with the
+    /// current one.
+    pub fn undo(&mut self) -> Option<&mut PaletteGroup> {
         info!("undo");
         // invariant: must have 1 item(empty canvas)
         if self.stack.len() == 1 {
-            return;
+            return None;
         }
 
-        if let Some(last) = self.stack.pop() {
-            self.redos.push(last);
-        }
+        let last = self.stack.pop()?;
+        self.redos.push(last);
+        self.redos.last_mut()?.1.as_mut()
     }
 
-    pub fn redo(&mut self) {
-        if let Some(last) = self.redos.pop() {
-            self.stack.push(last);
-        }
+    /// Returns the palette of the redone snapshot, to be swapped with the
+    /// current one.
+    pub fn redo(&mut self) -> Option<&mut PaletteGroup> {
+        let last = self.redos.pop()?;
+        self.stack.push(last);
+        self.stack.last_mut()?.1.as_mut()
     }
 }
@@ -15,6 +15,7 @@ pub enum Bind {
     CloseXpr(usize),
     Unmapped,
     SetPaletteIndex(usize),
+    SwapColors,
     ToggleSymmetryPanel,
     ToggleExporterPanel,
 }
@@ -254,6 +255,11 @@ impl HotkeyController {
         binds.insert(Action::T(false, false, false, true), Bind::PushTool(ToolType::Texture));
         binds.insert(Action::M(false, false, false, true), Bind::PushTool(ToolType::Marquee));
         binds.insert(Action::A(true, true, false, true), Bind::PushTool(ToolType::AutoShade));
+        binds.insert(Action::B(false, true, false, true), Bind::PushTool(ToolType::Spray));
+        binds.insert(Action::P(false, false, false, true), Bind::PushTool(ToolType::Polygon));
+        binds.insert(Action::I(false, false, false, true), Bind::PushTool(ToolType::IsoBox));
+        binds.insert(Action::U(false, true, false, true), Bind::PushTool(ToolType::Shape));
+        binds.insert(Action::G(false, true, false, true), Bind::PushTool(ToolType::Gradient));
 
         binds.insert(Action::Comma(true, false, false, true), Bind::PushTool(ToolType::Settings));
 
@@ -270,6 +276,8 @@ impl HotkeyController {
         binds.insert(Action::Key6(false, false, true, true), Bind::SetPaletteIndex(5));
         binds.insert(Action::Key7(false, false, true, true), Bind::SetPaletteIndex(6));
         binds.insert(Action::Key8(false, false, true, true), Bind::SetPaletteIndex(7));
+        // swap primary and secondary colors
+        binds.insert(Action::X(false, false, false, true), Bind::SwapColors);
 
         // toggle symmetry panel
         binds.insert(Action::K(true, true, true, true), Bind::ToggleSymmetryPanel);
@@ -4,6 +4,7 @@ pub mod pixels;
 pub mod geom;
 pub mod brush;
 pub mod color;
+pub mod cycle;
 pub mod exporter;
 pub mod frames;
 pub mod history;
@@ -11,5 +12,6 @@ pub mod hotkey;
 pub mod input;
 pub mod outline;
 pub mod palette;
+pub mod ramp;
 pub mod toolbox;
 pub mod xprite;
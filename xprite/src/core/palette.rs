@@ -1,22 +1,35 @@
+use crate::algorithms::quantize::extract_palette;
+use crate::core::cycle::{loop_frames, ColorCycle};
+use crate::core::ramp::color_ramp;
 use crate::prelude::*;
 use image::{self, GenericImageView};
 use indexmap::IndexMap;
 use natord;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::{fs, io, path};
 
 #[derive(Debug)]
 pub struct PaletteGroup {
     pub colors: Rc<RefCell<IndexMap<String, Color>>>,
+    /// primary color, painted with the left mouse button
     pub idx: usize,
+    /// secondary color, painted with the right mouse button
+    pub secondary_idx: usize,
+    /// named groups of entries, each ordered from dark to light
+    pub ramps: IndexMap<String, Vec<usize>>,
+    /// color cycling ranges, earlier ones win where they overlap
+    pub cycles: Vec<ColorCycle>,
 }
 
 #[derive(Debug)]
 pub struct PaletteManager {
-    /// usize is for the index of the selected color within the palette
+    /// library of palettes, only used as a source to copy into the document
     pub palettes: IndexMap<String, PaletteGroup>,
     pub selected_palette_idx: usize,
+    /// palette owned by the document, indexed colors are resolved against it
+    pub document: PaletteGroup,
 }
 
 impl PaletteManager {
@@ -28,29 +41,37 @@ impl PaletteManager {
             let dir = "config/palettes";
             let dir_entries = fs::read_dir(dir);
             if dir_entries.is_err() {
-                return Ok(Self {
-                    palettes,
-                    selected_palette_idx: Default::default(),
-                });
+                return Ok(Self::with_library(palettes));
             }
             let mut entries: Vec<_> = dir_entries?.map(|r| r.unwrap()).collect();
             entries.sort_by(|dir1, dir2| natord::compare(dir1.path().to_str().unwrap(), dir2.path().to_str().unwrap()));
             for entry in &entries {
                 let path = entry.path();
+                if PaletteFormat::from_path(&path).is_none() {
+                    continue;
+                }
                 let palette_name = path.file_stem().expect("file_stem").to_str().expect("file_stem to_str").to_owned();
-                let pal = match path.extension().expect("extension").to_str().expect("to_str") {
-                    "hex" => get_palette_hex(&path)?,
-                    "png" => get_palette_png(&path)?,
-                    _ => continue,
-                };
-                palettes.insert(palette_name, pal);
+                // other files with a palette extension, such as aseprite documents, are skipped
+                match PaletteGroup::load(&path) {
+                    Ok(pal) => {
+                        palettes.insert(palette_name, pal);
+                    }
+                    Err(e) => error!("cannot load palette {:?}: {}", path, e),
+                }
             }
         }
 
-        Ok(Self {
+        Ok(Self::with_library(palettes))
+    }
+
+    /// the document starts out with a copy of the first library palette
+    fn with_library(palettes: IndexMap<String, PaletteGroup>) -> Self {
+        let document = palettes.get_index(0).map(|(_, pal)| pal.duplicate()).unwrap_or_else(pico8);
+        Self {
             palettes,
             selected_palette_idx: Default::default(),
-        })
+            document,
+        }
     }
 
     pub fn find_color(&self, color: Color) -> Option<usize> {
@@ -63,8 +84,9 @@ impl PaletteManager {
             .map(|i| i.0)
     }
 
-    pub fn set_color(&mut self, color: Color) {
-        let idx = match color {
+    /// index of `color` in the document palette, appended if missing
+    fn find_or_insert(&mut self, color: Color) -> usize {
+        match color {
             Color::Indexed(i) => i,
             Color::Rgba(_rgba) => {
                 if let Some(idx) = self.find_color(color) {
@@ -77,21 +99,82 @@ impl PaletteManager {
                     idx
                 }
             }
-        };
+        }
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        let idx = self.find_or_insert(color);
         self.current_palette_mut().idx = idx;
     }
 
+    pub fn set_secondary_color(&mut self, color: Color) {
+        let idx = self.find_or_insert(color);
+        self.current_palette_mut().secondary_idx = idx;
+    }
+
+    /// exchanges primary and secondary colors
+    pub fn swap_colors(&mut self) {
+        let pal = self.current_palette_mut();
+        std::mem::swap(&mut pal.idx, &mut pal.secondary_idx);
+    }
+
     pub fn modify_color(&mut self, idx: usize, color: Color) {
         let pal = self.current_palette_mut();
         *(pal.colors.borrow_mut().get_index_mut(idx).unwrap().1) = color;
     }
 
     pub fn current_palette_mut(&mut self) -> &mut PaletteGroup {
-        self.palettes.get_index_mut(self.selected_palette_idx).unwrap().1
+        &mut self.document
     }
 
     pub fn current_palette(&self) -> &PaletteGroup {
-        &self.palettes.get_index(self.selected_palette_idx).unwrap().1
+        &self.document
+    }
+
+    pub fn selected_library_palette(&self) -> Option<(&String, &PaletteGroup)> {
+        self.palettes.get_index(self.selected_palette_idx)
+    }
+
+    /// replaces the document palette with a copy of the selected library palette,
+    /// indexed pixels keep their index and take on the new colors
+    pub fn copy_to_document(&mut self) -> Result<(), String> {
+        let (_, pal) = self.selected_library_palette().ok_or_else(|| "no library palette selected".to_owned())?;
+        let mut document = pal.duplicate();
+        let last = document.colors.borrow().len().saturating_sub(1);
+        document.idx = self.document.idx.min(last);
+        document.secondary_idx = self.document.secondary_idx.min(last);
+        self.document = document;
+        Ok(())
+    }
+
+    /// adds a copy of the document palette to the library
+    pub fn copy_to_library(&mut self, name: &str) {
+        self.palettes.insert(name.to_owned(), self.document.duplicate());
+    }
+
+    /// adds a palette to the library and selects it
+    pub fn add_to_library(&mut self, name: &str, pal: PaletteGroup) {
+        let (idx, _) = self.palettes.insert_full(name.to_owned(), pal);
+        self.selected_palette_idx = idx;
+    }
+
+    /// imports a palette file into the library, named after the file
+    pub fn load_palette<P: AsRef<path::Path>>(&mut self, p: P) -> io::Result<()> {
+        let name = p
+            .as_ref()
+            .file_stem()
+            .and_then(|i| i.to_str())
+            .ok_or_else(|| invalid("palette file has no name"))?
+            .to_owned();
+        let pal = PaletteGroup::load(&p)?;
+        self.palettes.insert(name, pal);
+        Ok(())
+    }
+
+    /// writes the document palette to disk in `fmt`
+    pub fn save_palette<P: AsRef<path::Path>>(&self, p: P, fmt: PaletteFormat) -> io::Result<()> {
+        let name = p.as_ref().file_stem().and_then(|i| i.to_str()).unwrap_or("document");
+        self.document.save(name, p.as_ref(), fmt)
     }
 
     pub fn current_color(&self) -> (String, Color) {
@@ -124,35 +207,908 @@ fn pico8() -> PaletteGroup {
     colors.insert("indigo".to_owned(), Color::rgba(131, 118, 156, 255));
     colors.insert("pink".to_owned(), Color::rgba(255, 119, 168, 255));
     colors.insert("peach".to_owned(), Color::rgba(255, 204, 170, 255));
-    PaletteGroup {
-        colors: Rc::new(RefCell::new(colors)),
-        idx: 0,
+    PaletteGroup::new(colors)
+}
+
+/// neighbouring ramp entries that are hard to tell apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContrastIssue {
+    pub ramp: String,
+    /// palette indices of the two entries
+    pub entries: (usize, usize),
+    /// WCAG contrast ratio between them
+    pub ratio: f64,
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum PaletteSort {
+    Hue,
+    Luminance,
+    Saturation,
+}
+
+impl PaletteSort {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PaletteSort::Hue => "Hue",
+            PaletteSort::Luminance => "Luminance",
+            PaletteSort::Saturation => "Saturation",
+        }
+    }
+
+    pub const VARIANTS: [PaletteSort; 3] = [PaletteSort::Hue, PaletteSort::Luminance, PaletteSort::Saturation];
+
+    fn key(self, c: XpriteRgba) -> f64 {
+        match self {
+            PaletteSort::Hue => c.to_hsv().0,
+            PaletteSort::Luminance => c.to_lab()[0],
+            PaletteSort::Saturation => c.to_hsv().1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PaletteFormat {
+    HEX,
+    PNG,
+    GPL,
+    PAL,
+    ACT,
+    ASE,
+}
+
+impl PaletteFormat {
+    pub const VARIANTS: [PaletteFormat; 6] = [
+        PaletteFormat::HEX,
+        PaletteFormat::PNG,
+        PaletteFormat::GPL,
+        PaletteFormat::PAL,
+        PaletteFormat::ACT,
+        PaletteFormat::ASE,
+    ];
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            PaletteFormat::HEX => "Hex codes",
+            PaletteFormat::PNG => "PNG",
+            PaletteFormat::GPL => "GIMP",
+            PaletteFormat::PAL => "JASC",
+            PaletteFormat::ACT => "Photoshop color table",
+            PaletteFormat::ASE => "Adobe swatch exchange",
+        }
+    }
+
+    pub fn as_file_extension(self) -> &'static str {
+        match self {
+            PaletteFormat::HEX => "hex",
+            PaletteFormat::PNG => "png",
+            PaletteFormat::GPL => "gpl",
+            PaletteFormat::PAL => "pal",
+            PaletteFormat::ACT => "act",
+            PaletteFormat::ASE => "ase",
+        }
+    }
+
+    pub fn from_path<P: AsRef<path::Path>>(p: P) -> Option<Self> {
+        let ext = p.as_ref().extension()?.to_str()?.to_lowercase();
+        PaletteFormat::VARIANTS.iter().find(|f| f.as_file_extension() == ext).cloned()
+    }
+
+    /// leading bytes of every file in the format
+    fn signature(self) -> Option<&'static [u8]> {
+        match self {
+            PaletteFormat::PNG => Some(b"\x89PNG"),
+            PaletteFormat::GPL => Some(b"GIMP Palette"),
+            PaletteFormat::PAL => Some(b"JASC-PAL"),
+            PaletteFormat::ASE => Some(b"ASEF"),
+            PaletteFormat::HEX | PaletteFormat::ACT => None,
+        }
+    }
+
+    /// Format of a palette file. Formats with a signature are recognized by
+    /// content only, so an Aseprite `.ase` document is not mistaken for an
+    /// Adobe swatch file. The rest go by extension.
+    pub fn detect<P: AsRef<path::Path>>(p: P, bytes: &[u8]) -> Option<Self> {
+        let signed = PaletteFormat::VARIANTS.iter().find(|f| f.signature().map_or(false, |s| bytes.starts_with(s)));
+        signed.cloned().or_else(|| PaletteFormat::from_path(p).filter(|f| f.signature().is_none()))
     }
 }
 
-fn get_palette_hex(p: &path::PathBuf) -> io::Result<PaletteGroup> {
+impl PaletteGroup {
+    pub fn new(colors: IndexMap<String, Color>) -> Self {
+        PaletteGroup {
+            colors: Rc::new(RefCell::new(colors)),
+            idx: 0,
+            secondary_idx: 0,
+            ramps: IndexMap::new(),
+            cycles: vec![],
+        }
+    }
+
+    /// palette of opaque colors named after their hex code
+    pub fn from_rgba(colors: &[XpriteRgba]) -> Self {
+        let mut map = IndexMap::new();
+        for c in colors {
+            insert_named(&mut map, &format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b), Color::Rgba(*c));
+        }
+        Self::new(map)
+    }
+
+    /// picks at most `n` colors from an image
+    pub fn extract_from_image(im: &image::DynamicImage, n: usize, mode: PaletteExtraction) -> Self {
+        let colors: Vec<XpriteRgba> = im
+            .pixels()
+            .map(|(_, _, p)| XpriteRgba {
+                r: p[0],
+                g: p[1],
+                b: p[2],
+                a: p[3],
+            })
+            .collect();
+        Self::from_rgba(&extract_palette(&colors, n, mode))
+    }
+
+    /// Keeps the entries at the old indices in `order`, in that order.
+    /// Returns the new index of every old entry, None if it was dropped.
+    pub fn reorder(&mut self, order: &[usize]) -> Vec<Option<usize>> {
+        let mut colors = self.colors.borrow_mut();
+        let mut remap = vec![None; colors.len()];
+        let mut reordered = IndexMap::new();
+        for &old in order {
+            if let Some((key, color)) = colors.get_index(old) {
+                if remap[old].is_none() {
+                    remap[old] = Some(reordered.len());
+                    reordered.insert(key.to_owned(), *color);
+                }
+            }
+        }
+        *colors = reordered;
+        self.idx = remap.get(self.idx).cloned().and_then(|i| i).unwrap_or(0);
+        self.secondary_idx = remap.get(self.secondary_idx).cloned().and_then(|i| i).unwrap_or(0);
+        for ramp in self.ramps.values_mut() {
+            let mut remapped: Vec<usize> = vec![];
+            for i in ramp.iter().filter_map(|&i| remap.get(i).cloned().and_then(|i| i)) {
+                if !remapped.contains(&i) {
+                    remapped.push(i);
+                }
+            }
+            *ramp = remapped;
+        }
+        self.ramps.retain(|_, ramp| !ramp.is_empty());
+        // a cycle is kept only while its entries stay together and in order
+        self.cycles = self
+            .cycles
+            .iter()
+            .filter_map(|c| {
+                let start = remap.get(c.start).cloned().and_then(|i| i)?;
+                let together = (c.start..=c.end).all(|i| remap.get(i).cloned().and_then(|i| i) == Some(start + i - c.start));
+                if together {
+                    Some(ColorCycle::new(start, start + c.span() - 1, c.speed, c.direction))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        remap
+    }
+
+    /// appends a generated ramp as new entries grouped under `name`
+    pub fn add_ramp(&mut self, name: &str, base: XpriteRgba, params: &RampParams) {
+        let mut colors = self.colors.borrow_mut();
+        let mut ramp = vec![];
+        for (i, c) in color_ramp(base, params).into_iter().enumerate() {
+            ramp.push(colors.len());
+            insert_named(&mut colors, &format!("{}{}", name, i), Color::Rgba(c));
+        }
+        self.ramps.insert(name.to_owned(), ramp);
+    }
+
+    /// entries of the ramp called `name`, from dark to light
+    pub fn ramp(&self, name: &str) -> Option<&[usize]> {
+        self.ramps.get(name).map(Vec::as_slice)
+    }
+
+    /// the ramp an entry belongs to, with the entry's position in it
+    pub fn ramp_of(&self, idx: usize) -> Option<(&str, &[usize], usize)> {
+        self.ramps
+            .iter()
+            .find_map(|(name, ramp)| ramp.iter().position(|&i| i == idx).map(|pos| (name.as_str(), ramp.as_slice(), pos)))
+    }
+
+    /// Entry `steps` further along the ramp of `idx`, toward the light end if
+    /// positive, stops at the ends of the ramp. Entries on no ramp step through
+    /// the whole palette ordered from dark to light.
+    pub fn shade(&self, idx: usize, steps: isize) -> Option<usize> {
+        let by_luminance;
+        let (ramp, pos) = match self.ramp_of(idx) {
+            Some((_, ramp, pos)) => (ramp, pos),
+            None => {
+                by_luminance = self.sorted_order(PaletteSort::Luminance);
+                let pos = by_luminance.iter().position(|&i| i == idx)?;
+                (by_luminance.as_slice(), pos)
+            }
+        };
+        let pos = (pos as isize + steps).max(0).min(ramp.len() as isize - 1);
+        Some(ramp[pos as usize])
+    }
+
+    /// Adjacent entries of every ramp whose luminance contrast, as seen with
+    /// `sim`, is below `min_ratio`.
+    pub fn contrast_report(&self, min_ratio: f64, sim: VisionSimulation) -> Vec<ContrastIssue> {
+        let colors = self.colors.borrow();
+        let seen = |i: usize| colors.get_index(i).and_then(|(_, c)| c.to_rgba(None)).map(|c| sim.apply(c));
+        let mut issues = vec![];
+        for (name, ramp) in &self.ramps {
+            for pair in ramp.windows(2) {
+                if let (Some(a), Some(b)) = (seen(pair[0]), seen(pair[1])) {
+                    let ratio = a.contrast_ratio(b);
+                    if ratio < min_ratio {
+                        issues.push(ContrastIssue {
+                            ramp: name.to_owned(),
+                            entries: (pair[0], pair[1]),
+                            ratio,
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// entry whose color is shown by `idx` after `t` seconds of color cycling
+    pub fn cycled_index(&self, idx: usize, t: f64) -> usize {
+        self.cycles.iter().find_map(|c| c.apply(idx, t)).unwrap_or(idx)
+    }
+
+    /// frames at `fps` for the color cycles to loop
+    pub fn cycle_frames(&self, fps: f64) -> Result<usize, String> {
+        loop_frames(&self.cycles, fps)
+    }
+
+    /// stable sort by a color property, in ascending order
+    pub fn sort(&mut self, by: PaletteSort) -> Vec<Option<usize>> {
+        let order = self.sorted_order(by);
+        self.reorder(&order)
+    }
+
+    /// indices of the entries in the order `sort` would put them in
+    fn sorted_order(&self, by: PaletteSort) -> Vec<usize> {
+        let keys: Vec<f64> = self
+            .colors
+            .borrow()
+            .values()
+            .map(|c| c.to_rgba(None).map(|c| by.key(c)).unwrap_or(0.))
+            .collect();
+        let mut order: Vec<usize> = (0..keys.len()).collect();
+        order.sort_by(|a, b| keys[*a].partial_cmp(&keys[*b]).unwrap());
+        order
+    }
+
+    /// removes repeated colors, their indices are pointed to the first occurrence
+    pub fn dedupe(&mut self) -> Vec<Option<usize>> {
+        let colors: Vec<Color> = self.colors.borrow().values().cloned().collect();
+        let mut first = HashMap::new();
+        let order: Vec<usize> = (0..colors.len()).filter(|&i| *first.entry(colors[i]).or_insert(i) == i).collect();
+        let mut remap = self.reorder(&order);
+        for i in 0..colors.len() {
+            remap[i] = remap[first[&colors[i]]];
+        }
+        remap
+    }
+
+    /// keeps the entries for which `keep` returns true
+    pub fn retain<F: Fn(usize) -> bool>(&mut self, keep: F) -> Vec<Option<usize>> {
+        let len = self.colors.borrow().len();
+        let order: Vec<usize> = (0..len).filter(|&i| keep(i)).collect();
+        self.reorder(&order)
+    }
+
+    /// inserts a new entry so that it ends up at `idx`
+    pub fn insert(&mut self, idx: usize, name: &str, color: Color) -> Vec<Option<usize>> {
+        let len = self.colors.borrow().len();
+        insert_named(&mut self.colors.borrow_mut(), name, color);
+        let mut order: Vec<usize> = (0..len).collect();
+        order.insert(idx.min(len), len);
+        let mut remap = self.reorder(&order);
+        remap.truncate(len);
+        remap
+    }
+
+    /// moves the entry at `from` so that it ends up at `to`
+    pub fn move_entry(&mut self, from: usize, to: usize) -> Vec<Option<usize>> {
+        let len = self.colors.borrow().len();
+        let mut order: Vec<usize> = (0..len).collect();
+        if from < len {
+            let i = order.remove(from);
+            order.insert(to.min(len - 1), i);
+        }
+        self.reorder(&order)
+    }
+
+    /// deep copy, the clone does not share colors with the original
+    pub fn duplicate(&self) -> Self {
+        PaletteGroup {
+            colors: Rc::new(RefCell::new(self.colors.borrow().clone())),
+            idx: self.idx,
+            secondary_idx: self.secondary_idx,
+            ramps: self.ramps.clone(),
+            cycles: self.cycles.clone(),
+        }
+    }
+
+    pub fn as_ase_chunk(&self) -> Option<ase::chunk::PaletteChunk> {
+        let colors = self.colors.borrow();
+        let entries = colors
+            .iter()
+            .map(|(key, col)| {
+                let rgba = col.to_rgba(None)?;
+                let name = color_name(key);
+                Some(ase::chunk::PaletteEntry {
+                    entry_flags: if name.is_empty() { 0 } else { 1 },
+                    red: rgba.r,
+                    green: rgba.g,
+                    blue: rgba.b,
+                    alpha: rgba.a,
+                    name: if name.is_empty() { None } else { Some(name.to_owned()) },
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(ase::chunk::PaletteChunk {
+            palette_size: entries.len() as u32,
+            first_color_index: 0,
+            last_color_index: entries.len().saturating_sub(1) as u32,
+            entries,
+        })
+    }
+
+    pub fn from_ase_chunk(chunk: &ase::chunk::PaletteChunk) -> Self {
+        let mut colors = IndexMap::new();
+        for entry in &chunk.entries {
+            let color = Color::rgba(entry.red, entry.green, entry.blue, entry.alpha);
+            insert_named(&mut colors, entry.name.as_ref().map(String::as_str).unwrap_or(""), color);
+        }
+        Self::new(colors)
+    }
+
+    /// Ramps as text for the ase user data next to the palette chunk, one
+    /// line per ramp with its name, a tab and the entries.
+    pub fn ramps_as_text(&self) -> String {
+        self.ramps
+            .iter()
+            .map(|(name, ramp)| {
+                let name = name.replace(|c| c == '\t' || c == '\n', " ");
+                let entries: Vec<_> = ramp.iter().map(usize::to_string).collect();
+                format!("{}\t{}", name, entries.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// replaces the ramps with the ones in `text`, see `ramps_as_text`,
+    /// entries past the end of the palette are dropped
+    pub fn ramps_from_text(&mut self, text: &str) {
+        let len = self.colors.borrow().len();
+        self.ramps = text
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, '\t');
+                let name = parts.next()?;
+                let ramp: Vec<usize> = parts.next()?.split_whitespace().filter_map(|i| i.parse().ok()).filter(|&i| i < len).collect();
+                if ramp.is_empty() {
+                    None
+                } else {
+                    Some((name.to_owned(), ramp))
+                }
+            })
+            .collect();
+    }
+
+    /// reads a palette file, see [`PaletteFormat::detect`]
+    pub fn load<P: AsRef<path::Path>>(p: P) -> io::Result<Self> {
+        let bytes = fs::read(&p)?;
+        let fmt = PaletteFormat::detect(&p, &bytes).ok_or_else(|| invalid(format!("unknown palette format {:?}", p.as_ref())))?;
+        Self::read(&bytes, fmt)
+    }
+
+    pub fn read(bytes: &[u8], fmt: PaletteFormat) -> io::Result<Self> {
+        let colors = match fmt {
+            PaletteFormat::HEX => read_hex(&to_text(bytes)?)?,
+            PaletteFormat::PNG => read_png(bytes)?,
+            PaletteFormat::GPL => read_gpl(&to_text(bytes)?)?,
+            PaletteFormat::PAL => read_pal(&to_text(bytes)?)?,
+            PaletteFormat::ACT => read_act(bytes)?,
+            PaletteFormat::ASE => read_ase(bytes)?,
+        };
+        Ok(Self::new(colors))
+    }
+
+    /// writes the palette to disk in `fmt`
+    pub fn save<P: AsRef<path::Path>>(&self, name: &str, p: P, fmt: PaletteFormat) -> io::Result<()> {
+        info!("saving palette {} to {:?}", name, p.as_ref().as_os_str());
+        let bytes = self.write(name, fmt)?;
+        fs::write(p, bytes)
+    }
+
+    pub fn write(&self, name: &str, fmt: PaletteFormat) -> io::Result<Vec<u8>> {
+        let colors = self.colors.borrow();
+        let mut entries = Vec::with_capacity(colors.len());
+        for (key, col) in colors.iter() {
+            let rgba = col.to_rgba(None).ok_or_else(|| invalid(format!("palette color {} is not rgba", key)))?;
+            entries.push((color_name(key), rgba));
+        }
+        Ok(match fmt {
+            PaletteFormat::HEX => write_hex(&entries).into_bytes(),
+            PaletteFormat::PNG => write_png(&entries)?,
+            PaletteFormat::GPL => write_gpl(name, &entries).into_bytes(),
+            PaletteFormat::PAL => write_pal(&entries).into_bytes(),
+            PaletteFormat::ACT => write_act(&entries)?,
+            PaletteFormat::ASE => write_ase(&entries),
+        })
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn to_text(bytes: &[u8]) -> io::Result<String> {
+    String::from_utf8(bytes.to_vec()).map_err(invalid)
+}
+
+/// strips the imgui id suffix from a color key
+fn color_name(key: &str) -> &str {
+    key.split("##").next().unwrap_or("")
+}
+
+/// color keys have to be unique, repeated or missing names get a suffix
+fn insert_named(colors: &mut IndexMap<String, Color>, name: &str, color: Color) {
+    let mut key = name.to_owned();
+    let mut n = colors.len();
+    while key.is_empty() || colors.contains_key(&key) {
+        key = format!("{}##{}", name, n);
+        n += 1;
+    }
+    colors.insert(key, color);
+}
+
+fn read_hex(text: &str) -> io::Result<IndexMap<String, Color>> {
     let mut colors = IndexMap::new();
-    let cols = fs::read_to_string(p)?;
-    for col in cols.lines() {
-        let color = Color::from_hex(&col[1..]).unwrap_or_else(|_| panic!("Cannot decode hex in file {:?}", p));
-        colors.insert(col.to_owned(), color);
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let hex = line.trim_start_matches('#');
+        if hex.len() != 6 {
+            return Err(invalid(format!("cannot decode hex color {}", line)));
+        }
+        let color = Color::from_hex(hex).map_err(|_| invalid(format!("cannot decode hex color {}", line)))?;
+        insert_named(&mut colors, line, color);
     }
-    Ok(PaletteGroup {
-        colors: Rc::new(RefCell::new(colors)),
-        idx: 0,
-    })
+    Ok(colors)
+}
+
+fn write_hex(entries: &[(&str, XpriteRgba)]) -> String {
+    entries.iter().map(|(_, c)| format!("#{:02x}{:02x}{:02x}\n", c.r, c.g, c.b)).collect()
 }
 
-fn get_palette_png(p: &path::PathBuf) -> io::Result<PaletteGroup> {
+fn read_png(bytes: &[u8]) -> io::Result<IndexMap<String, Color>> {
     let mut colors = IndexMap::new();
-    let img = image::open(p).unwrap();
+    let img = image::load_from_memory(bytes).map_err(invalid)?;
     for pix in img.pixels() {
         let color = pix.2;
-        let my_color = Color::rgba(color[0], color[1], color[2], 255);
+        let my_color = Color::rgba(color[0], color[1], color[2], color[3]);
         colors.insert(format!("color##{},{}", pix.0, pix.1), my_color);
     }
-    Ok(PaletteGroup {
-        colors: Rc::new(RefCell::new(colors)),
-        idx: 0,
-    })
+    Ok(colors)
+}
+
+/// one pixel per color in a single row
+fn write_png(entries: &[(&str, XpriteRgba)]) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    let data: Vec<u8> = entries.iter().flat_map(|(_, c)| vec![c.r, c.g, c.b, c.a]).collect();
+    image::png::PNGEncoder::new(&mut bytes).encode(&data, entries.len() as u32, 1, image::ColorType::RGBA(8))?;
+    Ok(bytes)
+}
+
+/// GIMP palette, colors are `r g b name` lines after the header
+fn read_gpl(text: &str) -> io::Result<IndexMap<String, Color>> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err(invalid("missing GIMP Palette header"));
+    }
+    let mut colors = IndexMap::new();
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let mut channel = || -> io::Result<u8> {
+            parts
+                .next()
+                .and_then(|i| i.parse().ok())
+                .ok_or_else(|| invalid(format!("cannot decode gpl color {}", line)))
+        };
+        let (r, g, b) = (channel()?, channel()?, channel()?);
+        let name = parts.collect::<Vec<_>>().join(" ");
+        insert_named(&mut colors, &name, Color::rgba(r, g, b, 255));
+    }
+    Ok(colors)
+}
+
+fn write_gpl(name: &str, entries: &[(&str, XpriteRgba)]) -> String {
+    let mut ret = format!("GIMP Palette\nName: {}\nColumns: 8\n#\n", name);
+    for (name, c) in entries {
+        ret.push_str(&format!("{:3} {:3} {:3}\t{}\n", c.r, c.g, c.b, name));
+    }
+    ret
+}
+
+/// JASC (Paint Shop Pro) palette
+fn read_pal(text: &str) -> io::Result<IndexMap<String, Color>> {
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some("JASC-PAL") {
+        return Err(invalid("missing JASC-PAL header"));
+    }
+    let _version = lines.next();
+    let count: usize = lines
+        .next()
+        .and_then(|i| i.parse().ok())
+        .ok_or_else(|| invalid("cannot decode color count"))?;
+    let mut colors = IndexMap::new();
+    for line in lines.filter(|l| !l.is_empty()).take(count) {
+        let rgb: Vec<u8> = line.split_whitespace().filter_map(|i| i.parse().ok()).collect();
+        if rgb.len() < 3 {
+            return Err(invalid(format!("cannot decode pal color {}", line)));
+        }
+        insert_named(&mut colors, "", Color::rgba(rgb[0], rgb[1], rgb[2], 255));
+    }
+    Ok(colors)
+}
+
+fn write_pal(entries: &[(&str, XpriteRgba)]) -> String {
+    let mut ret = format!("JASC-PAL\r\n0100\r\n{}\r\n", entries.len());
+    for (_, c) in entries {
+        ret.push_str(&format!("{} {} {}\r\n", c.r, c.g, c.b));
+    }
+    ret
+}
+
+/// Photoshop color table, 256 rgb triplets optionally followed by
+/// the number of colors and the transparent index
+fn read_act(bytes: &[u8]) -> io::Result<IndexMap<String, Color>> {
+    if bytes.len() < 768 {
+        return Err(invalid("act palette is shorter than 768 bytes"));
+    }
+    let (count, transparent) = if bytes.len() >= 772 {
+        let count = u16::from_be_bytes([bytes[768], bytes[769]]) as usize;
+        let transparent = u16::from_be_bytes([bytes[770], bytes[771]]) as usize;
+        (count.min(256), transparent)
+    } else {
+        (256, 0xFFFF)
+    };
+    let mut colors = IndexMap::new();
+    for (i, rgb) in bytes[..768].chunks(3).take(count).enumerate() {
+        let a = if i == transparent { 0 } else { 255 };
+        insert_named(&mut colors, "", Color::rgba(rgb[0], rgb[1], rgb[2], a));
+    }
+    Ok(colors)
+}
+
+fn write_act(entries: &[(&str, XpriteRgba)]) -> io::Result<Vec<u8>> {
+    if entries.len() > 256 {
+        return Err(invalid("act palettes hold at most 256 colors"));
+    }
+    let mut bytes = vec![0; 772];
+    for (i, (_, c)) in entries.iter().enumerate() {
+        bytes[i * 3..i * 3 + 3].copy_from_slice(&[c.r, c.g, c.b]);
+    }
+    let transparent = entries.iter().position(|(_, c)| c.a == 0).map(|i| i as u16).unwrap_or(0xFFFF);
+    bytes[768..770].copy_from_slice(&(entries.len() as u16).to_be_bytes());
+    bytes[770..772].copy_from_slice(&transparent.to_be_bytes());
+    Ok(bytes)
+}
+
+/// Adobe swatch exchange, groups are flattened into a single palette
+fn read_ase(bytes: &[u8]) -> io::Result<IndexMap<String, Color>> {
+    let eof = || invalid("unexpected end of ase swatch file");
+    let u16_at = |i: usize| bytes.get(i..i + 2).map(|b| u16::from_be_bytes([b[0], b[1]])).ok_or_else(eof);
+    let u32_at = |i: usize| bytes.get(i..i + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(eof);
+    let f32_at = |i: usize| u32_at(i).map(f32::from_bits);
+
+    if bytes.get(..4) != Some(b"ASEF") {
+        return Err(invalid("missing ASEF header"));
+    }
+    let n_blocks = u32_at(8)?;
+    let mut colors = IndexMap::new();
+    let mut pos = 12;
+    for _ in 0..n_blocks {
+        let block_type = u16_at(pos)?;
+        let block_len = u32_at(pos + 2)? as usize;
+        let start = pos + 6;
+        pos = start + block_len;
+        if block_type != 0x0001 {
+            // group start and end
+            continue;
+        }
+        let name_len = u16_at(start)? as usize;
+        let name: Vec<u16> = (0..name_len).map(|i| u16_at(start + 2 + i * 2)).collect::<io::Result<_>>()?;
+        let name = String::from_utf16_lossy(&name).trim_end_matches('\0').to_owned();
+        let model_at = start + 2 + name_len * 2;
+        let model = bytes.get(model_at..model_at + 4).ok_or_else(eof)?;
+        let v = |i: usize| f32_at(model_at + 4 + i * 4);
+        let (r, g, b) = match model {
+            b"RGB " => (v(0)?, v(1)?, v(2)?),
+            b"Gray" => (v(0)?, v(0)?, v(0)?),
+            b"CMYK" => {
+                let k = 1. - v(3)?;
+                ((1. - v(0)?) * k, (1. - v(1)?) * k, (1. - v(2)?) * k)
+            }
+            b"LAB " => {
+                let rgb = XpriteRgba::from_lab([f64::from(v(0)?) * 100., f64::from(v(1)?), f64::from(v(2)?)], 255);
+                (f32::from(rgb.r) / 255., f32::from(rgb.g) / 255., f32::from(rgb.b) / 255.)
+            }
+            _ => return Err(invalid(format!("unknown ase color model {:?}", model))),
+        };
+        let channel = |c: f32| (c.max(0.).min(1.) * 255.).round() as u8;
+        insert_named(&mut colors, &name, Color::rgba(channel(r), channel(g), channel(b), 255));
+    }
+    Ok(colors)
+}
+
+fn write_ase(entries: &[(&str, XpriteRgba)]) -> Vec<u8> {
+    let mut bytes = b"ASEF".to_vec();
+    bytes.extend_from_slice(&1u16.to_be_bytes());
+    bytes.extend_from_slice(&0u16.to_be_bytes());
+    bytes.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+    for (name, c) in entries {
+        let mut name: Vec<u16> = name.encode_utf16().collect();
+        name.push(0);
+        let block_len = 2 + name.len() * 2 + 4 + 3 * 4 + 2;
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&(block_len as u32).to_be_bytes());
+        bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        for i in name {
+            bytes.extend_from_slice(&i.to_be_bytes());
+        }
+        bytes.extend_from_slice(b"RGB ");
+        for channel in &[c.r, c.g, c.b] {
+            bytes.extend_from_slice(&(f32::from(*channel) / 255.).to_bits().to_be_bytes());
+        }
+        // normal (not global or spot) color
+        bytes.extend_from_slice(&2u16.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_palette_roundtrip() {
+        use super::*;
+        let pal = pico8();
+        for fmt in PaletteFormat::VARIANTS.iter() {
+            let bytes = pal.write("pico8", *fmt).unwrap();
+            let read = PaletteGroup::read(&bytes, *fmt).unwrap();
+            let expected: Vec<_> = pal.colors.borrow().values().cloned().collect();
+            let got: Vec<_> = read.colors.borrow().values().cloned().collect();
+            assert_eq!(expected, got, "{:?}", fmt);
+        }
+    }
+
+    #[test]
+    fn test_detect_format() {
+        use super::*;
+        let pal = pico8();
+        let swatch = pal.write("pico8", PaletteFormat::ASE).unwrap();
+        assert_eq!(Some(PaletteFormat::ASE), PaletteFormat::detect("pico8.ase", &swatch));
+        // an aseprite document is not a swatch file
+        let document = [0x00, 0x10, 0x00, 0x00, 0xE0, 0xA5];
+        assert_eq!(None, PaletteFormat::detect("sprite.ase", &document));
+        // content wins over a wrong extension
+        let gpl = pal.write("pico8", PaletteFormat::GPL).unwrap();
+        assert_eq!(Some(PaletteFormat::GPL), PaletteFormat::detect("pico8.txt", &gpl));
+        assert_eq!(Some(PaletteFormat::HEX), PaletteFormat::detect("pico8.hex", b"#000000\n"));
+        assert_eq!(Some(PaletteFormat::ACT), PaletteFormat::detect("pico8.act", &[0; 772]));
+
+        // png palettes keep their alpha
+        let mut colors = IndexMap::new();
+        colors.insert("clear".to_owned(), Color::rgba(10, 20, 30, 0));
+        colors.insert("half".to_owned(), Color::rgba(10, 20, 30, 128));
+        let bytes = PaletteGroup::new(colors).write("", PaletteFormat::PNG).unwrap();
+        let read = PaletteGroup::read(&bytes, PaletteFormat::PNG).unwrap();
+        let alpha: Vec<_> = read.colors.borrow().values().map(|c| c.to_rgba(None).unwrap().a).collect();
+        assert_eq!(vec![0, 128], alpha);
+    }
+
+    #[test]
+    fn test_palette_names() {
+        use super::*;
+        let pal = pico8();
+        for fmt in &[PaletteFormat::GPL, PaletteFormat::ASE] {
+            let bytes = pal.write("pico8", *fmt).unwrap();
+            let read = PaletteGroup::read(&bytes, *fmt).unwrap();
+            assert_eq!(read.colors.borrow().get_index(3).unwrap().0, "dark-green");
+        }
+    }
+
+    #[test]
+    fn test_read_gpl() {
+        use super::*;
+        let gpl = "GIMP Palette\nName: test\nColumns: 2\n# comment\n255   0   0\tRed\n  0 255   0\tRed\n0 0 255\n";
+        let colors = read_gpl(gpl).unwrap();
+        let keys: Vec<_> = colors.keys().cloned().collect();
+        assert_eq!(keys, vec!["Red", "Red##1", "##2"]);
+        assert_eq!(colors.get_index(2).unwrap().1, &Color::rgba(0, 0, 255, 255));
+    }
+
+    #[test]
+    fn test_document_palette() {
+        use super::*;
+        let mut library = IndexMap::new();
+        library.insert("pico8".to_owned(), pico8());
+        library.insert("red".to_owned(), PaletteGroup::new(vec![("red".to_owned(), Color::red())].into_iter().collect()));
+        let mut pm = PaletteManager::with_library(library);
+        let black = Color::rgba(0, 0, 0, 255);
+
+        // browsing the library leaves the document alone
+        pm.selected_palette_idx = 1;
+        assert_eq!(pm.current_palette().colors.borrow().get_index(0).unwrap().1, &black);
+
+        pm.copy_to_document().unwrap();
+        assert_eq!(pm.current_palette().colors.borrow().get_index(0).unwrap().1, &Color::red());
+
+        // edits do not leak back into the library
+        pm.modify_color(0, Color::blue());
+        assert_eq!(pm.palettes["red"].colors.borrow().get_index(0).unwrap().1, &Color::red());
+    }
+
+    #[test]
+    fn test_reorder() {
+        use super::*;
+        let mut colors = IndexMap::new();
+        colors.insert("a".to_owned(), Color::red());
+        colors.insert("b".to_owned(), Color::blue());
+        colors.insert("c".to_owned(), Color::red());
+        let mut pal = PaletteGroup::new(colors);
+        pal.idx = 2;
+        assert_eq!(vec![Some(0), Some(1), Some(0)], pal.dedupe());
+        assert_eq!(0, pal.idx);
+        assert_eq!(vec![Some(1), Some(2)], pal.insert(0, "", Color::black()));
+        assert_eq!(pal.colors.borrow().get_index(0).unwrap().1, &Color::black());
+        assert_eq!(vec![Some(2), Some(0), Some(1)], pal.move_entry(0, 2));
+        assert_eq!(vec![Some(0), None, Some(1)], pal.retain(|i| i != 1));
+        let keys: Vec<_> = pal.colors.borrow().keys().cloned().collect();
+        assert_eq!(vec!["a", "##2"], keys);
+    }
+
+    #[test]
+    fn test_reorder_cycles() {
+        use super::*;
+        let mut pal = pico8();
+        pal.cycles.push(ColorCycle::new(1, 3, 1., CycleDirection::Forward));
+        // moving the run as a whole keeps the cycle
+        pal.move_entry(10, 0);
+        assert_eq!((pal.cycles[0].start, pal.cycles[0].end), (2, 4));
+        // splitting it up drops the cycle
+        pal.sort(PaletteSort::Luminance);
+        assert!(pal.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_secondary_color() {
+        use super::*;
+        let mut pm = PaletteManager::with_library(IndexMap::new());
+        pm.set_color(Color::Indexed(3));
+        pm.set_secondary_color(Color::rgba(1, 2, 3, 255));
+        assert_eq!(16, pm.current_palette().secondary_idx);
+        pm.swap_colors();
+        assert_eq!((16, 3), (pm.current_palette().idx, pm.current_palette().secondary_idx));
+        // both slots follow palette edits
+        pm.current_palette_mut().move_entry(16, 0);
+        assert_eq!((0, 4), (pm.current_palette().idx, pm.current_palette().secondary_idx));
+    }
+
+    #[test]
+    fn test_ramps() {
+        use super::*;
+        let mut pal = pico8();
+        let skin = XpriteRgba {
+            r: 230,
+            g: 170,
+            b: 140,
+            a: 255,
+        };
+        pal.add_ramp("skin", skin, &RampParams::default());
+        assert_eq!(Some(&[16, 17, 18, 19, 20][..]), pal.ramp("skin"));
+        assert_eq!(Some(("skin", &[16, 17, 18, 19, 20][..], 2)), pal.ramp_of(18));
+        assert_eq!(None, pal.ramp_of(3));
+        assert_eq!(Some(19), pal.shade(18, 1));
+        assert_eq!(Some(16), pal.shade(18, -5));
+        assert_eq!(Some(20), pal.shade(20, 1));
+        assert_eq!(None, pal.shade(99, 1));
+
+        // ramps follow palette edits
+        pal.move_entry(18, 0);
+        assert_eq!(Some(&[17, 18, 0, 19, 20][..]), pal.ramp("skin"));
+        pal.retain(|i| i < 16);
+        assert_eq!(Some(&[0][..]), pal.ramp("skin"));
+        pal.retain(|i| i != 0);
+        assert_eq!(None, pal.ramp("skin"));
+    }
+
+    #[test]
+    fn test_shade_without_ramp() {
+        use super::*;
+        let grey = |v| XpriteRgba { r: v, g: v, b: v, a: 255 };
+        let pal = PaletteGroup::from_rgba(&[grey(255), grey(0), grey(128), grey(64)]);
+        assert_eq!(Some(3), pal.shade(1, 1));
+        assert_eq!(Some(0), pal.shade(3, 2));
+        assert_eq!(Some(0), pal.shade(0, 1));
+        assert_eq!(Some(1), pal.shade(2, -5));
+    }
+
+    #[test]
+    fn test_ramps_text() {
+        use super::*;
+        let mut pal = pico8();
+        pal.ramps.insert("grey".to_owned(), vec![0, 5, 6, 7]);
+        pal.ramps.insert("warm".to_owned(), vec![2, 8, 9]);
+        let text = pal.ramps_as_text();
+        assert_eq!(text, "grey\t0 5 6 7\nwarm\t2 8 9");
+        let mut loaded = pico8();
+        loaded.ramps_from_text(&text);
+        assert_eq!(loaded.ramps, pal.ramps);
+        // entries past the palette are dropped, so are ramps left empty
+        loaded.ramps_from_text("grey\t0 99\nnone\t40\nbroken");
+        assert_eq!(loaded.ramp("grey"), Some(&[0][..]));
+        assert_eq!(loaded.ramps.len(), 1);
+    }
+
+    #[test]
+    fn test_contrast_report() {
+        use super::*;
+        let mut pal = PaletteGroup::from_rgba(&[
+            XpriteRgba { r: 40, g: 40, b: 40, a: 255 },
+            XpriteRgba { r: 44, g: 44, b: 44, a: 255 },
+            XpriteRgba { r: 200, g: 0, b: 0, a: 255 },
+            XpriteRgba { r: 0, g: 120, b: 0, a: 255 },
+        ]);
+        pal.ramps.insert("grey".to_owned(), vec![0, 1, 2]);
+        // red and green differ in hue only, luminance is about the same
+        pal.ramps.insert("christmas".to_owned(), vec![2, 3]);
+        let issues = pal.contrast_report(1.2, VisionSimulation::Normal);
+        let flagged: Vec<_> = issues.iter().map(|i| (i.ramp.as_str(), i.entries)).collect();
+        assert_eq!(flagged, vec![("grey", (0, 1)), ("christmas", (2, 3))]);
+        assert!(issues.iter().all(|i| i.ratio >= 1. && i.ratio < 1.2));
+        assert!(pal.contrast_report(1., VisionSimulation::Normal).is_empty());
+    }
+
+    #[test]
+    fn test_cycles() {
+        use super::*;
+        use crate::core::cycle::CycleDirection;
+        let mut pal = pico8();
+        pal.cycles.push(ColorCycle::new(12, 14, 2., CycleDirection::Forward));
+        assert_eq!(pal.cycled_index(3, 10.), 3);
+        assert_eq!(pal.cycled_index(12, 0.), 12);
+        assert_eq!(pal.cycled_index(12, 0.5), 14);
+        assert_eq!(pal.cycle_frames(2.), Ok(3));
+
+        // cycles follow palette edits
+        pal.move_entry(0, 15);
+        assert_eq!((11, 13), (pal.cycles[0].start, pal.cycles[0].end));
+        pal.retain(|i| i != 11);
+        assert!(pal.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_act_transparent() {
+        use super::*;
+        let mut colors = IndexMap::new();
+        colors.insert("a".to_owned(), Color::rgba(1, 2, 3, 255));
+        colors.insert("b".to_owned(), Color::rgba(0, 0, 0, 0));
+        let bytes = PaletteGroup::new(colors).write("", PaletteFormat::ACT).unwrap();
+        assert_eq!(772, bytes.len());
+        let read = read_act(&bytes).unwrap();
+        assert_eq!(2, read.len());
+        assert_eq!(read.get_index(1).unwrap().1, &Color::rgba(0, 0, 0, 0));
+    }
 }
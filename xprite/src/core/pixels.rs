@@ -5,6 +5,7 @@ use crate::algorithms::{
     rotsprite::rotsprite,
     selective_antialias::selective_antialias,
     sorter::sort_path,
+    upscale::{upscale, UpscaleFilter},
 };
 use crate::prelude::*;
 use fnv::FnvBuildHasher;
@@ -252,6 +253,10 @@ impl Pixels {
         rotsprite(&self, angle, pivot)
     }
 
+    pub fn upscale(&self, filter: UpscaleFilter, scale: u32, xpr: Option<&Xprite>) -> Pixels {
+        upscale(&self, filter, scale, xpr)
+    }
+
     pub fn extend(&mut self, other: &Pixels) {
         for i in other.0.iter() {
             self.0.replace(*i);
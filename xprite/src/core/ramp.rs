@@ -0,0 +1,90 @@
+//! Shading ramp generator
+//!
+//! Spreads a base color into darker and lighter steps. Hue is rotated toward
+//! warm yellow in the highlights and toward cool blue in the shadows, the way
+//! pixel artists hue shift by hand.
+use crate::prelude::*;
+
+const WARM_HUE: f64 = 60.;
+const COOL_HUE: f64 = 240.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RampParams {
+    /// number of colors, the base color sits in the middle
+    pub steps: usize,
+    /// how far the lightest step goes toward full brightness, in [0, 1]
+    pub light: f64,
+    /// how far the darkest step goes toward black, in [0, 1]
+    pub dark: f64,
+    /// degrees of hue rotation at either end,
+    /// negative values shift highlights cool and shadows warm instead
+    pub hue_shift: f64,
+    /// saturation added at either end, spread along a quadratic curve,
+    /// negative values wash out highlights and shadows
+    pub saturation: f64,
+}
+
+impl Default for RampParams {
+    fn default() -> Self {
+        Self {
+            steps: 5,
+            light: 0.7,
+            dark: 0.7,
+            hue_shift: 20.,
+            saturation: -0.1,
+        }
+    }
+}
+
+/// ramp of `params.steps` colors ordered from dark to light
+pub fn color_ramp(base: XpriteRgba, params: &RampParams) -> Vec<XpriteRgba> {
+    let (h, s, v) = base.to_hsv();
+    let n = params.steps;
+    (0..n)
+        .map(|i| {
+            // -1 at the darkest step, 1 at the lightest
+            let t = if n > 1 { 2. * i as f64 / (n - 1) as f64 - 1. } else { 0. };
+            let value = if t > 0. { v + t * params.light * (1. - v) } else { v + t * params.dark * v };
+            let (warm, cool) = if params.hue_shift >= 0. { (WARM_HUE, COOL_HUE) } else { (COOL_HUE, WARM_HUE) };
+            let target = if t > 0. { warm } else { cool };
+            let hue = rotate_toward(h, target, t.abs() * params.hue_shift.abs());
+            let saturation = (s + params.saturation * t * t).max(0.).min(1.);
+            XpriteRgba::from_hsv(hue, saturation, value.max(0.).min(1.), base.a)
+        })
+        .collect()
+}
+
+/// rotates hue `h` toward `target` by at most `amount` degrees
+fn rotate_toward(h: f64, target: f64, amount: f64) -> f64 {
+    // signed shortest distance in (-180, 180]
+    let d = (target - h + 180.).rem_euclid(360.) - 180.;
+    let step = d.abs().min(amount) * d.signum();
+    (h + step).rem_euclid(360.)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_color_ramp() {
+        use super::*;
+        let base = XpriteRgba { r: 200, g: 60, b: 60, a: 255 };
+        let ramp = color_ramp(base, &RampParams::default());
+        assert_eq!(5, ramp.len());
+        assert_eq!(base, ramp[2]);
+        // brightness is monotonic
+        for w in ramp.windows(2) {
+            assert!(w[0].to_hsv().2 < w[1].to_hsv().2);
+        }
+        // red highlights lean toward yellow, shadows toward magenta
+        assert!(ramp[4].to_hsv().0 > 0. && ramp[4].to_hsv().0 < 60.);
+        assert!(ramp[0].to_hsv().0 > 300.);
+    }
+
+    #[test]
+    fn test_rotate_toward() {
+        use super::*;
+        assert_eq!(rotate_toward(350., 60., 30.), 20.);
+        assert_eq!(rotate_toward(50., 60., 30.), 60.);
+        assert_eq!(rotate_toward(0., 240., 30.), 330.);
+    }
+}
@@ -4,8 +4,9 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::tools::{
-    autoshade::AutoShade, color_picker::ColorPicker, ellipse::Ellipse, eraser::Eraser, line::Line, marquee::Marquee, paint_bucket::PaintBucket, pencil::Pencil,
-    rect::Rect, symmetry::Symmetry, texture::Texture, vector::Vector, Tool,
+    autoshade::AutoShade, color_picker::ColorPicker, ellipse::Ellipse, eraser::Eraser, gradient::Gradient, iso_box::IsoBox, line::Line, marquee::Marquee,
+    paint_bucket::PaintBucket, pencil::Pencil, polygon::Polygon, rect::Rect, shape::Shape, spray::Spray, symmetry::Symmetry, texture::Texture, vector::Vector,
+    Tool,
 };
 
 #[derive(Default)]
@@ -22,6 +23,11 @@ pub struct Toolbox {
     pub marquee: Rc<RefCell<Marquee>>,
     pub symmetry: Rc<RefCell<Symmetry>>,
     pub autoshade: Rc<RefCell<AutoShade>>,
+    pub spray: Rc<RefCell<Spray>>,
+    pub polygon: Rc<RefCell<Polygon>>,
+    pub iso_box: Rc<RefCell<IsoBox>>,
+    pub shape: Rc<RefCell<Shape>>,
+    pub gradient: Rc<RefCell<Gradient>>,
 
     pub selected: ToolType,
     pub tool_stack: Vec<ToolType>,
@@ -41,6 +47,11 @@ impl Toolbox {
         let marquee = Rc::new(RefCell::new(Marquee::new()));
         let symmetry = Rc::new(RefCell::new(Symmetry::new()));
         let autoshade = Rc::new(RefCell::new(AutoShade::new()));
+        let spray = Rc::new(RefCell::new(Spray::new()));
+        let polygon = Rc::new(RefCell::new(Polygon::new()));
+        let iso_box = Rc::new(RefCell::new(IsoBox::new()));
+        let shape = Rc::new(RefCell::new(Shape::new()));
+        let gradient = Rc::new(RefCell::new(Gradient::new()));
 
         let selected = ToolType::Pencil;
 
@@ -60,6 +71,11 @@ impl Toolbox {
             marquee,
             symmetry,
             autoshade,
+            spray,
+            polygon,
+            iso_box,
+            shape,
+            gradient,
         }
     }
 
@@ -82,6 +98,11 @@ impl Toolbox {
             Marquee => self.marquee.clone(),
             Symmetry => self.symmetry.clone(),
             AutoShade => self.autoshade.clone(),
+            Spray => self.spray.clone(),
+            Polygon => self.polygon.clone(),
+            IsoBox => self.iso_box.clone(),
+            Shape => self.shape.clone(),
+            Gradient => self.gradient.clone(),
         }
     }
 
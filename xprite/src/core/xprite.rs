@@ -1,12 +1,20 @@
+use crate::algorithms::adjust::adjust_pixels;
+use crate::algorithms::quantize::{extract_palette, remap_to_palette};
+use crate::algorithms::replace::replace_color;
+use crate::algorithms::upscale::upscale_image;
 use crate::prelude::*;
 use crate::rendering::Renderer;
 use img::GenericImageView;
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::path::Path;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
+/// adjusted pixels per layer and adjusted palette entries, see `Xprite::adjusted_pixels`
+type AdjustedPixels = (Vec<(Rc<RefCell<Layer>>, Pixels)>, HashMap<usize, Color>);
+
 pub struct Xprite {
     pub name: String,
     history: History,
@@ -18,7 +26,10 @@ pub struct Xprite {
 
     pub canvas: Canvas,
     pub color_picker_color: Option<Color>,
+    /// seconds of color cycling played so far, None when stopped
+    pub cycle_time: Option<f64>,
     pub palette: PaletteManager,
+    pub brushes: BrushLibrary,
 
     pub toolbox: Toolbox,
     pub cursor: Pixels,
@@ -27,6 +38,8 @@ pub struct Xprite {
     pub log: Arc<Mutex<String>>,
 
     redraw: bool,
+    /// adjusted pixels drawn over their layers, the document is left untouched
+    adjust_preview: Option<Vec<(Rc<RefCell<Layer>>, Pixels)>>,
 }
 
 impl Default for Xprite {
@@ -35,7 +48,9 @@ impl Default for Xprite {
         Self {
             name: "Untitled".to_owned(),
             palette: palette_man,
+            brushes: BrushLibrary::new(),
             color_picker_color: None,
+            cycle_time: None,
             history: Default::default(),
             im_buf: Default::default(),
             line_buf: Default::default(),
@@ -47,6 +62,7 @@ impl Default for Xprite {
             last_mouse_pos: Default::default(),
             log: Arc::new(Mutex::new(String::new())),
             redraw: true,
+            adjust_preview: None,
         }
     }
 }
@@ -66,12 +82,18 @@ impl Xprite {
     }
 
     pub fn undo(&mut self) {
-        self.history.undo();
+        self.adjust_preview = None;
+        if let Some(palette) = self.history.undo() {
+            swap_palette(&mut self.palette.document, palette);
+        }
         self.set_redraw(true);
     }
 
     pub fn redo(&mut self) {
-        self.history.redo();
+        self.adjust_preview = None;
+        if let Some(palette) = self.history.redo() {
+            swap_palette(&mut self.palette.document, palette);
+        }
         self.set_redraw(true);
     }
 
@@ -156,6 +178,18 @@ impl Xprite {
         Color::Indexed(self.palette.current_palette().idx)
     }
 
+    pub fn secondary_color(&self) -> Color {
+        Color::Indexed(self.palette.current_palette().secondary_idx)
+    }
+
+    /// color painted while `button` is held, primary when no button is down
+    pub fn color_for(&self, button: Option<InputItem>) -> Color {
+        match button {
+            Some(InputItem::Right) => self.secondary_color(),
+            _ => self.color(),
+        }
+    }
+
     pub fn new_frame(&mut self) {
         self.pixels_mut().clear();
         self.bz_buf.clear();
@@ -242,6 +276,10 @@ impl Xprite {
                 let tool = self.toolbox.eraser.borrow_mut();
                 Some(tool.brush.clone())
             }
+            ToolType::Polygon => {
+                let tool = self.toolbox.polygon.borrow_mut();
+                Some(tool.brush.clone())
+            }
             _ => None,
         }
     }
@@ -262,6 +300,11 @@ impl Xprite {
                 tool.brush = brush;
                 Ok(())
             }
+            ToolType::Polygon => {
+                let mut tool = self.toolbox.polygon.borrow_mut();
+                tool.brush = brush;
+                Ok(())
+            }
             _ => Err("No brush attached to tool".to_owned()),
         }
     }
@@ -269,6 +312,376 @@ impl Xprite {
     pub fn last_tool(&self) -> ToolType {
         *self.toolbox.tool_stack.last().unwrap()
     }
+
+    /// area selected with the marquee tool
+    pub fn selection(&self) -> Option<Rect> {
+        self.toolbox.marquee.borrow().get_bb()
+    }
+
+    /// Pixels of `pixs` recolored with the entry `steps` along the palette ramp of the
+    /// color below them on the current layer, lighter if positive, see
+    /// `PaletteGroup::shade`. Pixels over nothing or over a color that is not in
+    /// the palette are dropped.
+    pub fn shade_pixels(&self, pixs: &Pixels, steps: isize) -> Pixels {
+        let l = match self.cel() {
+            Some(l) => l,
+            None => return Pixels::new(),
+        };
+        let layer = l.borrow();
+        let pal = self.palette.current_palette();
+        pixs.iter()
+            .filter_map(|p| {
+                let idx = match layer.get_color(p.point)? {
+                    Color::Indexed(i) => i,
+                    color => self.palette.find_color(color)?,
+                };
+                Some(Pixel {
+                    color: Color::Indexed(pal.shade(idx, steps)?),
+                    ..*p
+                })
+            })
+            .collect()
+    }
+
+    /// adds the pixels of the current layer inside the selection to the brush library,
+    /// returns the index of the new brush
+    pub fn capture_brush(&mut self, name: &str) -> Result<usize, String> {
+        let bb = self.selection().ok_or_else(|| "nothing selected".to_owned())?;
+        let l = self.cel().ok_or_else(|| "no layer".to_owned())?;
+        let mut pixs = l.borrow().content.clone();
+        pixs.retain_in_rect_mut(bb);
+        if pixs.is_empty() {
+            return Err("the selection is empty".to_owned());
+        }
+        let pixs = pixs.to_rgba(Some(self)).ok_or_else(|| "cannot resolve colors".to_owned())?;
+        self.brushes.insert(name, &pixs)
+    }
+
+    /// upscale the current layer in place,
+    /// only the pixels inside `selection` if there is one
+    pub fn upscale_layer(&mut self, filter: UpscaleFilter, scale: u32, selection: Option<Rect>) -> Result<(), String> {
+        self.commit();
+        let l = self.cel().ok_or_else(|| "no layer".to_owned())?;
+        let mut pixs = l.borrow().content.clone();
+        if let Some(bb) = selection {
+            pixs.retain_in_rect_mut(bb);
+        }
+        let scaled = pixs.upscale(filter, scale, Some(self));
+        let content = &mut l.borrow_mut().content;
+        content.sub_mut(&pixs);
+        content.extend(&scaled);
+        self.set_redraw(true);
+        Ok(())
+    }
+
+    /// picks at most `n` colors from the current layer,
+    /// only the pixels inside `selection` if there is one
+    pub fn extract_palette(&self, mode: PaletteExtraction, n: usize, selection: Option<Rect>) -> Result<PaletteGroup, String> {
+        let l = self.cel().ok_or_else(|| "no layer".to_owned())?;
+        let mut pixs = l.borrow().content.clone();
+        if let Some(bb) = selection {
+            pixs.retain_in_rect_mut(bb);
+        }
+        let colors = pixs
+            .iter()
+            .map(|p| p.color.to_rgba(Some(self)).ok_or_else(|| "color index too big".to_owned()))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(PaletteGroup::from_rgba(&extract_palette(&colors, n, mode)))
+    }
+
+    /// rgba value of every document palette entry, in palette order
+    fn palette_rgba(&self) -> Result<Vec<XpriteRgba>, String> {
+        let pal = self.palette.current_palette().colors.borrow();
+        pal.values()
+            .map(|c| c.to_rgba(None).ok_or_else(|| "palette color is not rgba".to_owned()))
+            .collect()
+    }
+
+    fn remap_layer(
+        &self,
+        layer: &Rc<RefCell<Layer>>,
+        palette: &[XpriteRgba],
+        space: ColorSpace,
+        dithering: Dithering,
+        selection: Option<Rect>,
+    ) -> Result<(), String> {
+        let mut pixs = layer.borrow().content.clone();
+        if let Some(bb) = selection {
+            pixs.retain_in_rect_mut(bb);
+        }
+        let remapped = remap_to_palette(&pixs, palette, space, dithering, Some(self)).ok_or_else(|| "cannot convert to palette".to_owned())?;
+        layer.borrow_mut().content.extend(&remapped);
+        Ok(())
+    }
+
+    /// maps the current layer onto the document palette,
+    /// only the pixels inside `selection` if there is one
+    pub fn convert_layer_to_palette(&mut self, space: ColorSpace, dithering: Dithering, selection: Option<Rect>) -> Result<(), String> {
+        let palette = self.palette_rgba()?;
+        self.commit();
+        let l = self.cel().ok_or_else(|| "no layer".to_owned())?;
+        self.remap_layer(&l, &palette, space, dithering, selection)?;
+        self.set_redraw(true);
+        Ok(())
+    }
+
+    /// Applies `edit` to the document palette, which returns the new index of
+    /// every old entry. Indexed pixels of every frame and layer, undo history
+    /// included, are remapped so the artwork does not change. Pixels whose entry
+    /// was dropped keep their old color as rgba.
+    pub fn edit_palette<F>(&mut self, edit: F)
+    where
+        F: FnOnce(&mut PaletteGroup) -> Vec<Option<usize>>,
+    {
+        let old: Vec<Color> = self.palette.current_palette().colors.borrow().values().cloned().collect();
+        let remap = edit(self.palette.current_palette_mut());
+        // palette colors kept for undo no longer line up with the entries
+        self.history.forget_palettes();
+        for frames in self.history.iter() {
+            for layer in frames.iter().flat_map(|f| f.iter_layers()) {
+                let mut layer = layer.borrow_mut();
+                let remapped = layer
+                    .content
+                    .iter()
+                    .map(|p| match p.color {
+                        Color::Indexed(i) if i < remap.len() => {
+                            let color = match remap[i] {
+                                Some(j) => Color::Indexed(j),
+                                None => old[i],
+                            };
+                            Pixel { color, ..*p }
+                        }
+                        _ => *p,
+                    })
+                    .collect();
+                layer.content = remapped;
+            }
+        }
+        self.set_redraw(true);
+    }
+
+    /// Replaces the document palette with the selected library palette as an
+    /// undo step. Palette colors kept for undo belong to the old palette and are
+    /// dropped.
+    pub fn use_library_palette(&mut self) -> Result<(), String> {
+        self.palette.copy_to_document()?;
+        self.commit();
+        self.history.forget_palettes();
+        self.set_redraw(true);
+        Ok(())
+    }
+
+    /// appends a generated ramp to the document palette as an undo step
+    pub fn add_ramp(&mut self, name: &str, base: XpriteRgba, params: &RampParams) {
+        self.commit_palette();
+        self.palette.current_palette_mut().add_ramp(name, base, params);
+        self.set_redraw(true);
+    }
+
+    /// palette indices used by any layer of any frame
+    pub fn used_palette_indices(&self) -> HashSet<usize> {
+        let mut used = HashSet::new();
+        for layer in self.frames().iter().flat_map(|f| f.iter_layers()) {
+            for p in layer.borrow().content.iter() {
+                if let Color::Indexed(i) = p.color {
+                    used.insert(i);
+                }
+            }
+        }
+        used
+    }
+
+    /// drops palette entries no pixel in the document refers to
+    pub fn remove_unused_colors(&mut self) {
+        let used = self.used_palette_indices();
+        self.edit_palette(|pal| pal.retain(|i| used.contains(&i)));
+    }
+
+    /// rect the pixels of an edit must lie in, fails if `scope` is the selection and there is none
+    fn scope_rect(&self, scope: ReplaceScope) -> Result<Option<Rect>, String> {
+        match scope {
+            ReplaceScope::Selection => self.selection().map(Some).ok_or_else(|| "nothing selected".to_owned()),
+            _ => Ok(None),
+        }
+    }
+
+    fn scoped_layers(&self, scope: ReplaceScope) -> Vec<Rc<RefCell<Layer>>> {
+        match scope {
+            ReplaceScope::Selection | ReplaceScope::Layer => self.cel().into_iter().collect(),
+            ReplaceScope::Frame => self.frame().iter_layers().cloned().collect(),
+            ReplaceScope::AllFrames => self.frames().iter().flat_map(|f| f.iter_layers().cloned().collect::<Vec<_>>()).collect(),
+        }
+    }
+
+    /// plays color cycling for another `dt` seconds, redraws when a color moved
+    pub fn advance_color_cycles(&mut self, dt: f64) {
+        let t0 = self.cycle_time.unwrap_or(0.);
+        let t1 = t0 + dt;
+        let moved = self.cycle_time.is_none() || self.palette.current_palette().cycles.iter().any(|c| c.step(t0) != c.step(t1));
+        self.cycle_time = Some(t1);
+        self.set_redraw(moved);
+    }
+
+    /// stops color cycling, colors are back in place
+    pub fn stop_color_cycles(&mut self) {
+        if self.cycle_time.take().is_some() {
+            self.set_redraw(true);
+        }
+    }
+
+    /// indexed pixels moved to the entries they show after `t` seconds of color cycling
+    fn cycled_pixels(&self, pixs: &Pixels, t: f64) -> Pixels {
+        let pal = self.palette.current_palette();
+        pixs.iter()
+            .map(|p| match p.color {
+                Color::Indexed(i) => Pixel {
+                    color: Color::Indexed(pal.cycled_index(i, t)),
+                    ..*p
+                },
+                _ => *p,
+            })
+            .collect()
+    }
+
+    /// Turns one loop of color cycling into frames following the current one,
+    /// sampled at `fps`. Returns the number of frames added.
+    pub fn bake_color_cycles(&mut self, fps: f64) -> Result<usize, String> {
+        if self.palette.current_palette().cycles.is_empty() {
+            return Err("palette has no color cycles".to_owned());
+        }
+        let n = self.palette.current_palette().cycle_frames(fps)?;
+        self.commit();
+        let base = self.frame().clone();
+        let idx = self.frames().current_frame_idx;
+        for k in 1..n {
+            let frame = base.clone();
+            for l in frame.iter_layers() {
+                let cycled = self.cycled_pixels(&l.borrow().content, k as f64 / fps);
+                l.borrow_mut().content = cycled;
+            }
+            self.frames_mut().insert_frame(idx + k, frame);
+        }
+        self.set_redraw(true);
+        Ok(n - 1)
+    }
+
+    /// Replaces `from` with `to` in every layer `scope` reaches, as a single
+    /// undo step. Returns the number of pixels replaced.
+    pub fn replace_color(&mut self, from: Color, to: Color, tolerance: u8, scope: ReplaceScope) -> Result<usize, String> {
+        let selection = self.scope_rect(scope)?;
+        self.commit();
+        let mut count = 0;
+        for l in &self.scoped_layers(scope) {
+            let mut pixs = l.borrow().content.clone();
+            if let Some(bb) = selection {
+                pixs.retain_in_rect_mut(bb);
+            }
+            let replaced = replace_color(&pixs, from, to, tolerance, Some(self));
+            count += replaced.len();
+            l.borrow_mut().content.extend(&replaced);
+        }
+        self.set_redraw(true);
+        Ok(count)
+    }
+
+    /// Adjusted copies of the pixels `adj` changes in every layer `scope`
+    /// reaches. In palette mode indexed pixels are left out and the palette
+    /// entries they use are returned with their adjusted colors instead.
+    fn adjusted_pixels(&self, adj: Adjustment, scope: ReplaceScope, palette_mode: bool) -> Result<AdjustedPixels, String> {
+        let selection = self.scope_rect(scope)?;
+        let mut adjusted = vec![];
+        let mut indices = HashSet::new();
+        for l in self.scoped_layers(scope) {
+            let mut pixs = l.borrow().content.clone();
+            if let Some(bb) = selection {
+                pixs.retain_in_rect_mut(bb);
+            }
+            if palette_mode {
+                indices.extend(pixs.iter().filter_map(|p| if let Color::Indexed(i) = p.color { Some(i) } else { None }));
+                pixs = pixs.iter().filter(|p| !p.color.is_indexed()).cloned().collect();
+            }
+            let pixs = adjust_pixels(&pixs, adj, Some(self)).ok_or_else(|| "cannot resolve colors".to_owned())?;
+            adjusted.push((l, pixs));
+        }
+        let colors = self.palette.current_palette().colors.borrow();
+        let entries = indices
+            .into_iter()
+            .filter_map(|i| Some((i, Color::Rgba(adj.apply(colors.get_index(i)?.1.to_rgba(None)?)))))
+            .collect();
+        Ok((adjusted, entries))
+    }
+
+    /// Shows `adj` on the canvas without touching the document, replacing the
+    /// previous preview. See `adjust_colors`.
+    pub fn preview_adjustment(&mut self, adj: Adjustment, scope: ReplaceScope, palette_mode: bool) -> Result<(), String> {
+        self.cancel_adjustment_preview();
+        let (mut layers, entries) = self.adjusted_pixels(adj, scope, palette_mode)?;
+        if !entries.is_empty() {
+            // an adjusted entry shows up wherever it is used
+            for l in self.frame().iter_layers() {
+                let pixs: Pixels = l
+                    .borrow()
+                    .content
+                    .iter()
+                    .filter_map(|p| match p.color {
+                        Color::Indexed(i) => entries.get(&i).map(|&color| Pixel { color, ..*p }),
+                        _ => None,
+                    })
+                    .collect();
+                match layers.iter_mut().find(|(other, _)| Rc::ptr_eq(l, other)) {
+                    Some((_, adjusted)) => adjusted.extend(&pixs),
+                    None => layers.push((Rc::clone(l), pixs)),
+                }
+            }
+        }
+        self.adjust_preview = Some(layers);
+        self.set_redraw(true);
+        Ok(())
+    }
+
+    /// hides the preview of `preview_adjustment`
+    pub fn cancel_adjustment_preview(&mut self) {
+        if self.adjust_preview.take().is_some() {
+            self.set_redraw(true);
+        }
+    }
+
+    /// Adjusts the colors of every layer `scope` reaches as a single undo step.
+    /// With `palette_mode`, indexed pixels keep their index and the palette
+    /// entries they use are adjusted instead.
+    pub fn adjust_colors(&mut self, adj: Adjustment, scope: ReplaceScope, palette_mode: bool) -> Result<(), String> {
+        self.cancel_adjustment_preview();
+        self.scope_rect(scope)?;
+        if palette_mode {
+            self.commit_palette();
+        } else {
+            self.commit();
+        }
+        let (layers, entries) = self.adjusted_pixels(adj, scope, palette_mode)?;
+        for (l, pixs) in layers {
+            l.borrow_mut().content.extend(&pixs);
+        }
+        let pal = self.palette.current_palette();
+        for (i, color) in entries {
+            if let Some((_, col)) = pal.colors.borrow_mut().get_index_mut(i) {
+                *col = color;
+            }
+        }
+        self.set_redraw(true);
+        Ok(())
+    }
+
+    /// maps every layer of every frame onto the document palette
+    pub fn convert_document_to_palette(&mut self, space: ColorSpace, dithering: Dithering) -> Result<(), String> {
+        let palette = self.palette_rgba()?;
+        self.commit();
+        let layers: Vec<_> = self.frames().iter().flat_map(|f| f.iter_layers().cloned().collect::<Vec<_>>()).collect();
+        for l in &layers {
+            self.remap_layer(l, &palette, space, dithering, None)?;
+        }
+        self.set_redraw(true);
+        Ok(())
+    }
 }
 
 impl Xprite {
@@ -379,13 +792,27 @@ impl Xprite {
         Some(rdr.image)
     }
 
+    /// visible layers of the current frame merged into one, upper layers win
+    pub fn flatten_visible(&self) -> Pixels {
+        let mut pixs = Pixels::new();
+        for (_, group) in self.frame().groups.iter().rev() {
+            for layer in group.iter().rev() {
+                let layer = layer.borrow();
+                if layer.visible {
+                    pixs.extend(&layer.content);
+                }
+            }
+        }
+        pixs
+    }
+
     pub fn preview(&self, rdr: &mut dyn Renderer) -> Result<(), String> {
         for (i, group) in self.frame().groups.iter().enumerate().rev() {
             for (j, layer) in group.1.iter().enumerate().rev() {
                 let draw_buf = |rdr: &mut dyn Renderer| -> Result<(), String> {
                     if i == self.frame().group_idx && j == self.frame().layer_idx {
                         // draw current layer pixels
-                        for &Pixel { point, color } in self.pixels().iter() {
+                        for &Pixel { point, color } in self.on_canvas(self.pixels()).iter() {
                             let Vec2f { x, y } = point;
                             // println!("{:?}", color);
                             let c = color.to_rgba(Some(self)).ok_or_else(||"color index too big".to_owned())?.into();
@@ -398,7 +825,13 @@ impl Xprite {
                     draw_buf(rdr)?;
                     continue;
                 } else {
-                    layer.borrow().draw(rdr, Some(self));
+                    let layer_ = layer.borrow();
+                    Layer {
+                        content: self.on_canvas(&layer_.content),
+                        ..layer_.clone()
+                    }
+                    .draw(rdr, Some(self));
+                    self.draw_adjust_preview(rdr, layer)?;
                     draw_buf(rdr)?;
                 }
             }
@@ -407,6 +840,26 @@ impl Xprite {
         Ok(())
     }
 
+    /// `pixs` as shown on the canvas, indexed colors move along the color
+    /// cycles while they play
+    fn on_canvas(&self, pixs: &Pixels) -> Pixels {
+        match self.cycle_time {
+            Some(t) => self.cycled_pixels(pixs, t),
+            None => pixs.clone(),
+        }
+    }
+
+    fn draw_adjust_preview(&self, rdr: &mut dyn Renderer, layer: &Rc<RefCell<Layer>>) -> Result<(), String> {
+        let preview = self.adjust_preview.iter().flatten().filter(|(l, _)| Rc::ptr_eq(l, layer));
+        for (_, pixs) in preview {
+            for &Pixel { point, color } in self.on_canvas(pixs).iter() {
+                let c = color.to_rgba(Some(self)).ok_or_else(|| "color index too big".to_owned())?.into();
+                rdr.pixel(point.x, point.y, c, true);
+            }
+        }
+        Ok(())
+    }
+
     /// export pixels to an image via renderer
     pub fn export(&self, rdr: &mut dyn Renderer) -> Result<(), String> {
         for layer in self.frame().iter_layers().rev() {
@@ -427,6 +880,11 @@ impl Xprite {
     pub fn as_ase(&self) -> Option<ase::Aseprite> {
         let header = ase::Header::new(self.canvas.art_w as u16, self.canvas.art_h as u16);
         let mut frame = ase::Frame::new();
+        let palette = self.palette.current_palette().as_ase_chunk()?;
+        frame.add_chunk(ase::Chunk::new(ase::ChunkData::PaletteChunk(palette)));
+        if !self.palette.current_palette().ramps.is_empty() {
+            frame.add_chunk(user_data_chunk(format!("{}{}", RAMPS_TAG, self.palette.current_palette().ramps_as_text())));
+        }
         for (i, layer) in self.history.top().frame().iter_layers().rev().enumerate() {
             let layer = layer.borrow();
             frame.add_chunk(ase::Chunk::new(ase::ChunkData::LayerChunk(ase::chunk::LayerChunk::new(
@@ -434,13 +892,16 @@ impl Xprite {
                 layer.visible,
             ))));
             if !layer.content.is_empty() {
+                let bb = layer.content.bounding_rect();
                 frame.add_chunk(ase::Chunk::new(ase::ChunkData::CelChunk({
-                    let Rect(Vec2f { x: x0, y: y0 }, Vec2f { x: x1, y: y1 }) = layer.content.bounding_rect();
+                    let Rect(Vec2f { x: x0, y: y0 }, Vec2f { x: x1, y: y1 }) = bb;
                     let w = x1 - x0 + 1.;
                     let h = y1 - y0 + 1.;
                     let pixels: ase::Pixels = layer.content.clone().to_ase_pixels(Some(self))?;
                     ase::chunk::CelChunk::new(i as u16, x0 as i16, y0 as i16, w as u16, h as u16, pixels)
                 })));
+                // the cel only holds rgba, its user data tells which pixels are palette entries
+                frame.add_chunk(user_data_chunk(format!("{}{}", INDEXED_TAG, indexed_runs(&layer.content, bb))));
             }
         }
         Some(ase::Aseprite::new(header, vec![frame]))
@@ -456,16 +917,24 @@ impl Xprite {
         let canvas = Canvas::new(f64::from(*width_in_pixels), f64::from(*height_in_pixels));
         let mut history = History::new();
 
+        let mut palette: Option<PaletteGroup> = None;
+        // layer and bounds of the cel the next user data chunk belongs to
+        let mut last_cel = None;
         let frame = &frames[0];
         let ase::Frame { chunks, .. } = frame;
         for ase::Chunk { chunk_data, .. } in chunks {
             match chunk_data {
+                ase::ChunkData::PaletteChunk(chunk) => {
+                    palette = Some(PaletteGroup::from_ase_chunk(chunk));
+                    last_cel = None;
+                }
                 ase::ChunkData::LayerChunk(ase::chunk::LayerChunk {
                     flags,
                     layer_type,
                     layer_name,
                     ..
                 }) => {
+                    last_cel = None;
                     let visible = flags.contains(ase::chunk::layer_chunk::Flags::Visible);
                     if *layer_type == ase::chunk::LayerType::Normal {
                         // image layer
@@ -491,9 +960,23 @@ impl Xprite {
                     let pixs = Pixels::from_ase_pixels(&ase_pixs, bb);
                     let layer = &mut history.top_mut().frame_mut().groups[0].1[usize::from(*layer_index)].borrow_mut();
                     layer.content.extend(&pixs);
+                    last_cel = Some((usize::from(*layer_index), bb));
 
                     // dbg!(pixs);
                 }
+                ase::ChunkData::UserDataChunk(ase::chunk::UserDataChunk { text: Some(text), .. }) if text.starts_with(INDEXED_TAG) => {
+                    // files written elsewhere have no such chunk and stay rgba
+                    if let Some((layer_index, bb)) = last_cel.take() {
+                        let pal_len = palette.as_ref().map_or(0, |pal| pal.colors.borrow().len());
+                        let layer = &mut history.top_mut().frame_mut().groups[0].1[layer_index].borrow_mut();
+                        bind_indexed(&mut layer.content, bb, &text[INDEXED_TAG.len()..], pal_len);
+                    }
+                }
+                ase::ChunkData::UserDataChunk(ase::chunk::UserDataChunk { text: Some(text), .. }) if text.starts_with(RAMPS_TAG) => {
+                    if let Some(palette) = &mut palette {
+                        palette.ramps_from_text(&text[RAMPS_TAG.len()..]);
+                    }
+                }
                 _ => (),
             };
         }
@@ -506,11 +989,87 @@ impl Xprite {
             history,
             ..Default::default()
         };
+        if let Some(palette) = palette {
+            xpr.palette.document = palette;
+        }
         xpr.switch_layer(0, 0);
         xpr
     }
 }
 
+/// exchanges the entries, ramps and cycles of `pal` with those of `other`,
+/// the selected colors stay where they are
+fn swap_palette(pal: &mut PaletteGroup, other: &mut PaletteGroup) {
+    std::mem::swap(&mut *pal.colors.borrow_mut(), &mut *other.colors.borrow_mut());
+    std::mem::swap(&mut pal.ramps, &mut other.ramps);
+    std::mem::swap(&mut pal.cycles, &mut other.cycles);
+    let last = pal.colors.borrow().len().saturating_sub(1);
+    pal.idx = pal.idx.min(last);
+    pal.secondary_idx = pal.secondary_idx.min(last);
+}
+
+/// starts the user data text of a cel that lists its palette pixels
+const INDEXED_TAG: &str = "xprite:indexed ";
+/// starts the user data text listing the palette ramps
+const RAMPS_TAG: &str = "xprite:ramps\n";
+
+fn user_data_chunk(text: String) -> ase::Chunk {
+    ase::Chunk::new(ase::ChunkData::UserDataChunk(ase::chunk::UserDataChunk { text: Some(text), color: None }))
+}
+
+/// Palette indices of the pixels in `bb` row by row, as runs of `count*index`.
+/// Empty and rgba pixels are `-`.
+fn indexed_runs(pixs: &Pixels, bb: Rect) -> String {
+    let mut runs: Vec<(usize, Option<usize>)> = vec![];
+    for y in bb.0.y as isize..=bb.1.y as isize {
+        for x in bb.0.x as isize..=bb.1.x as isize {
+            let idx = match pixs.0.get(&pixel!(y, x, Color::red())) {
+                Some(Pixel { color: Color::Indexed(i), .. }) => Some(*i),
+                _ => None,
+            };
+            match runs.last_mut() {
+                Some((n, last)) if *last == idx => *n += 1,
+                _ => runs.push((1, idx)),
+            }
+        }
+    }
+    let run = |(n, idx): &(usize, Option<usize>)| match idx {
+        Some(i) => format!("{}*{}", n, i),
+        None => format!("{}*-", n),
+    };
+    runs.iter().map(run).collect::<Vec<_>>().join(" ")
+}
+
+/// Turns the pixels in `bb` listed in `runs` (see `indexed_runs`) back into
+/// palette entries. Malformed runs and indices past the palette are ignored.
+fn bind_indexed(pixs: &mut Pixels, bb: Rect, runs: &str, pal_len: usize) {
+    let w = (bb.1.x - bb.0.x + 1.).max(0.) as usize;
+    let h = (bb.1.y - bb.0.y + 1.).max(0.) as usize;
+    let mut indices = Vec::with_capacity(w * h);
+    for run in runs.split_whitespace() {
+        let mut parts = run.splitn(2, '*');
+        let n = parts.next().and_then(|n| n.parse::<usize>().ok());
+        let idx = match parts.next() {
+            Some("-") => Some(None),
+            Some(i) => i.parse::<usize>().ok().map(Some),
+            None => None,
+        };
+        match (n, idx) {
+            (Some(n), Some(idx)) if indices.len() + n <= w * h => indices.resize(indices.len() + n, idx),
+            _ => return,
+        }
+    }
+    for (i, idx) in indices.into_iter().enumerate() {
+        let point = vec2f_xy!(bb.0.x + (i % w) as f64, bb.0.y + (i / w) as f64);
+        match idx {
+            Some(idx) if idx < pal_len && pixs.0.contains(&pixel!(point, Color::red())) => {
+                pixs.0.replace(pixel!(point, Color::Indexed(idx)));
+            }
+            _ => (),
+        }
+    }
+}
+
 /// handle events
 impl Xprite {
     pub fn event(&mut self, evt: &InputEvent) -> Result<(), String> {
@@ -563,9 +1122,18 @@ impl Xprite {
 
 impl Xprite {
     pub fn commit(&mut self) {
+        self.adjust_preview = None;
         self.history.duplicate();
         self.history.clear_redo();
     }
+
+    /// like `commit`, undo also brings back the current document palette
+    pub fn commit_palette(&mut self) {
+        self.adjust_preview = None;
+        let palette = self.palette.current_palette().duplicate();
+        self.history.duplicate_with_palette(palette);
+        self.history.clear_redo();
+    }
 }
 
 /// layers
@@ -597,44 +1165,184 @@ impl Xprite {
 
 /// import/export
 impl Xprite {
-    pub fn save_layer_img<P: AsRef<Path>>(&self, group_idx: usize, layer_idx: usize, img_path: P, rescale: u32, trim: bool) -> Option<()> {
+    pub fn save_layer_img<P: AsRef<Path>>(
+        &self,
+        group_idx: usize,
+        layer_idx: usize,
+        img_path: P,
+        rescale: u32,
+        filter: UpscaleFilter,
+        trim: bool,
+    ) -> Option<()> {
         let im = self.layer_as_im(group_idx, layer_idx, trim)?;
-        let nwidth = im.width() * rescale;
-        let nheight = im.height() * rescale;
-        let filter = img::FilterType::Nearest;
-        let im = img::imageops::resize(&im, nwidth, nheight, filter);
+        let im = upscale_image(&im, filter, rescale);
 
         info!("writing file to {:?}", img_path.as_ref().as_os_str());
         im.save(img_path).unwrap();
         Some(())
     }
 
-    pub fn save_group_img<P: AsRef<Path>>(&self, group_idx: usize, img_path: P, rescale: u32, trim: bool) -> Option<()> {
+    pub fn save_group_img<P: AsRef<Path>>(&self, group_idx: usize, img_path: P, rescale: u32, filter: UpscaleFilter, trim: bool) -> Option<()> {
         let im = self.group_as_im(group_idx, trim)?;
-        let nwidth = im.width() * rescale;
-        let nheight = im.height() * rescale;
-        let filter = img::FilterType::Nearest;
-        let im = img::imageops::resize(&im, nwidth, nheight, filter);
+        let im = upscale_image(&im, filter, rescale);
         info!("writing file to {:?}", img_path.as_ref().as_os_str());
         im.save(img_path).unwrap();
         Some(())
     }
 
-    pub fn save_img<P: AsRef<Path>>(&self, img_path: P, rescale: u32) -> Option<()> {
+    pub fn save_img<P: AsRef<Path>>(&self, img_path: P, rescale: u32, filter: UpscaleFilter) -> Option<()> {
         let mut rdr = ImageRenderer::new(self.canvas.bg, self.canvas.art_w, self.canvas.art_h);
         self.export(&mut rdr).unwrap();
         rdr.render(Some(self))?;
         let im = rdr.as_img();
         //rescale image
-        let nwidth = im.width() * rescale;
-        let nheight = im.height() * rescale;
-        let filter = img::FilterType::Nearest;
-        let im = img::imageops::resize(im, nwidth, nheight, filter);
+        let im = upscale_image(im, filter, rescale);
         info!("writing file to {:?}", img_path.as_ref().as_os_str());
         im.save(img_path).unwrap();
         Some(())
     }
 
+    /// Writes `pixs` inside `bb` as an 8-bit paletted png.
+    /// PLTE is the current palette in order, empty pixels use the first
+    /// fully transparent entry or one appended after the palette.
+    pub fn save_indexed_png<P: AsRef<Path>>(&self, pixs: &Pixels, bb: Rect, img_path: P, rescale: u32, filter: UpscaleFilter) -> Result<(), String> {
+        use png::HasParameters;
+
+        let mut plte = vec![];
+        let mut trns = vec![];
+        let pal_len = {
+            let pal = self.palette.current_palette().colors.borrow();
+            for (_, col) in pal.iter().take(256) {
+                let rgba = col.to_rgba(Some(self)).ok_or_else(|| "palette contains an indexed color".to_owned())?;
+                plte.extend_from_slice(&[rgba.r, rgba.g, rgba.b]);
+                trns.push(rgba.a);
+            }
+            pal.len()
+        };
+
+        // scale around the corner of bb instead of the corner of the pixels
+        let pixs = pixs.shifted(-bb.0);
+        let origin = pixs.bounding_rect().0;
+        let pixs = pixs.upscale(filter, rescale, Some(self)).shifted(origin * (f64::from(rescale) - 1.));
+
+        let w = bb.w() as u32 * rescale;
+        let h = bb.h() as u32 * rescale;
+        let mut data = vec![None; (w * h) as usize];
+        for Pixel { point, color } in pixs.iter() {
+            if oob(point.x, point.y, f64::from(w), f64::from(h)) {
+                continue;
+            }
+            let idx = match color {
+                Color::Indexed(i) if *i < 256 && *i < pal_len => *i as u8,
+                Color::Indexed(i) if *i < pal_len => {
+                    return Err(format!("color #{} is past the 256 colors an indexed png can hold", i));
+                }
+                Color::Indexed(i) => return Err(format!("color #{} is not in the palette", i)),
+                Color::Rgba(_) => return Err("indexed png export needs all pixels to be palette colors".to_owned()),
+            };
+            data[(point.y as u32 * w + point.x as u32) as usize] = Some(idx);
+        }
+
+        let data: Vec<u8> = if data.iter().any(Option::is_none) {
+            let empty = match trns.iter().position(|a| *a == 0) {
+                Some(i) => i as u8,
+                None if trns.len() < 256 => {
+                    plte.extend_from_slice(&[0, 0, 0]);
+                    trns.push(0);
+                    (trns.len() - 1) as u8
+                }
+                None => return Err("palette has 256 opaque colors, no room left for transparent pixels".to_owned()),
+            };
+            data.into_iter().map(|i| i.unwrap_or(empty)).collect()
+        } else {
+            data.into_iter().map(Option::unwrap).collect()
+        };
+        // tRNS can stop at the last entry that is not opaque
+        while trns.last() == Some(&255) {
+            trns.pop();
+        }
+
+        info!("writing indexed png to {:?}", img_path.as_ref().as_os_str());
+        let f = File::create(img_path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(f, w, h);
+        encoder.set(png::ColorType::Indexed).set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer.write_chunk(png::chunk::PLTE, &plte).map_err(|e| e.to_string())?;
+        if !trns.is_empty() {
+            writer.write_chunk(png::chunk::tRNS, &trns).map_err(|e| e.to_string())?;
+        }
+        writer.write_image_data(&data).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Writes `pixs` inside `bb` as a looping gif of the palette color cycles,
+    /// sampled at `fps`. Every frame can hold at most 256 colors.
+    pub fn save_cycle_gif<P: AsRef<Path>>(&self, pixs: &Pixels, bb: Rect, img_path: P, rescale: u32, filter: UpscaleFilter, fps: f64) -> Result<(), String> {
+        use gif::SetParameter;
+
+        let w = bb.w() as u32 * rescale;
+        let h = bb.h() as u32 * rescale;
+        if w > u32::from(u16::max_value()) || h > u32::from(u16::max_value()) {
+            return Err("image is too large for a gif".to_owned());
+        }
+        let n = self.palette.current_palette().cycle_frames(fps)?;
+
+        info!("writing gif to {:?}", img_path.as_ref().as_os_str());
+        let f = File::create(img_path).map_err(|e| e.to_string())?;
+        let mut encoder = gif::Encoder::new(f, w as u16, h as u16, &[]).map_err(|e| e.to_string())?;
+        encoder.set(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+        for k in 0..n {
+            let frame = self
+                .cycled_pixels(pixs, k as f64 / fps)
+                .iter()
+                .map(|p| {
+                    let color = p.color.to_rgba(Some(self)).ok_or_else(|| "color is not in the palette".to_owned())?;
+                    Ok(Pixel {
+                        color: Color::Rgba(color),
+                        ..*p
+                    })
+                })
+                .collect::<Result<Pixels, String>>()?;
+            // scale around the corner of bb instead of the corner of the pixels
+            let frame = frame.shifted(-bb.0);
+            let origin = frame.bounding_rect().0;
+            let frame = frame.upscale(filter, rescale, None).shifted(origin * (f64::from(rescale) - 1.));
+
+            let mut plte = vec![];
+            let mut lookup = HashMap::new();
+            let mut data = vec![None; (w * h) as usize];
+            for Pixel { point, color } in frame.iter() {
+                if oob(point.x, point.y, f64::from(w), f64::from(h)) {
+                    continue;
+                }
+                let rgba = unsafe { color.as_rgba() };
+                if rgba.a == 0 {
+                    continue;
+                }
+                let next = lookup.len();
+                let idx = *lookup.entry((rgba.r, rgba.g, rgba.b)).or_insert(next);
+                if idx == next {
+                    plte.extend_from_slice(&[rgba.r, rgba.g, rgba.b]);
+                }
+                data[(point.y as u32 * w + point.x as u32) as usize] = Some(idx);
+            }
+            let transparent = if data.iter().any(Option::is_none) {
+                plte.extend_from_slice(&[0, 0, 0]);
+                Some(lookup.len())
+            } else {
+                None
+            };
+            if plte.len() > 256 * 3 {
+                return Err(format!("frame {} has more than 256 colors", k));
+            }
+            let data: Vec<u8> = data.into_iter().map(|i| i.or(transparent).unwrap() as u8).collect();
+            let mut gif_frame = gif::Frame::from_palette_pixels(w as u16, h as u16, &data, &plte, transparent.map(|i| i as u8));
+            gif_frame.delay = (100. / fps).round().max(1.) as u16;
+            encoder.write_frame(&gif_frame).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
     pub fn load_img<P: AsRef<Path>>(png_path: P) -> Xprite {
         info!("loading png file {:?}", png_path.as_ref().as_os_str());
         let img = img::open(png_path.as_ref()).unwrap();
@@ -713,4 +1421,337 @@ mod tests {
         // dbg!(xpr);
     }
 
+    #[test]
+    fn test_ase_keeps_indexed_pixels() {
+        use super::*;
+        let xpr = Xprite::new("test".to_owned(), 4., 4.);
+        let rgba = *xpr.palette.current_palette().colors.borrow().get_index(3).unwrap().1;
+        xpr.cel().unwrap().borrow_mut().content.extend(&pixels!(
+            pixel!(0, 0, Color::Indexed(3)),
+            pixel!(0, 1, rgba),
+            pixel!(2, 1, Color::Indexed(8)),
+            pixel!(2, 2, Color::Indexed(8))
+        ));
+        let mut aseprite = xpr.as_ase().unwrap();
+        // from_ase keeps the empty default layer next to the loaded ones
+        let load = |aseprite: &ase::Aseprite| {
+            let xpr = Xprite::from_ase("test".to_owned(), aseprite);
+            let layers = xpr.history.top().frame().iter_layers().map(|l| l.borrow().content.clone()).collect::<Vec<_>>();
+            layers.into_iter().max_by_key(|c| c.len()).unwrap()
+        };
+        let content = load(&aseprite);
+        assert_eq!(content.len(), 4);
+        assert_eq!(content.get_pixel(0, 0).unwrap().color, Color::Indexed(3));
+        // rgba pixels that happen to match a palette color stay rgba
+        assert_eq!(content.get_pixel(0, 1).unwrap().color, rgba);
+        assert_eq!(content.get_pixel(2, 2).unwrap().color, Color::Indexed(8));
+
+        // files from other editors are all rgba
+        aseprite.frames[0].chunks.retain(|c| match c.chunk_data {
+            ase::ChunkData::UserDataChunk(_) => false,
+            _ => true,
+        });
+        let content = load(&aseprite);
+        assert!(content.iter().all(|p| !p.color.is_indexed()));
+    }
+
+    #[test]
+    fn test_ase_keeps_ramps() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 4.);
+        {
+            let pal = xpr.palette.current_palette_mut();
+            pal.ramps.insert("grey\tscale".to_owned(), vec![0, 5, 6, 7]);
+            pal.ramps.insert("red".to_owned(), vec![2, 8, 14]);
+        }
+        let aseprite = xpr.as_ase().unwrap();
+        let loaded = Xprite::from_ase("test".to_owned(), &aseprite);
+        let ramps: Vec<_> = loaded.palette.current_palette().ramps.clone().into_iter().collect();
+        assert_eq!(ramps, vec![("grey scale".to_owned(), vec![0, 5, 6, 7]), ("red".to_owned(), vec![2, 8, 14])]);
+    }
+
+    #[test]
+    fn test_indexed_runs() {
+        use super::*;
+        let pixs = pixels!(pixel!(0, 0, Color::Indexed(1)), pixel!(0, 1, Color::Indexed(1)), pixel!(1, 2, Color::red()));
+        let bb = Rect(vec2f!(0, 0), vec2f!(1, 2));
+        assert_eq!(indexed_runs(&pixs, bb), "2*1 4*-");
+        let mut rgba: Pixels = pixs.iter().map(|p| pixel!(p.point, Color::red())).collect();
+        // malformed or oversized runs leave everything rgba
+        bind_indexed(&mut rgba, bb, "2*1 5*-", 4);
+        bind_indexed(&mut rgba, bb, "2*x", 4);
+        assert!(rgba.iter().all(|p| !p.color.is_indexed()));
+        bind_indexed(&mut rgba, bb, "1*1 1*9 4*-", 4);
+        assert_eq!(rgba.get_pixel(0, 0).unwrap().color, Color::Indexed(1));
+        assert_eq!(rgba.get_pixel(0, 1).unwrap().color, Color::red());
+    }
+
+    #[test]
+    fn test_save_indexed_png() {
+        use super::*;
+        let xpr = Xprite::new("test".to_owned(), 4., 2.);
+        let pixs = pixels!(pixel!(0, 0, Color::Indexed(8)), pixel!(1, 3, Color::Indexed(15)));
+        let bb = Rect(vec2f!(0, 0), vec2f!(1, 3));
+        let path = std::env::temp_dir().join("xprite_indexed.png");
+        xpr.save_indexed_png(&pixs, bb, &path, 2, UpscaleFilter::Nearest).unwrap();
+
+        use png::HasParameters;
+        let mut decoder = png::Decoder::new(File::open(&path).unwrap());
+        decoder.set(png::Transformations::IDENTITY);
+        let (info, mut reader) = decoder.read_info().unwrap();
+        assert_eq!((8, 4), (info.width, info.height));
+        assert_eq!(png::ColorType::Indexed, info.color_type);
+        let mut buf = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buf).unwrap();
+        // pico8 has 16 opaque colors so transparent is appended as #16
+        assert_eq!(&buf[..8], &[8, 8, 16, 16, 16, 16, 16, 16]);
+        assert_eq!(&buf[24..], &[16, 16, 16, 16, 16, 16, 15, 15]);
+        assert_eq!(17 * 3, reader.info().palette.as_ref().unwrap().len());
+        std::fs::remove_file(&path).unwrap();
+
+        let rgba = pixels!(pixel!(0, 0, Color::red()));
+        assert!(xpr.save_indexed_png(&rgba, bb, &path, 1, UpscaleFilter::Nearest).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_convert_to_palette() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 2.);
+        xpr.frames_mut().add_frame_after_current();
+        for frame in xpr.frames().iter() {
+            let l = frame.layer().unwrap();
+            l.borrow_mut().content.push(pixel!(0, 0, Color::rgba(250, 10, 70, 255)));
+        }
+        xpr.convert_document_to_palette(ColorSpace::Lab, Dithering::None).unwrap();
+        // pico8 #8 is ff004d
+        for frame in xpr.frames().iter() {
+            let l = frame.layer().unwrap();
+            assert_eq!(l.borrow().content.get_pixel(0, 0).unwrap().color, Color::Indexed(8));
+        }
+        xpr.undo();
+        let l = xpr.cel().unwrap();
+        assert_eq!(l.borrow().content.get_pixel(0, 0).unwrap().color, Color::rgba(250, 10, 70, 255));
+    }
+
+    #[test]
+    fn test_edit_palette() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 2.);
+        let l = xpr.cel().unwrap();
+        l.borrow_mut().content.push(pixel!(0, 0, Color::Indexed(8)));
+        xpr.commit();
+        let l = xpr.cel().unwrap();
+        l.borrow_mut().content.push(pixel!(0, 1, Color::Indexed(12)));
+        let before = |xpr: &Xprite| -> Vec<_> {
+            let l = xpr.cel().unwrap();
+            let pixs = l.borrow().content.to_rgba(Some(xpr)).unwrap();
+            pixs.iter().map(|p| (p.point, p.color)).collect()
+        };
+        let expected = before(&xpr);
+
+        xpr.edit_palette(|pal| pal.sort(PaletteSort::Luminance));
+        assert_eq!(before(&xpr), expected);
+        xpr.edit_palette(|pal| pal.move_entry(0, 10));
+        assert_eq!(before(&xpr), expected);
+
+        xpr.remove_unused_colors();
+        assert_eq!(2, xpr.palette.current_palette().colors.borrow().len());
+        assert_eq!(before(&xpr), expected);
+
+        // the undo snapshot no longer has #12 in the palette
+        xpr.undo();
+        let l = xpr.cel().unwrap();
+        let p = l.borrow().content.get_pixel(0, 0).unwrap();
+        assert!(p.color.is_indexed());
+        assert_eq!(p.color.to_rgba(Some(&xpr)), Color::rgba(255, 0, 77, 255).to_rgba(None));
+    }
+
+    #[test]
+    fn test_replace_color() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 2.);
+        xpr.frames_mut().add_frame_after_current();
+        for frame in xpr.frames().iter() {
+            let l = frame.layer().unwrap();
+            l.borrow_mut().content.push(pixel!(0, 0, Color::Indexed(8)));
+            l.borrow_mut().content.push(pixel!(1, 1, Color::rgba(255, 0, 77, 255)));
+        }
+        let replaced = xpr.replace_color(Color::Indexed(8), Color::Indexed(3), 0, ReplaceScope::AllFrames).unwrap();
+        // pico8 #8 is ff004d, so the rgba pixel is an occurrence too
+        assert_eq!(4, replaced);
+        for frame in xpr.frames().iter() {
+            let l = frame.layer().unwrap();
+            assert_eq!(l.borrow().content.get_pixel(1, 1).unwrap().color, Color::Indexed(3));
+        }
+        xpr.undo();
+        let l = xpr.cel().unwrap();
+        assert_eq!(l.borrow().content.get_pixel(0, 0).unwrap().color, Color::Indexed(8));
+        assert!(xpr.replace_color(Color::Indexed(8), Color::Indexed(3), 0, ReplaceScope::Selection).is_err());
+    }
+
+    #[test]
+    fn test_color_cycles() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 2.);
+        xpr.cel().unwrap().borrow_mut().content.push(pixel!(0, 0, Color::Indexed(12)));
+        xpr.palette
+            .current_palette_mut()
+            .cycles
+            .push(ColorCycle::new(12, 14, 1., CycleDirection::Forward));
+        let colors = Rc::clone(&xpr.palette.current_palette().colors);
+        let color_of = |i| colors.borrow().get_index(i).unwrap().1.to_rgba(None);
+
+        let shown = |xpr: &Xprite| {
+            let mut rdr = ImageRenderer::new(xpr.canvas.bg, xpr.canvas.art_w, xpr.canvas.art_h);
+            xpr.preview(&mut rdr).unwrap();
+            rdr.render(Some(xpr)).unwrap();
+            let p = rdr.image.get_pixel(0, 0);
+            Some(XpriteRgba {
+                r: p[0],
+                g: p[1],
+                b: p[2],
+                a: p[3],
+            })
+        };
+
+        // only the canvas plays the cycles, the colors themselves stay put
+        xpr.advance_color_cycles(1.);
+        assert_eq!(shown(&xpr), color_of(14));
+        assert_eq!(Color::Indexed(12).to_rgba(Some(&xpr)), color_of(12));
+        xpr.stop_color_cycles();
+        assert_eq!(shown(&xpr), color_of(12));
+
+        let path = std::env::temp_dir().join("xprite_cycle.gif");
+        let pixs = xpr.flatten_visible();
+        xpr.save_cycle_gif(&pixs, Rect(vec2f!(0, 0), vec2f!(1, 3)), &path, 2, UpscaleFilter::Nearest, 1.)
+            .unwrap();
+        assert!(path.exists());
+
+        assert_eq!(Ok(2), xpr.bake_color_cycles(1.));
+        assert_eq!(3, xpr.frames().count());
+        let colors: Vec<_> = xpr
+            .frames()
+            .iter()
+            .map(|f| f.layer().unwrap().borrow().content.get_pixel(0, 0).unwrap().color)
+            .collect();
+        assert_eq!(colors, vec![Color::Indexed(12), Color::Indexed(14), Color::Indexed(13)]);
+    }
+
+    #[test]
+    fn test_adjust_colors() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 2.);
+        let l = xpr.cel().unwrap();
+        l.borrow_mut().content.push(pixel!(0, 0, Color::rgba(0, 0, 0, 255)));
+        l.borrow_mut().content.push(pixel!(0, 1, Color::Indexed(0)));
+        let color_at = |xpr: &Xprite, x| xpr.cel().unwrap().borrow().content.get_pixel(0, x).unwrap().color;
+
+        let black = Some(XpriteRgba::black());
+        let white = Some(XpriteRgba::white());
+        let shown = |xpr: &Xprite| {
+            let mut rdr = ImageRenderer::new(xpr.canvas.bg, xpr.canvas.art_w, xpr.canvas.art_h);
+            xpr.preview(&mut rdr).unwrap();
+            rdr.render(Some(xpr)).unwrap();
+            let rgba = |x| {
+                let p = rdr.image.get_pixel(x, 0);
+                XpriteRgba {
+                    r: p[0],
+                    g: p[1],
+                    b: p[2],
+                    a: p[3],
+                }
+            };
+            (Some(rgba(0)), Some(rgba(1)))
+        };
+
+        // the preview is drawn over the layer, the document does not change
+        xpr.preview_adjustment(Adjustment::Invert, ReplaceScope::Layer, false).unwrap();
+        assert_eq!(shown(&xpr), (white, white));
+        assert_eq!(color_at(&xpr, 0), Color::rgba(0, 0, 0, 255));
+        assert_eq!(color_at(&xpr, 1), Color::Indexed(0));
+        xpr.preview_adjustment(Adjustment::Invert, ReplaceScope::Layer, true).unwrap();
+        assert_eq!(shown(&xpr), (white, white));
+        assert_eq!(Color::Indexed(0).to_rgba(Some(&xpr)), black);
+        xpr.cancel_adjustment_preview();
+        assert_eq!(shown(&xpr), (black, black));
+
+        // committing or undoing drops the preview
+        xpr.preview_adjustment(Adjustment::Invert, ReplaceScope::Layer, false).unwrap();
+        xpr.commit();
+        assert_eq!(shown(&xpr), (black, black));
+
+        xpr.preview_adjustment(Adjustment::Desaturate, ReplaceScope::Layer, false).unwrap();
+        xpr.adjust_colors(Adjustment::Invert, ReplaceScope::Layer, false).unwrap();
+        assert_eq!(color_at(&xpr, 0), Color::rgba(255, 255, 255, 255));
+        assert_eq!(color_at(&xpr, 1), Color::rgba(255, 255, 255, 255));
+        xpr.undo();
+        assert_eq!(color_at(&xpr, 0), Color::rgba(0, 0, 0, 255));
+
+        // palette entries are restored by undo and adjusted again by redo
+        xpr.adjust_colors(Adjustment::Invert, ReplaceScope::Layer, true).unwrap();
+        assert_eq!(color_at(&xpr, 1), Color::Indexed(0));
+        assert_eq!(Color::Indexed(0).to_rgba(Some(&xpr)), white);
+        xpr.undo();
+        assert_eq!(Color::Indexed(0).to_rgba(Some(&xpr)), black);
+        xpr.redo();
+        assert_eq!(Color::Indexed(0).to_rgba(Some(&xpr)), white);
+        xpr.undo();
+        xpr.undo();
+        assert_eq!(Color::Indexed(0).to_rgba(Some(&xpr)), black);
+        assert!(xpr.adjust_colors(Adjustment::Invert, ReplaceScope::Selection, false).is_err());
+
+        // undo across a palette swap leaves the new palette alone
+        xpr.adjust_colors(Adjustment::Invert, ReplaceScope::Layer, true).unwrap();
+        let red = PaletteGroup::new(vec![("red".to_owned(), Color::red())].into_iter().collect());
+        xpr.palette.add_to_library("red", red);
+        xpr.use_library_palette().unwrap();
+        let used = xpr.palette.current_palette().colors.borrow().clone();
+        xpr.undo();
+        xpr.undo();
+        assert_eq!(*xpr.palette.current_palette().colors.borrow(), used);
+    }
+
+    #[test]
+    fn test_add_ramp_undo() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 2.);
+        let len = xpr.palette.current_palette().colors.borrow().len();
+        xpr.add_ramp("skin", XpriteRgba::white(), &RampParams::default());
+        assert!(xpr.palette.current_palette().ramp("skin").is_some());
+        xpr.undo();
+        assert!(xpr.palette.current_palette().ramp("skin").is_none());
+        assert_eq!(xpr.palette.current_palette().colors.borrow().len(), len);
+        xpr.redo();
+        assert!(xpr.palette.current_palette().colors.borrow().len() > len);
+        assert!(xpr.palette.current_palette().ramp("skin").is_some());
+    }
+
+    #[test]
+    fn test_shade_pixels() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 4., 2.);
+        xpr.palette.current_palette_mut().ramps.insert("grey".to_owned(), vec![0, 5, 6, 7]);
+        let l = xpr.cel().unwrap();
+        l.borrow_mut().content.push(pixel!(0, 0, Color::Indexed(5)));
+        l.borrow_mut().content.push(pixel!(0, 1, Color::Indexed(7)));
+        l.borrow_mut().content.push(pixel!(0, 2, Color::Indexed(8)));
+        let stroke = pixels!(
+            pixel!(0, 0, Color::Indexed(1)),
+            pixel!(0, 1, Color::Indexed(1)),
+            pixel!(0, 2, Color::Indexed(1)),
+            pixel!(0, 3, Color::Indexed(1))
+        );
+        let lighter = xpr.shade_pixels(&stroke, 1);
+        assert_eq!(lighter.len(), 3);
+        assert_eq!(lighter.get_pixel(0, 0).unwrap().color, Color::Indexed(6));
+        assert_eq!(lighter.get_pixel(0, 1).unwrap().color, Color::Indexed(7));
+        // red is on no ramp and steps to the next lighter palette color
+        let red = lighter.get_pixel(0, 2).unwrap().color;
+        assert_ne!(red, Color::Indexed(8));
+        assert_eq!(Some(red), xpr.palette.current_palette().shade(8, 1).map(Color::Indexed));
+        let darker = xpr.shade_pixels(&stroke, -1);
+        assert_eq!(darker.get_pixel(0, 0).unwrap().color, Color::Indexed(0));
+        assert_eq!(darker.get_pixel(0, 1).unwrap().color, Color::Indexed(6));
+    }
 }
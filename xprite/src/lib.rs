@@ -9,10 +9,12 @@
 extern crate bitflags;
 pub extern crate ase;
 extern crate fnv;
+extern crate gif;
 extern crate hex;
 extern crate image as img;
 extern crate imageproc;
 extern crate palette;
+extern crate png;
 #[macro_use]
 extern crate log;
 
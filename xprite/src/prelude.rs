@@ -1,19 +1,32 @@
-pub use crate::algorithms::{path::Path, polyline::Polyline};
+pub use crate::algorithms::{
+    adjust::Adjustment,
+    dither::DitherPattern,
+    path::Path,
+    polyline::Polyline,
+    quantize::{ColorSpace, Dithering, PaletteExtraction},
+    replace::ReplaceScope,
+    stabilizer::{Stabilizer, StabilizerMode},
+    stroke::StrokePipeline,
+    upscale::UpscaleFilter,
+    vision::VisionSimulation,
+};
 pub use crate::core::{
-    brush::{Brush, BrushType},
+    brush::{Brush, BrushLibrary, BrushType, PatternMode},
     color::{Color, XpriteRgba},
+    cycle::{ColorCycle, CycleDirection},
     frames::Frames,
     geom::{CubicBezierSegment, Rect, Vec2f},
     history::History,
     input::{InputEvent, InputItem, InputState},
     outline::{MarqueePixel, Outline},
-    palette::PaletteManager,
+    palette::{PaletteFormat, PaletteGroup, PaletteManager, PaletteSort},
     pixels::{Pixel, Pixels},
+    ramp::RampParams,
     toolbox::Toolbox,
     xprite::Xprite,
 };
 pub use crate::rendering::{
-    canvas::Canvas,
+    canvas::{Canvas, GridMode},
     image_renderer::{save_img, ImageRenderer},
     traits::Renderer,
 };
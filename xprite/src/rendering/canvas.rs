@@ -1,5 +1,7 @@
+use crate::algorithms::iso::iso_grid_lines;
 use crate::prelude::*;
 use crate::rendering::Renderer;
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Scroll {
@@ -13,6 +15,42 @@ impl Default for Scroll {
     }
 }
 
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum GridMode {
+    /// one cell per pixel
+    Pixel,
+    /// 2:1 diamonds of `tile_w` by `tile_w / 2` pixels
+    Isometric,
+}
+
+impl GridMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            GridMode::Pixel => "Pixel",
+            GridMode::Isometric => "Isometric",
+        }
+    }
+
+    pub const VARIANTS: [GridMode; 2] = [GridMode::Pixel, GridMode::Isometric];
+}
+
+impl FromStr for GridMode {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Pixel" => Ok(GridMode::Pixel),
+            "Isometric" => Ok(GridMode::Isometric),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for GridMode {
+    fn default() -> Self {
+        GridMode::Pixel
+    }
+}
+
 #[derive(Debug)]
 pub struct Canvas {
     pub bg: Color,
@@ -25,6 +63,9 @@ pub struct Canvas {
     pub art_h: f64,
     pub scroll: Scroll,
     pub show_grid: bool,
+    pub grid_mode: GridMode,
+    /// width of an isometric tile in pixels, shared by the grid and the isometric tools
+    pub tile_w: u32,
     pub initialized: bool,
 }
 
@@ -45,6 +86,8 @@ impl Default for Canvas {
 
             scroll: Scroll::default(),
             show_grid: false,
+            grid_mode: GridMode::Pixel,
+            tile_w: 32,
 
             initialized: false,
         }
@@ -202,6 +245,12 @@ impl Canvas {
         let o = self.origin();
 
         let color = XpriteRgba::black().into();
+        if self.grid_mode == GridMode::Isometric {
+            for (p0, p1) in iso_grid_lines(self.art_w, self.art_h, f64::from(self.tile_w)) {
+                self.draw_line(rdr, p0, p1, color);
+            }
+            return;
+        }
         let mut x = 0.;
         while x < self.scale * self.art_w {
             rdr.line([o.x + x, o.y], [o.x + x, o.y + self.scale * self.art_h], color);
@@ -2,6 +2,7 @@ use crate::prelude::*;
 use crate::rendering::{MouseCursorType, Renderer};
 use img::GenericImage;
 use img::{DynamicImage, Rgba};
+use std::collections::HashMap;
 
 pub struct ImageRenderer {
     color: Color,
@@ -61,6 +62,28 @@ impl ImageRenderer {
         Self { w, h, image, draw_list, color }
     }
 
+    /// post-processes the rendered image as seen with `sim`, the document is untouched
+    pub fn simulate(&mut self, sim: VisionSimulation) {
+        if sim == VisionSimulation::Normal {
+            return;
+        }
+        let mut seen = HashMap::new();
+        let mut image = self.image.to_rgba();
+        for p in image.pixels_mut() {
+            let col = XpriteRgba {
+                r: p[0],
+                g: p[1],
+                b: p[2],
+                a: p[3],
+            };
+            let out = *seen.entry(col).or_insert_with(|| sim.apply(col));
+            *p = Rgba {
+                data: [out.r, out.g, out.b, out.a],
+            };
+        }
+        self.image = DynamicImage::ImageRgba8(image);
+    }
+
     pub fn as_img(&self) -> &DynamicImage {
         &self.image
     }
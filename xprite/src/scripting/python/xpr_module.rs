@@ -1,6 +1,7 @@
 use crate::algorithms;
 use crate::prelude::*;
 use pyo3::class::{basic::PyObjectProtocol, number::PyNumberProtocol};
+use pyo3::exceptions;
 use pyo3::types::PyTuple;
 
 pub fn init_mod(py: Python) -> PyResult<&PyModule> {
@@ -10,11 +11,13 @@ pub fn init_mod(py: Python) -> PyResult<&PyModule> {
     pymod.add::<(i32, i32, i32, i32)>("BLUE", Color::blue().into())?;
     pymod.add_class::<Pixel>()?;
     pymod.add_class::<MyPixels>()?;
+    pymod.add_class::<MyStrokePipeline>()?;
 
     pymod.add_function(wrap_function!(add))?;
     pymod.add_function(wrap_function!(bezier))?;
     pymod.add_function(wrap_function!(rect))?;
     pymod.add_function(wrap_function!(line))?;
+    pymod.add_function(wrap_function!(extract_palette_from_image))?;
 
     Ok(pymod)
 }
@@ -117,6 +120,14 @@ impl MyPixels {
         self.p = self.shift(dist)?.p;
         Ok(self)
     }
+
+    /// at most `n` colors representing the pixels, indexed colors are skipped
+    pub fn extract_palette(&self, n: usize, mode: &str) -> PyResult<Vec<(i32, i32, i32, i32)>> {
+        let mode = parse_extraction(mode)?;
+        let colors: Vec<XpriteRgba> = self.p.iter().filter_map(|p| p.color.to_rgba(None)).collect();
+        let pal = algorithms::quantize::extract_palette(&colors, n, mode);
+        Ok(pal.into_iter().map(Into::into).collect())
+    }
 }
 
 #[pyproto]
@@ -134,6 +145,51 @@ impl PyNumberProtocol for MyPixels {
     }
 }
 
+#[pyclass(name=StrokePipeline)]
+pub struct MyStrokePipeline {
+    pub p: StrokePipeline,
+}
+
+#[pymethods]
+impl MyStrokePipeline {
+    #[new]
+    #[args(
+        pixel_perfect = "None",
+        sorted_monotonic = "false",
+        selective_anti_aliasing = "false",
+        aa_threshold = "0.5",
+        min_segment_length = "2"
+    )]
+    fn __new__(
+        obj: &PyRawObject,
+        pixel_perfect: Option<bool>,
+        sorted_monotonic: bool,
+        selective_anti_aliasing: bool,
+        aa_threshold: f32,
+        min_segment_length: i32,
+    ) -> PyResult<()> {
+        let p = StrokePipeline {
+            run_pixel_perfect: pixel_perfect,
+            sorted_monotonic,
+            selective_anti_aliasing,
+            aa_threshold,
+            min_segment_length,
+            ..StrokePipeline::new()
+        };
+        obj.init(|_| MyStrokePipeline { p })
+    }
+
+    /// color anti-aliased pixels are blended towards
+    pub fn set_aa_color(&mut self, pycolor: &PyTuple) -> PyResult<()> {
+        self.p.aa_alt_color = Some(pycolor.into());
+        Ok(())
+    }
+
+    pub fn run(&self, pixs: &MyPixels) -> PyResult<MyPixels> {
+        Ok(MyPixels { p: self.p.run(pixs.p.clone()) })
+    }
+}
+
 impl From<&PyTuple> for Vec2f {
     fn from(p: &PyTuple) -> Vec2f {
         Vec2f {
@@ -194,3 +250,17 @@ fn line(start: &PyTuple, stop: &PyTuple) -> PyResult<MyPixels> {
     let p = algorithms::line::continuous_line(p0.point, p1.point);
     Ok(MyPixels { p })
 }
+
+#[pyfunction(name=extract_palette_from_image)]
+fn extract_palette_from_image(path: &str, n: usize, mode: &str) -> PyResult<Vec<(i32, i32, i32, i32)>> {
+    let mode = parse_extraction(mode)?;
+    let im = img::open(path).map_err(|e| exceptions::IOError::py_err(format!("cannot open {}: {}", path, e)))?;
+    let pal = PaletteGroup::extract_from_image(&im, n, mode);
+    let colors = pal.colors.borrow();
+    Ok(colors.values().filter_map(|c| c.to_rgba(None)).map(Into::into).collect())
+}
+
+fn parse_extraction(mode: &str) -> PyResult<PaletteExtraction> {
+    mode.parse()
+        .map_err(|_| exceptions::ValueError::py_err(format!("unknown palette extraction mode {}", mode)))
+}
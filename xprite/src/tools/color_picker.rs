@@ -4,7 +4,8 @@ use crate::prelude::*;
 pub struct ColorPicker {
     cursor: Option<Pixels>,
     hovered_color: Option<Color>,
-    clicked_color: Option<Color>,
+    /// picked color and the button it was picked with
+    clicked_color: Option<(Color, InputItem)>,
 }
 
 impl ColorPicker {
@@ -53,13 +54,10 @@ impl Tool for ColorPicker {
         Ok(())
     }
 
-    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, _button: InputItem) -> Result<(), String> {
+    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
         let point = xpr.canvas.shrink_size(p);
-        self.clicked_color = if let Some(col) = self.find_color(xpr, point) {
-            Some(col)
-        } else {
-            Some(Color::transparent())
-        };
+        let col = self.find_color(xpr, point).unwrap_or_else(Color::transparent);
+        self.clicked_color = Some((col, button));
         Ok(())
     }
 
@@ -68,8 +66,13 @@ impl Tool for ColorPicker {
             // order is important
             xpr.color_picker_color = Some(hovered_color);
         }
-        if let Some(col) = self.clicked_color {
-            xpr.palette.set_color(col);
+        if let Some((col, button)) = self.clicked_color {
+            // right click picks the secondary color
+            if button == InputItem::Right {
+                xpr.palette.set_secondary_color(col);
+            } else {
+                xpr.palette.set_color(col);
+            }
             xpr.color_picker_color = None;
         }
         self.clicked_color = None;
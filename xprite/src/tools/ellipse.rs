@@ -21,6 +21,12 @@ pub struct Ellipse {
     snap: bool,
     symmetric: bool,
     pub filled: bool,
+    /// degrees counter-clockwise the ellipse is turned about its center
+    pub angle: f32,
+    /// only pixels on the pattern are painted
+    pub pattern: DitherPattern,
+    /// run over outlines, filled shapes are left alone
+    pub pipeline: StrokePipeline,
     buffer: Option<Pixels>,
 }
 
@@ -33,6 +39,9 @@ impl Ellipse {
             snap: false,
             symmetric: false,
             filled: false,
+            angle: 0.,
+            pattern: DitherPattern::Solid,
+            pipeline: StrokePipeline::new(),
             buffer: None,
         }
     }
@@ -68,7 +77,13 @@ impl Ellipse {
         if let (Some(start), Some(stop)) = (self.start_pos, self.cursor_pos) {
             let end = self.process_snap(start, stop);
             let begin_pos = self.process_symmetry(start, end);
-            get_ellipse(Some(begin_pos), Some(end), self.filled, color)
+            let pixs = if self.angle != 0. {
+                let angle = f64::from(self.angle).to_radians();
+                get_rotated_ellipse(Some(begin_pos), Some(end), angle, self.filled, color)?
+            } else {
+                get_ellipse(Some(begin_pos), Some(end), self.filled, color)?
+            };
+            Ok(if self.filled { pixs } else { self.pipeline.run(pixs) })
         } else {
             Err("start or end is none".to_owned())
         }
@@ -103,7 +118,7 @@ impl Ellipse {
     }
 
     fn finalize_ellipse(&mut self, xpr: &Xprite) -> Result<bool, String> {
-        if let Ok(pixs) = self.get_ellipse(xpr.color()) {
+        if let Ok(pixs) = self.get_ellipse(xpr.color_for(self.is_mouse_down)) {
             if pixs.is_empty() {
                 return Ok(false);
             }
@@ -133,7 +148,7 @@ impl Tool for Ellipse {
     }
 
     fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
-        if InputItem::Left != button {
+        if InputItem::Left != button && InputItem::Right != button {
             return Ok(());
         }
         self.is_mouse_down = Some(button);
@@ -144,7 +159,7 @@ impl Tool for Ellipse {
 
     fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
         if let Some(pixs) = &self.buffer {
-            xpr.finalize_pixels(&pixs)?;
+            xpr.finalize_pixels(&self.pattern.mask(pixs))?;
             self.buffer = None;
             Ok(true)
         } else {
@@ -155,10 +170,10 @@ impl Tool for Ellipse {
     fn draw(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
         xpr.new_frame();
         if let Some(p) = self.cursor_pos {
-            xpr.set_cursor(&pixels!(pixel!(p, xpr.color())));
+            xpr.set_cursor(&pixels!(pixel!(p, xpr.color_for(self.is_mouse_down))));
         }
-        if let Ok(pixs) = self.get_ellipse(xpr.color()) {
-            xpr.add_pixels(&pixs);
+        if let Ok(pixs) = self.get_ellipse(xpr.color_for(self.is_mouse_down)) {
+            xpr.add_pixels(&self.pattern.mask(&pixs));
             Ok(true)
         } else {
             Ok(false)
@@ -181,10 +196,15 @@ impl Tool for Ellipse {
                 }
                 _ => error!("unimpl for ctrl: {}", value),
             },
+            "angle" => {
+                self.angle = value.parse().map_err(|_| format!("invalid angle: {}", value))?;
+            }
             "alt" => {
                 info!("alt pressed (unimplemented)");
             }
-            _ => (),
+            _ => {
+                self.pipeline.set(option, value)?;
+            }
         }
         Ok(())
     }
@@ -24,9 +24,9 @@ impl BufferedPolyline {
         self.polyline.push(point);
     }
 
-    pub fn process(&mut self, brush: &Brush, color: Color) -> Result<&Pixels, String> {
+    pub fn process(&mut self, brush: &Brush, pipeline: &StrokePipeline, color: Color) -> Result<&Pixels, String> {
         self.upto += self.polyline.pos.len();
-        let line_pixs = self.polyline.connect_with_line(color)?;
+        let line_pixs = pipeline.run(self.polyline.connect_with_line(color)?);
         let brushstroke = brush.follow_stroke(&line_pixs).unwrap();
         self.buffer.extend(&brushstroke);
         Ok(&self.buffer)
@@ -46,6 +46,7 @@ pub struct Eraser {
     cursor: Option<Pixels>,
     cursor_pos: Option<Vec2f>,
     pub brush: Brush,
+    pub pipeline: StrokePipeline,
     draw_buffer: Pixels,
     last_mouse_down_or_up: Option<Vec2f>,
     finalized: Option<FinalizeType>,
@@ -80,13 +81,14 @@ impl Eraser {
             last_mouse_down_or_up: None,
             cursor_pos,
             brush,
+            pipeline: StrokePipeline::new(),
             finalized: None,
             draw_buffer: Pixels::new(),
         }
     }
 
     fn draw_line(&self, color: Color) -> Option<Pixels> {
-        let buf = continuous_line(self.last_mouse_down_or_up?, self.cursor_pos?, color);
+        let buf = self.pipeline.run(continuous_line(self.last_mouse_down_or_up?, self.cursor_pos?, color));
         let buf = self.brush.follow_stroke(&buf)?;
         Some(buf)
     }
@@ -109,7 +111,7 @@ impl Eraser {
                 let start = self.last_mouse_down_or_up;
                 let stop = self.cursor_pos;
                 if let (Some(start), Some(stop)) = (start, stop) {
-                    let buf = continuous_line(start, stop, color);
+                    let buf = self.pipeline.run(continuous_line(start, stop, color));
                     add(&self.brush.follow_stroke(&buf).unwrap());
                     Ok(true)
                 } else {
@@ -119,7 +121,7 @@ impl Eraser {
             Some(FinalizeType::Polyline) => {
                 let b = Rc::clone(&self.buffered_polyline);
                 let mut c = b.borrow_mut();
-                let buf = c.process(&self.brush, color);
+                let buf = c.process(&self.brush, &self.pipeline, color);
                 if let Ok(buf) = buf {
                     add(buf);
                     Ok(true)
@@ -156,7 +158,7 @@ impl Tool for Eraser {
 
         let b = Rc::clone(&self.buffered_polyline);
         let mut c = b.borrow_mut();
-        let stroke = c.process(&self.brush, xpr.color())?;
+        let stroke = c.process(&self.brush, &self.pipeline, xpr.color())?;
         self.draw_buffer.extend(&stroke);
 
         // let pixels = self.brush.to_canvas_pixels(p, xpr.color());
@@ -256,7 +258,9 @@ impl Tool for Eraser {
                 }
                 _ => error!("malformed value: {}", value),
             },
-            _ => (),
+            _ => {
+                self.pipeline.set(option, value)?;
+            }
         }
         Ok(())
     }
@@ -0,0 +1,309 @@
+use crate::algorithms::floodfill::{floodfill, FloodFillDegrees};
+use crate::algorithms::gradient::*;
+use crate::algorithms::line::snap_angle;
+use crate::tools::*;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum GradientColors {
+    /// the palette ramp of the current color, up to the other color if it is on the same ramp
+    Ramp,
+    /// the primary and the secondary color
+    Endpoints,
+}
+
+impl GradientColors {
+    pub fn as_str(&self) -> &str {
+        match self {
+            GradientColors::Ramp => "Ramp",
+            GradientColors::Endpoints => "Endpoints",
+        }
+    }
+
+    pub const VARIANTS: [GradientColors; 2] = [GradientColors::Ramp, GradientColors::Endpoints];
+}
+
+impl FromStr for GradientColors {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Ramp" => Ok(GradientColors::Ramp),
+            "Endpoints" => Ok(GradientColors::Endpoints),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for GradientColors {
+    fn default() -> Self {
+        GradientColors::Ramp
+    }
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum GradientRegion {
+    /// the marquee selection
+    Selection,
+    /// the area flood filled from where the drag starts
+    FloodFill,
+}
+
+impl GradientRegion {
+    pub fn as_str(&self) -> &str {
+        match self {
+            GradientRegion::Selection => "Selection",
+            GradientRegion::FloodFill => "Flood fill",
+        }
+    }
+
+    pub const VARIANTS: [GradientRegion; 2] = [GradientRegion::Selection, GradientRegion::FloodFill];
+}
+
+impl FromStr for GradientRegion {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Selection" => Ok(GradientRegion::Selection),
+            "Flood fill" => Ok(GradientRegion::FloodFill),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for GradientRegion {
+    fn default() -> Self {
+        GradientRegion::FloodFill
+    }
+}
+
+/// Fills a region with palette colors in bands from where the drag starts
+/// to where it ends. Shift snaps the drag to 45 degrees.
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    is_mouse_down: Option<InputItem>,
+    cursor_pos: Option<Vec2f>,
+    start_pos: Option<Vec2f>,
+    snap: bool,
+    /// pixels filled by the current drag
+    target: Option<Pixels>,
+    /// colors of the current drag
+    bands: Vec<Color>,
+    buffer: Option<Pixels>,
+    pub shape: GradientShape,
+    pub source: GradientColors,
+    pub region: GradientRegion,
+    /// neighbours of the flood fill
+    pub degrees: FloodFillDegrees,
+    /// share of each band dithered into the next, hard edges at 0
+    pub dither: f32,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gradient {
+    pub fn new() -> Self {
+        Self {
+            is_mouse_down: None,
+            cursor_pos: None,
+            start_pos: None,
+            snap: false,
+            target: None,
+            bands: vec![],
+            buffer: None,
+            shape: GradientShape::Linear,
+            source: GradientColors::Ramp,
+            region: GradientRegion::FloodFill,
+            degrees: Default::default(),
+            dither: 0.5,
+        }
+    }
+
+    /// Bands from the color of `button` to the other one. A ramp runs from
+    /// the color to the other color if that is on the same ramp, otherwise to
+    /// the far end of the ramp.
+    pub fn colors(&self, xpr: &Xprite, button: Option<InputItem>) -> Result<Vec<Color>, String> {
+        let first = xpr.color_for(button);
+        let second = match button {
+            Some(InputItem::Right) => xpr.color(),
+            _ => xpr.secondary_color(),
+        };
+        if self.source == GradientColors::Endpoints {
+            return Ok(vec![first, second]);
+        }
+        let pal = xpr.palette.current_palette();
+        let (from, to) = match (first, second) {
+            (Color::Indexed(from), Color::Indexed(to)) => (from, to),
+            _ => return Err("the colors are not in the palette".to_owned()),
+        };
+        let (_, ramp, pos) = pal.ramp_of(from).ok_or_else(|| "the color is on no palette ramp".to_owned())?;
+        let end = match ramp.iter().position(|&i| i == to) {
+            Some(end) if end != pos => end,
+            _ if pos < ramp.len() - 1 - pos => ramp.len() - 1,
+            _ => 0,
+        };
+        let mut ret: Vec<_> = ramp[pos.min(end)..=pos.max(end)].iter().map(|&i| Color::Indexed(i)).collect();
+        if end < pos {
+            ret.reverse();
+        }
+        Ok(ret)
+    }
+
+    /// pixels the gradient fills for a drag starting at `point`
+    pub fn area(&self, xpr: &Xprite, point: Vec2f) -> Result<Pixels, String> {
+        let (w, h) = (xpr.canvas.art_w, xpr.canvas.art_h);
+        let pixs = match self.region {
+            GradientRegion::Selection => {
+                let bb = xpr.selection().ok_or_else(|| "nothing selected".to_owned())?;
+                let (x0, y0) = (bb.0.x.min(bb.1.x) as isize, bb.0.y.min(bb.1.y) as isize);
+                let (x1, y1) = (bb.0.x.max(bb.1.x) as isize, bb.0.y.max(bb.1.y) as isize);
+                (y0..=y1)
+                    .flat_map(|y| (x0..=x1).map(move |x| pixel!(y, x, Color::red())))
+                    .filter(|p| !oob(p.point.x, p.point.y, w, h))
+                    .collect()
+            }
+            GradientRegion::FloodFill => {
+                let l = xpr.cel().ok_or_else(|| "no layer".to_owned())?;
+                let layer = l.borrow();
+                let bg_color = layer.get_color(point);
+                floodfill(w, h, &layer.content, point, bg_color, Color::red(), self.degrees)
+            }
+        };
+        if pixs.is_empty() {
+            return Err("nothing to fill".to_owned());
+        }
+        Ok(pixs)
+    }
+
+    fn end_pos(&self) -> Option<Vec2f> {
+        let (start, stop) = (self.start_pos?, self.cursor_pos?);
+        if self.snap {
+            Some(snap_angle(start, stop, PI / 4.))
+        } else {
+            Some(stop)
+        }
+    }
+
+    fn get_gradient(&self) -> Option<Pixels> {
+        let (target, start, end) = (self.target.as_ref()?, self.start_pos?, self.end_pos()?);
+        Some(gradient(target, self.shape, start, end, &self.bands, f64::from(self.dither)))
+    }
+}
+
+impl Tool for Gradient {
+    fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
+        self.cursor_pos = Some(xpr.canvas.shrink_size(p));
+        Ok(())
+    }
+
+    fn mouse_up(&mut self, xpr: &mut Xprite, p: Vec2f) -> Result<(), String> {
+        if self.is_mouse_down.is_none() {
+            return Ok(());
+        }
+        self.cursor_pos = Some(xpr.canvas.shrink_size(p));
+        self.buffer = self.get_gradient();
+        self.is_mouse_down = None;
+        self.start_pos = None;
+        self.target = None;
+        Ok(())
+    }
+
+    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
+        if InputItem::Left != button && InputItem::Right != button {
+            return Ok(());
+        }
+        // right button runs from the secondary color
+        let point = xpr.canvas.shrink_size(p);
+        self.bands = self.colors(xpr, Some(button))?;
+        self.target = Some(self.area(xpr, point)?);
+        self.is_mouse_down = Some(button);
+        self.start_pos = Some(point);
+        self.cursor_pos = Some(point);
+        Ok(())
+    }
+
+    fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        if let Some(pixs) = &self.buffer {
+            xpr.finalize_pixels(pixs)?;
+            self.buffer = None;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn draw(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        xpr.new_frame();
+        if let Some(p) = self.cursor_pos {
+            xpr.set_cursor(&pixels!(pixel!(p, xpr.color_for(self.is_mouse_down))));
+        }
+        if let Some(pixs) = self.get_gradient() {
+            xpr.add_pixels(&pixs);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn set(&mut self, _xpr: &Xprite, option: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value for {}: {}", option, value);
+        match option {
+            "LShift" | "RShift" => match value {
+                "true" => self.snap = true,
+                "false" => self.snap = false,
+                _ => error!("unimpl for shift: {}", value),
+            },
+            "shape" => self.shape = GradientShape::from_str(value).map_err(|_| invalid())?,
+            "colors" => self.source = GradientColors::from_str(value).map_err(|_| invalid())?,
+            "region" => self.region = GradientRegion::from_str(value).map_err(|_| invalid())?,
+            "dither" => self.dither = value.parse().map_err(|_| invalid())?,
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_gradient_tool() {
+        use super::*;
+        let mut xpr = Xprite::new("test".to_owned(), 8., 4.);
+        {
+            let pal = xpr.palette.current_palette_mut();
+            pal.ramps.insert("test".to_owned(), vec![1, 2, 3, 4, 5]);
+            pal.idx = 2;
+            pal.secondary_idx = 4;
+        }
+        let mut tool = Gradient::new();
+        let idx = |colors: Vec<Color>| colors.iter().map(|c| if let Color::Indexed(i) = c { *i } else { 0 }).collect::<Vec<_>>();
+        assert_eq!(idx(tool.colors(&xpr, None).unwrap()), vec![2, 3, 4]);
+        assert_eq!(idx(tool.colors(&xpr, Some(InputItem::Right)).unwrap()), vec![4, 3, 2]);
+        // off the ramp the bands run to its far end
+        xpr.palette.current_palette_mut().secondary_idx = 0;
+        assert_eq!(idx(tool.colors(&xpr, None).unwrap()), vec![2, 3, 4, 5]);
+        assert!(tool.colors(&xpr, Some(InputItem::Right)).is_err());
+        tool.source = GradientColors::Endpoints;
+        assert_eq!(idx(tool.colors(&xpr, None).unwrap()), vec![2, 0]);
+
+        // an empty layer floods entirely
+        assert_eq!(tool.area(&xpr, vec2f!(1, 1)).unwrap().len(), 32);
+        assert!(tool.area(&xpr, vec2f!(9, 9)).is_err());
+        tool.region = GradientRegion::Selection;
+        assert!(tool.area(&xpr, vec2f!(1, 1)).is_err());
+
+        tool.region = GradientRegion::FloodFill;
+        tool.bands = tool.colors(&xpr, None).unwrap();
+        tool.target = tool.area(&xpr, vec2f!(0, 0)).ok();
+        tool.start_pos = Some(vec2f!(0, 0));
+        tool.cursor_pos = Some(vec2f!(1, 7));
+        tool.snap = true;
+        assert_eq!(tool.end_pos(), Some(vec2f!(0, 7)));
+        let pixs = tool.get_gradient().unwrap();
+        assert_eq!(pixs.len(), 32);
+        assert_eq!(pixs.get_pixel(3, 0).unwrap().color, Color::Indexed(2));
+        assert_eq!(pixs.get_pixel(0, 7).unwrap().color, Color::Indexed(0));
+    }
+}
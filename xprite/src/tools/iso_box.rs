@@ -0,0 +1,200 @@
+use crate::algorithms::iso::*;
+use crate::tools::*;
+
+/// Drags out the three visible faces of an isometric box. The drag spans the
+/// box, shift snaps it to the tiles of the canvas grid.
+#[derive(Clone, Debug)]
+pub struct IsoBox {
+    is_mouse_down: Option<InputItem>,
+    cursor_pos: Option<Vec2f>,
+    start_pos: Option<Vec2f>,
+    snap: bool,
+    buffer: Option<Pixels>,
+    pub filled: bool,
+    pub outlined: bool,
+    /// ramp steps from the current color for the top, left and right faces
+    pub shades: [isize; 3],
+    /// ramp steps from the current color for the edges
+    pub outline_shade: isize,
+}
+
+impl Default for IsoBox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsoBox {
+    pub fn new() -> Self {
+        Self {
+            is_mouse_down: None,
+            cursor_pos: None,
+            start_pos: None,
+            snap: false,
+            buffer: None,
+            filled: true,
+            outlined: true,
+            shades: [1, 0, -1],
+            outline_shade: -2,
+        }
+    }
+
+    /// top left corner, half width `n` in 2:1 steps and side height of the
+    /// box spanned by the drag
+    pub fn geometry(&self, tile_w: u32) -> Option<(Vec2f, usize, usize)> {
+        let start = self.start_pos?;
+        let stop = self.cursor_pos?;
+        let d = stop - start;
+        let mut n = ((d.x.abs() as usize + 1) / 4).max(1);
+        let mut h = (d.y.abs() as usize + 1).saturating_sub(2 * n);
+        if self.snap {
+            let tile_w = tile_width(tile_w) as usize;
+            let (tile_n, tile_h) = (tile_w / 4, tile_w / 2);
+            n = ((n + tile_n / 2) / tile_n).max(1) * tile_n;
+            h = (h + tile_h / 2) / tile_h * tile_h;
+        }
+        // the box grows away from where the drag started
+        let x = if d.x < 0. { start.x + 1. - (4 * n) as f64 } else { start.x };
+        let y = if d.y < 0. { start.y + 1. - (2 * n + h) as f64 } else { start.y };
+        let mut origin = vec2f_xy!(x, y);
+        if self.snap {
+            origin = snap_to_tile(origin, tile_width(tile_w));
+        }
+        Some((origin, n, h))
+    }
+
+    /// `[top, left, right, outline]` colors shaded along the ramp of `color`, see `PaletteGroup::shade`
+    fn colors(&self, xpr: &Xprite, color: Color) -> [Color; 4] {
+        let pal = xpr.palette.current_palette();
+        let shade = |steps| match color {
+            Color::Indexed(idx) => pal.shade(idx, steps).map(Color::Indexed).unwrap_or(color),
+            _ => color,
+        };
+        [shade(self.shades[0]), shade(self.shades[1]), shade(self.shades[2]), shade(self.outline_shade)]
+    }
+
+    /// pixels of the box, faces in `colors[..3]` and edges in `colors[3]`
+    pub fn shape(&self, origin: Vec2f, n: usize, h: usize, colors: [Color; 4]) -> Pixels {
+        let mut pixs = Pixels::new();
+        if self.filled {
+            for (face, &color) in iso_box_faces(origin, n, h).iter_mut().zip(colors.iter()) {
+                face.set_color(color);
+                pixs.extend(face);
+            }
+        }
+        if self.outlined {
+            // edges are drawn over the faces
+            for p in iso_box_edges(origin, n, h, colors[3]).iter() {
+                pixs.0.replace(*p);
+            }
+        }
+        pixs
+    }
+
+    fn get_box(&self, xpr: &Xprite) -> Option<Pixels> {
+        let (origin, n, h) = self.geometry(xpr.canvas.tile_w)?;
+        let colors = self.colors(xpr, xpr.color_for(self.is_mouse_down));
+        Some(self.shape(origin, n, h, colors))
+    }
+}
+
+impl Tool for IsoBox {
+    fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
+        self.cursor_pos = Some(xpr.canvas.shrink_size(p));
+        Ok(())
+    }
+
+    fn mouse_up(&mut self, xpr: &mut Xprite, p: Vec2f) -> Result<(), String> {
+        if self.is_mouse_down.is_none() {
+            return Ok(());
+        }
+        self.cursor_pos = Some(xpr.canvas.shrink_size(p));
+        self.buffer = self.get_box(xpr);
+        self.is_mouse_down = None;
+        self.start_pos = None;
+        Ok(())
+    }
+
+    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
+        if InputItem::Left != button && InputItem::Right != button {
+            return Ok(());
+        }
+        // right button shades the secondary color
+        self.is_mouse_down = Some(button);
+        self.start_pos = Some(xpr.canvas.shrink_size(p));
+        Ok(())
+    }
+
+    fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        if let Some(pixs) = &self.buffer {
+            xpr.finalize_pixels(pixs)?;
+            self.buffer = None;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn draw(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        xpr.new_frame();
+        if let Some(p) = self.cursor_pos {
+            xpr.set_cursor(&pixels!(pixel!(p, xpr.color_for(self.is_mouse_down))));
+        }
+        if let Some(pixs) = self.get_box(xpr) {
+            xpr.add_pixels(&pixs);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn set(&mut self, _xpr: &Xprite, option: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value for {}: {}", option, value);
+        match option {
+            "LShift" | "RShift" => match value {
+                "true" => self.snap = true,
+                "false" => self.snap = false,
+                _ => error!("unimpl for shift: {}", value),
+            },
+            "filled" => self.filled = value.parse().map_err(|_| invalid())?,
+            "outlined" => self.outlined = value.parse().map_err(|_| invalid())?,
+            "top_shade" => self.shades[0] = value.parse().map_err(|_| invalid())?,
+            "left_shade" => self.shades[1] = value.parse().map_err(|_| invalid())?,
+            "right_shade" => self.shades[2] = value.parse().map_err(|_| invalid())?,
+            "outline_shade" => self.outline_shade = value.parse().map_err(|_| invalid())?,
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_iso_box_drag() {
+        use super::*;
+        let mut tool = IsoBox::new();
+        tool.start_pos = Some(vec2f!(10, 10));
+        tool.cursor_pos = Some(vec2f!(21, 25));
+        // 16 wide and 12 tall: a diamond 8 tall with sides 4 tall
+        assert_eq!(tool.geometry(32), Some((vec2f!(10, 10), 4, 4)));
+        // dragging up and left grows the box the other way
+        tool.cursor_pos = Some(vec2f!(-1, -5));
+        assert_eq!(tool.geometry(32), Some((vec2f!(-1, -5), 4, 4)));
+        // snapped to one tile 16 wide with sides a tile tall
+        tool.snap = true;
+        tool.start_pos = Some(vec2f!(1, 1));
+        tool.cursor_pos = Some(vec2f!(16, 18));
+        assert_eq!(tool.geometry(16), Some((vec2f!(0, 0), 4, 8)));
+
+        let colors = [Color::Indexed(1), Color::Indexed(2), Color::Indexed(3), Color::Indexed(0)];
+        let pixs = tool.shape(vec2f!(0, 0), 3, 3, colors);
+        for color in &colors {
+            assert!(pixs.iter().any(|p| p.color == *color));
+        }
+        // edges replace the face pixels under them
+        assert_eq!(pixs.len(), 2 * (2 + 2 + 4 + 4 + 6 + 6) + 2 * 6 * 3);
+        tool.filled = false;
+        assert!(tool.shape(vec2f!(0, 0), 3, 3, colors).iter().all(|p| p.color == Color::Indexed(0)));
+    }
+}
@@ -1,6 +1,45 @@
 use crate::algorithms::line::*;
 use crate::tools::*;
 
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum LineMode {
+    /// shift snaps to the clean ratios, ctrl to 45 degrees
+    Free,
+    /// every line is 2:1 or vertical
+    Isometric,
+}
+
+impl LineMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LineMode::Free => "Free",
+            LineMode::Isometric => "Isometric",
+        }
+    }
+
+    pub const VARIANTS: [LineMode; 2] = [LineMode::Free, LineMode::Isometric];
+}
+
+impl FromStr for LineMode {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Free" => Ok(LineMode::Free),
+            "Isometric" => Ok(LineMode::Isometric),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for LineMode {
+    fn default() -> Self {
+        LineMode::Free
+    }
+}
+
+/// steps of an isometric line
+pub const ISOMETRIC_STEPS: [(u32, u32); 2] = [(2, 1), (0, 1)];
+
 #[derive(Clone, Default, Debug)]
 pub struct Line {
     is_mouse_down: Option<InputItem>,
@@ -8,10 +47,17 @@ pub struct Line {
     start_pos: Option<Vec2f>,
     snap: bool,
     is_snap_45: bool,
+    pub mode: LineMode,
+    /// ratios shift snaps to, each in both orientations
+    pub ratios: Vec<(u32, u32)>,
+    pub pipeline: StrokePipeline,
     buffer: Option<Pixels>,
 }
 
 impl Line {
+    /// ratios offered in the panel
+    pub const RATIO_CHOICES: [(u32, u32); 6] = [(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (3, 2)];
+
     pub fn new() -> Self {
         Line {
             is_mouse_down: None,
@@ -19,18 +65,49 @@ impl Line {
             start_pos: None,
             snap: false,
             is_snap_45: false,
+            mode: LineMode::Free,
+            ratios: vec![(1, 1), (2, 1), (3, 1)],
+            pipeline: StrokePipeline::new(),
             buffer: None,
         }
     }
 
-    fn get_line(&self, color: Color) -> Option<Pixels> {
+    /// the repeated step and how many of them, when the line is snapped to a ratio
+    fn step(&self) -> Option<(Vec2f, usize)> {
         let start = self.start_pos?;
         let stop = self.cursor_pos?;
-        if self.snap {
-            Some(snapped_line(self.is_snap_45, start, stop, color))
-        } else {
-            Some(continuous_line(start, stop, color))
+        match self.mode {
+            LineMode::Isometric => Some(snap_to_steps(start, stop, &ISOMETRIC_STEPS)),
+            LineMode::Free if self.snap && !self.is_snap_45 => Some(snap_to_steps(start, stop, &ratio_steps(&self.ratios))),
+            LineMode::Free => None,
+        }
+    }
+
+    /// horizontal to vertical pixels of the line being drawn, in lowest terms
+    pub fn ratio(&self) -> Option<(u32, u32)> {
+        let (x, y) = match self.step() {
+            Some((step, _)) => (step.x.abs() as u32, step.y.abs() as u32),
+            None => {
+                let d = self.cursor_pos? - self.start_pos?;
+                (d.x.abs() as u32, d.y.abs() as u32)
+            }
+        };
+        let gcd = gcd(x, y);
+        if gcd == 0 {
+            return None;
         }
+        Some((x / gcd, y / gcd))
+    }
+
+    fn get_line(&self, color: Color) -> Option<Pixels> {
+        let start = self.start_pos?;
+        let stop = self.cursor_pos?;
+        let line = match self.step() {
+            Some((step, n)) => stepped_line(start, step, n, color),
+            None if self.snap => snapped_line(self.is_snap_45, start, stop, color),
+            None => continuous_line(start, stop, color),
+        };
+        Some(self.pipeline.run(line))
     }
 
     fn finalize_line(&mut self, color: Color) -> Result<(), String> {
@@ -41,6 +118,14 @@ impl Line {
     }
 }
 
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
 impl Tool for Line {
     fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
         // set current cursor_pos
@@ -52,14 +137,14 @@ impl Tool for Line {
     fn mouse_up(&mut self, xpr: &mut Xprite, p: Vec2f) -> Result<(), String> {
         let point = xpr.canvas.shrink_size(p);
         self.cursor_pos = Some(point);
-        self.finalize_line(xpr.color())?;
+        self.finalize_line(xpr.color_for(self.is_mouse_down))?;
         self.is_mouse_down = None;
         self.start_pos = None;
         Ok(())
     }
 
     fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
-        if InputItem::Left != button {
+        if InputItem::Left != button && InputItem::Right != button {
             return Ok(());
         }
         self.is_mouse_down = Some(button);
@@ -82,10 +167,10 @@ impl Tool for Line {
         xpr.new_frame();
 
         if let Some(p) = self.cursor_pos {
-            xpr.set_cursor(&pixels!(pixel!(p, xpr.color())));
+            xpr.set_cursor(&pixels!(pixel!(p, xpr.color_for(self.is_mouse_down))));
         }
 
-        if let Some(pixs) = self.get_line(xpr.color()) {
+        if let Some(pixs) = self.get_line(xpr.color_for(self.is_mouse_down)) {
             xpr.add_pixels(&pixs);
             Ok(true)
         } else {
@@ -114,8 +199,64 @@ impl Tool for Line {
             "alt" => {
                 info!("alt pressed (unimplemented)");
             }
-            _ => info!("unimplemented option: {}", option),
+            "mode" => {
+                if let Ok(mode) = LineMode::from_str(value) {
+                    self.mode = mode;
+                }
+            }
+            "ratios" => {
+                // "1:1,2:1"
+                let parse = |r: &str| -> Option<(u32, u32)> {
+                    let mut it = r.trim().splitn(2, ':');
+                    Some((it.next()?.parse().ok()?, it.next()?.parse().ok()?))
+                };
+                self.ratios = value
+                    .split(',')
+                    .filter(|r| !r.trim().is_empty())
+                    .map(|r| parse(r).ok_or_else(|| format!("malformed ratio: {}", r)))
+                    .collect::<Result<_, _>>()?;
+            }
+            _ => {
+                if !self.pipeline.set(option, value)? {
+                    info!("unimplemented option: {}", option);
+                }
+            }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_line_ratio() {
+        use super::*;
+        let mut line = Line::new();
+        line.start_pos = Some(vec2f!(0, 0));
+        line.cursor_pos = Some(vec2f!(5, 11));
+        assert_eq!(line.ratio(), Some((11, 5)));
+        assert_eq!(line.get_line(Color::red()).unwrap().len(), 12);
+
+        line.snap = true;
+        assert_eq!(line.ratio(), Some((2, 1)));
+        let snapped = line.get_line(Color::red()).unwrap();
+        assert_eq!(snapped.len(), 11);
+        assert!(snapped.get_pixel(5, 10).is_some());
+
+        line.snap = false;
+        line.mode = LineMode::Isometric;
+        line.cursor_pos = Some(vec2f!(4, 9));
+        assert_eq!(line.ratio(), Some((2, 1)));
+        line.cursor_pos = Some(vec2f!(9, 1));
+        assert_eq!(line.ratio(), Some((0, 1)));
+
+        let xpr = Xprite::new("test".to_owned(), 10., 10.);
+        line.set(&xpr, "ratios", "1:1, 4:1").unwrap();
+        assert_eq!(line.ratios, vec![(1, 1), (4, 1)]);
+        assert!(line.set(&xpr, "ratios", "4").is_err());
+
+        line.set(&xpr, "pixel_perfect", "true").unwrap();
+        assert_eq!(line.pipeline.run_pixel_perfect, Some(true));
+        assert!(line.set(&xpr, "pixel_perfect", "yes").is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::core::outline::outline_rect;
 use crate::tools::*;
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Debug)]
 pub struct Marquee {
     is_mouse_down: Option<InputItem>,
     cursor_pos: Option<Vec2f>,
@@ -9,6 +9,15 @@ pub struct Marquee {
 
     move_orig_pos: Option<Vec2f>,
     move_final_pos: Option<Vec2f>,
+
+    pub upscale_filter: UpscaleFilter,
+    pub upscale_scale: u32,
+}
+
+impl Default for Marquee {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Marquee {
@@ -19,6 +28,8 @@ impl Marquee {
             cursor_pos: None,
             move_orig_pos: None,
             move_final_pos: None,
+            upscale_filter: UpscaleFilter::Scale2x,
+            upscale_scale: 2,
         }
     }
 
@@ -4,11 +4,16 @@ pub mod autoshade;
 pub mod color_picker;
 pub mod ellipse;
 pub mod eraser;
+pub mod gradient;
+pub mod iso_box;
 pub mod line;
 pub mod marquee;
 pub mod paint_bucket;
 pub mod pencil;
+pub mod polygon;
 pub mod rect;
+pub mod shape;
+pub mod spray;
 pub mod symmetry;
 pub mod texture;
 pub mod vector;
@@ -35,6 +40,11 @@ pub enum ToolType {
     Settings,
     Symmetry,
     AutoShade,
+    Spray,
+    Polygon,
+    IsoBox,
+    Shape,
+    Gradient,
 }
 
 impl Default for ToolType {
@@ -44,7 +54,7 @@ impl Default for ToolType {
 }
 
 impl ToolType {
-    pub const VARIANTS: [ToolType; 10] = [
+    pub const VARIANTS: [ToolType; 15] = [
         ToolType::Pencil,
         ToolType::Line,
         ToolType::PaintBucket,
@@ -55,6 +65,11 @@ impl ToolType {
         ToolType::Ellipse,
         ToolType::Marquee,
         ToolType::AutoShade,
+        ToolType::Spray,
+        ToolType::Polygon,
+        ToolType::IsoBox,
+        ToolType::Shape,
+        ToolType::Gradient,
     ];
 
     pub fn as_str(&self) -> &str {
@@ -74,6 +89,11 @@ impl ToolType {
             ToolType::Settings => "Settings",
             ToolType::Symmetry => "Symmetry",
             ToolType::AutoShade => "AutoShade",
+            ToolType::Spray => "Spray",
+            ToolType::Polygon => "Polygon",
+            ToolType::IsoBox => "IsoBox",
+            ToolType::Shape => "Shape",
+            ToolType::Gradient => "Gradient",
         }
     }
 }
@@ -97,6 +117,11 @@ impl FromStr for ToolType {
             "Settings" => Ok(ToolType::Settings),
             "Symmetry" => Ok(ToolType::Symmetry),
             "AutoShade" => Ok(ToolType::AutoShade),
+            "Spray" => Ok(ToolType::Spray),
+            "Polygon" => Ok(ToolType::Polygon),
+            "IsoBox" => Ok(ToolType::IsoBox),
+            "Shape" => Ok(ToolType::Shape),
+            "Gradient" => Ok(ToolType::Gradient),
             _ => Err(()),
         }
     }
@@ -40,27 +40,30 @@ impl Default for PaintBucketMode {
 #[derive(Clone, Debug, Default)]
 pub struct PaintBucket {
     cursor: Option<Pixels>,
-    is_mouse_down: bool,
+    is_mouse_down: Option<InputItem>,
     update_buffer: Option<Pixels>,
     draw_buffer: Option<Pixels>,
     pub mode: PaintBucketMode,
     pub degrees: FloodFillDegrees,
+    /// only pixels on the pattern are filled
+    pub pattern: DitherPattern,
 }
 
 impl PaintBucket {
     pub fn new() -> Self {
         PaintBucket {
             cursor: None,
-            is_mouse_down: false,
+            is_mouse_down: None,
             update_buffer: None,
             mode: PaintBucketMode::Fill,
             draw_buffer: None,
             degrees: Default::default(),
+            pattern: Default::default(),
         }
     }
 
     pub fn floodfill(&self, xpr: &Xprite, p: Vec2f, bg_color: Option<Color>) -> Result<Pixels, String> {
-        let color = xpr.color();
+        let color = xpr.color_for(self.is_mouse_down);
         let w = xpr.canvas.art_w;
         let h = xpr.canvas.art_h;
         let l = xpr.cel().unwrap();
@@ -73,8 +76,8 @@ impl PaintBucket {
 
 impl Tool for PaintBucket {
     fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
-        if self.is_mouse_down {
-            return self.mouse_down(xpr, p, InputItem::Left);
+        if let Some(button) = self.is_mouse_down {
+            return self.mouse_down(xpr, p, button);
         }
         let point = xpr.canvas.shrink_size(p);
         let color = xpr.color();
@@ -83,7 +86,7 @@ impl Tool for PaintBucket {
     }
 
     fn mouse_up(&mut self, xpr: &mut Xprite, p: Vec2f) -> Result<(), String> {
-        self.is_mouse_down = false;
+        self.is_mouse_down = None;
 
         // reset cursor
         let point = xpr.canvas.shrink_size(p);
@@ -99,8 +102,9 @@ impl Tool for PaintBucket {
         Ok(())
     }
 
-    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, _button: InputItem) -> Result<(), String> {
-        self.is_mouse_down = true;
+    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
+        // right button fills with the secondary color
+        self.is_mouse_down = Some(button);
         let point = xpr.canvas.shrink_size(p);
         let bg_color = xpr.cel().unwrap().borrow().get_color(point);
 
@@ -117,13 +121,16 @@ impl Tool for PaintBucket {
             }
         };
 
-        self.cursor = Some(pixels!(Pixel { point, color: xpr.color() }));
+        self.cursor = Some(pixels!(Pixel {
+            point,
+            color: xpr.color_for(self.is_mouse_down)
+        }));
         Ok(())
     }
 
     fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
         if let Some(pixs) = &self.update_buffer {
-            xpr.finalize_pixels(&pixs)?;
+            xpr.finalize_pixels(&self.pattern.mask(pixs))?;
             self.update_buffer = None;
             Ok(true)
         } else {
@@ -138,7 +145,7 @@ impl Tool for PaintBucket {
         }
         if let Some(pixs) = &self.draw_buffer {
             // pixs.set_color(xpr.color());
-            xpr.add_pixels(&pixs);
+            xpr.add_pixels(&self.pattern.mask(pixs));
             Ok(true)
         } else {
             Ok(false)
@@ -3,17 +3,17 @@
 //! The pencil is a pipelined tool:
 //!
 //! 1. Raw input
-//! 2. Pixel perfect
-//! 3. Pixel AntiPerfect
-//! 4. Sorted Monotonic
-//! 5. Selective Anti-Alias
+//! 2. Stabilizer
+//! 3. Smooth to curve
+//! 4. Stroke pipeline, shared with the other drawing tools
+//! 5. Follow stroke with the brush
 //!
 //! Internally, it is implemented this way:
 //!
 //! 1. Raw input
-//! 2. pixel_perfect? or pixel_antiperfect?
-//! 3. sorted_monotonic?
-//! 4. anti_alias?
+//! 2. stabilize
+//! 3. smooth_curve?
+//! 4. pipeline.run
 //! 5. follow_stroke
 
 use crate::algorithms::line::continuous_line;
@@ -21,86 +21,78 @@ use crate::prelude::*;
 
 #[derive(PartialEq, Clone, Debug, Default)]
 pub struct PencilProcessor {
-    /// pixel perfect - nothing else
-    ///
-    /// None -> None
-    /// true -> pixel perfect
-    /// false -> pixel anti-perfect
-    pub run_pixel_perfect: Option<bool>,
-    /// sort each monotonic segment
-    pub sorted_monotonic: bool,
-    /// Anti-aliasing with background color for each segment
-    pub selective_anti_aliasing: bool,
-    /// aa color
-    pub aa_alt_color: Option<Color>,
-    /// aa threshold
-    pub aa_threshold: f32,
-    /// aa min_segment_length
-    pub min_segment_length: i32,
+    pub pipeline: StrokePipeline,
+    /// fit the finished stroke with bezier curves
+    pub smooth_curve: bool,
+    /// simplification tolerance before fitting, in pixels
+    pub smooth_tolerance: f32,
+    pub stabilizer: Stabilizer,
     pub polyline: Polyline,
 }
 
 impl PencilProcessor {
     pub fn new() -> Self {
         Self {
-            run_pixel_perfect: Some(true),
-            sorted_monotonic: false,
-            selective_anti_aliasing: false,
-            aa_alt_color: None,
-            aa_threshold: 0.5,
-            min_segment_length: 2,
+            pipeline: StrokePipeline::pixel_perfect(),
+            smooth_curve: false,
+            smooth_tolerance: 1.,
+            stabilizer: Stabilizer::new(),
             polyline: Polyline::new(),
         }
     }
 
     pub fn clear(&mut self) {
         self.polyline.clear();
+        self.stabilizer.clear();
+    }
+
+    /// the stroke simplified and fitted with bezier curves, None if it is too short
+    fn smoothed(&self, color: Color) -> Option<Pixels> {
+        let mut line = self.polyline.clone();
+        line.pos.dedup();
+        let simplified = line.reumann_witkam(f64::from(self.smooth_tolerance)).ok()?;
+        let path = simplified.interp();
+        if path.segments.is_empty() {
+            return simplified.connect_with_line(color).ok();
+        }
+        let mut pixs = path.rasterize(false, color)?;
+        // sampling stops short of the end of the path
+        pixs.push(pixel!(simplified.pos[simplified.pos.len() - 1], color));
+        Some(pixs)
     }
 
     pub fn finalize(&self, brush: &Brush, color: Color) -> Result<Pixels, String> {
-        let mut points = self.polyline.connect_with_line(color)?;
-        // TODO: check self.moved
-        match self.run_pixel_perfect {
-            None => (),
-            Some(true) => {
-                points.pixel_perfect();
-            }
-            Some(false) => {
-                points.pixel_antiperfect();
-            }
+        let smoothed = if self.smooth_curve { self.smoothed(color) } else { None };
+        let points = match smoothed {
+            Some(points) => points,
+            None => self.polyline.connect_with_line(color)?,
         };
-        if self.sorted_monotonic {
-            assert_eq!(self.run_pixel_perfect, Some(true));
-            if points.len() > 1 {
-                // TODO: move len check inside function
-                points.monotonic_sort();
-            }
-        }
-        if self.selective_anti_aliasing {
-            assert_eq!(self.run_pixel_perfect, Some(true));
-            points.selective_antialias(
-                f64::from(self.aa_threshold),
-                self.aa_alt_color.unwrap_or_else(||Color::Indexed(0)),
-                self.min_segment_length as usize,
-            );
-        }
+        // TODO: check self.moved
+        let points = self.pipeline.run(points);
         let path = brush.follow_stroke(&points).unwrap();
         Ok(path)
     }
 
     pub fn draw(&self, brush: &Brush, color: Color) -> Result<Pixels, String> {
-        let mut points = self.polyline.connect_with_line(color)?;
-        match self.run_pixel_perfect {
-            None => (),
-            Some(true) => points.pixel_perfect(),
-            Some(false) => points.pixel_antiperfect(),
-        };
+        let points = self.pipeline.preview(self.polyline.connect_with_line(color)?);
         let path = brush.follow_stroke(&points).unwrap();
         Ok(path)
     }
 
     pub fn push(&mut self, point: Vec2f) {
-        self.polyline.push(point)
+        match self.stabilizer.stabilize(point) {
+            // stabilized points fall between pixels
+            Some(p) if self.stabilizer.mode != StabilizerMode::None => self.polyline.push(p.floor()),
+            Some(p) => self.polyline.push(p),
+            None => (),
+        }
+    }
+
+    /// ends the stroke where the input stopped at `point`
+    pub fn release(&mut self, point: Vec2f) {
+        if let Some(p) = self.stabilizer.release(point) {
+            self.polyline.push(p.floor())
+        }
     }
 }
 
@@ -113,6 +105,10 @@ pub struct Pencil {
     last_mouse_down_or_up: Option<Vec2f>,
     shift: bool,
     pub brush: Brush,
+    /// only pixels on the pattern are painted
+    pub pattern: DitherPattern,
+    /// step existing colors along their palette ramp instead of painting, see `PaletteGroup::shade`
+    pub shading: bool,
     moved: bool,
     draw_buffer: Pixels,
     update_buffer: Option<Pixels>,
@@ -141,6 +137,8 @@ impl Pencil {
             shift: false,
             cursor,
             brush,
+            pattern: DitherPattern::default(),
+            shading: false,
             moved: false,
             draw_buffer: Pixels::new(),
             update_buffer: None,
@@ -150,7 +148,7 @@ impl Pencil {
 
     fn finalize_continuous_line(&mut self, xpr: &Xprite, start: Option<Vec2f>, stop: Option<Vec2f>) -> Result<(), String> {
         if let (Some(start), Some(stop)) = (start, stop) {
-            let buf = continuous_line(start, stop, xpr.color());
+            let buf = self.processor.pipeline.run(continuous_line(start, stop, xpr.color_for(self.is_mouse_down)));
             let buf = self.brush.follow_stroke(&buf).unwrap();
             self.update_buffer = Some(buf);
         }
@@ -158,14 +156,27 @@ impl Pencil {
     }
 
     fn finalize(&mut self, xpr: &Xprite) -> Result<(), String> {
-        let buf = self.processor.finalize(&self.brush, xpr.color())?;
+        let buf = self.processor.finalize(&self.brush, xpr.color_for(self.is_mouse_down))?;
         self.update_buffer = Some(buf);
         Ok(())
     }
 
+    /// pixels painted for `pixs`, the left button shades lighter and the right one darker
+    fn output(&self, xpr: &Xprite, pixs: &Pixels) -> Pixels {
+        let pixs = self.pattern.mask(pixs);
+        if !self.shading {
+            return pixs;
+        }
+        let steps = match self.is_mouse_down {
+            Some(InputItem::Right) => -1,
+            _ => 1,
+        };
+        xpr.shade_pixels(&pixs, steps)
+    }
+
     fn draw_line(&self, color: Color) -> Option<Pixels> {
         let buf = continuous_line(self.last_mouse_down_or_up?, self.cursor_pos?, color);
-        let buf = self.brush.follow_stroke(&buf)?;
+        let buf = self.brush.follow_stroke(&self.processor.pipeline.preview(buf))?;
         Some(buf)
     }
 }
@@ -173,12 +184,12 @@ impl Pencil {
 impl Tool for Pencil {
     fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
         let point = xpr.canvas.shrink_size(p);
-        let pixels = self.brush.to_canvas_pixels(point, xpr.color());
+        let pixels = self.brush.to_canvas_pixels(point, xpr.color_for(self.is_mouse_down));
         self.cursor = pixels.clone();
         self.cursor_pos = Some(point);
 
         if self.shift {
-            if let Some(pixs) = self.draw_line(xpr.color()) {
+            if let Some(pixs) = self.draw_line(xpr.color_for(self.is_mouse_down)) {
                 self.draw_buffer = pixs;
                 self.redraw = true;
                 return Ok(());
@@ -190,9 +201,9 @@ impl Tool for Pencil {
             return Ok(());
         }
         self.moved = true;
-        self.processor.polyline.push(point);
+        self.processor.push(point);
 
-        let stroke = self.processor.draw(&self.brush, xpr.color())?;
+        let stroke = self.processor.draw(&self.brush, xpr.color_for(self.is_mouse_down))?;
         self.draw_buffer = stroke;
         self.redraw = true;
 
@@ -207,16 +218,15 @@ impl Tool for Pencil {
         let point = xpr.canvas.shrink_size(p);
         self.last_mouse_down_or_up = Some(point);
 
-        let button = self.is_mouse_down.unwrap();
-        if button == InputItem::Right {
-            return Ok(());
-        }
-
         if self.shift {
             self.finalize_continuous_line(xpr, prev, self.cursor_pos)?;
         } else {
+            self.processor.release(point);
             self.finalize(xpr)?;
         }
+        if let Some(buf) = &self.update_buffer {
+            self.update_buffer = Some(self.output(xpr, buf));
+        }
 
         self.processor.clear();
         self.is_mouse_down = None;
@@ -231,16 +241,12 @@ impl Tool for Pencil {
         let point = xpr.canvas.shrink_size(p);
         self.is_mouse_down = Some(button);
 
-        self.processor.polyline.push(point);
-        let pixels = self.brush.to_canvas_pixels(xpr.canvas.shrink_size(p), xpr.color());
-        // TODO:
+        self.processor.push(point);
+        // right button paints with the secondary color
+        let pixels = self.brush.to_canvas_pixels(xpr.canvas.shrink_size(p), xpr.color_for(Some(button)));
         if let Some(pixels) = pixels {
-            if button == InputItem::Left {
-                self.draw_buffer.extend(&pixels);
-                self.redraw = true;
-            } else {
-                // xpr.remove_pixels(&pixels);
-            }
+            self.draw_buffer.extend(&pixels);
+            self.redraw = true;
         }
         Ok(())
     }
@@ -251,7 +257,7 @@ impl Tool for Pencil {
             xpr.set_cursor(cursor);
         }
         if self.redraw {
-            xpr.add_pixels(&self.draw_buffer);
+            xpr.add_pixels(&self.output(xpr, &self.draw_buffer));
             self.redraw = false;
             Ok(true)
         } else {
@@ -261,7 +267,7 @@ impl Tool for Pencil {
 
     fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
         if let Some(pixs) = &self.update_buffer {
-            xpr.finalize_pixels(&pixs)?;
+            xpr.finalize_pixels(pixs)?;
             self.update_buffer = None;
             Ok(true)
         } else {
@@ -278,7 +284,7 @@ impl Tool for Pencil {
             "LShift" | "RShift" => match value {
                 "true" => {
                     self.shift = true;
-                    if let Some(pixs) = self.draw_line(xpr.color()) {
+                    if let Some(pixs) = self.draw_line(xpr.color_for(self.is_mouse_down)) {
                         self.draw_buffer = pixs;
                     }
                     self.redraw = true;
@@ -290,8 +296,57 @@ impl Tool for Pencil {
                 }
                 _ => error!("malformed value: {}", value),
             },
-            _ => (),
+            _ => {
+                self.processor.pipeline.set(option, value)?;
+            }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_smooth_curve() {
+        use super::*;
+        let mut processor = PencilProcessor::new();
+        processor.smooth_curve = true;
+        processor.pipeline.run_pixel_perfect = None;
+        // jitter within the tolerance is straightened out
+        for x in 0..=30 {
+            processor.push(vec2f!(if x % 3 == 2 { 1 } else { 0 }, x));
+        }
+        let stroke = processor.finalize(&Brush::pixel(), Color::red()).unwrap();
+        assert_eq!(stroke.len(), 31);
+        assert!(stroke.iter().all(|p| p.point.y == 0.));
+
+        processor.clear();
+        for x in 0..=30 {
+            processor.push(vec2f!((x - 15) * (x - 15) / 20, x));
+        }
+        let stroke = processor.finalize(&Brush::pixel(), Color::red()).unwrap();
+        assert!(stroke.get_pixel(11, 0).is_some());
+        assert!(stroke.get_pixel(0, 15).is_some());
+        assert!(stroke.get_pixel(11, 30).is_some());
+    }
+
+    #[test]
+    fn test_stabilized_stroke() {
+        use super::*;
+        let mut processor = PencilProcessor::new();
+        processor.stabilizer.mode = StabilizerMode::LazyRope;
+        for x in 0..=30 {
+            processor.push(vec2f!(0, x));
+        }
+        assert_eq!(processor.polyline.pos.first(), Some(&vec2f!(0, 0)));
+        assert_eq!(processor.polyline.pos.last(), Some(&vec2f!(0, 26)));
+        processor.release(vec2f!(0, 30));
+        assert_eq!(processor.polyline.pos.last(), Some(&vec2f!(0, 30)));
+
+        // raw input is kept as it is
+        processor.clear();
+        processor.stabilizer.mode = StabilizerMode::None;
+        processor.push(vec2f!(0.5, 1.5));
+        assert_eq!(processor.polyline.pos.last(), Some(&vec2f!(0.5, 1.5)));
+    }
+}
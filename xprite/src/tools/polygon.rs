@@ -0,0 +1,297 @@
+use crate::algorithms::line::{continuous_line, snap_angle, snap_ratio};
+use crate::algorithms::polygon::*;
+use crate::tools::*;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum PolygonMode {
+    Polygon,
+    /// left open after the last vertex
+    Polyline,
+}
+
+impl PolygonMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PolygonMode::Polygon => "Polygon",
+            PolygonMode::Polyline => "Polyline",
+        }
+    }
+
+    pub const VARIANTS: [PolygonMode; 2] = [PolygonMode::Polygon, PolygonMode::Polyline];
+}
+
+impl FromStr for PolygonMode {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Polygon" => Ok(PolygonMode::Polygon),
+            "Polyline" => Ok(PolygonMode::Polyline),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for PolygonMode {
+    fn default() -> Self {
+        PolygonMode::Polygon
+    }
+}
+
+/// how edges snap while shift is held
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum PolygonSnap {
+    Angle15,
+    Angle45,
+    /// 1:1, 1:2 and 1:3 steps
+    PixelRatio,
+}
+
+impl PolygonSnap {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PolygonSnap::Angle15 => "15 degrees",
+            PolygonSnap::Angle45 => "45 degrees",
+            PolygonSnap::PixelRatio => "Pixel ratio",
+        }
+    }
+
+    pub const VARIANTS: [PolygonSnap; 3] = [PolygonSnap::Angle15, PolygonSnap::Angle45, PolygonSnap::PixelRatio];
+
+    /// `stop` snapped relative to `start`
+    pub fn snap(self, start: Vec2f, stop: Vec2f) -> Vec2f {
+        match self {
+            PolygonSnap::Angle15 => snap_angle(start, stop, PI / 12.),
+            PolygonSnap::Angle45 => snap_angle(start, stop, PI / 4.),
+            PolygonSnap::PixelRatio => snap_ratio(start, stop, &[(1, 1), (1, 2), (1, 3)]),
+        }
+    }
+}
+
+impl FromStr for PolygonSnap {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "15 degrees" => Ok(PolygonSnap::Angle15),
+            "45 degrees" => Ok(PolygonSnap::Angle45),
+            "Pixel ratio" => Ok(PolygonSnap::PixelRatio),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for PolygonSnap {
+    fn default() -> Self {
+        PolygonSnap::Angle45
+    }
+}
+
+/// Adds a vertex on every click. Clicking the last vertex again (a double
+/// click), clicking the first vertex or pressing enter finishes the shape,
+/// escape drops it.
+#[derive(Clone, Debug)]
+pub struct Polygon {
+    is_mouse_down: Option<InputItem>,
+    /// button that placed the first vertex
+    button: Option<InputItem>,
+    cursor_pos: Option<Vec2f>,
+    vertices: Vec<Vec2f>,
+    shift: bool,
+    buffer: Option<Pixels>,
+    pub mode: PolygonMode,
+    pub filled: bool,
+    pub snap: PolygonSnap,
+    pub brush: Brush,
+    /// run over the edges
+    pub pipeline: StrokePipeline,
+}
+
+impl Default for Polygon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Polygon {
+    pub fn new() -> Self {
+        Self {
+            is_mouse_down: None,
+            button: None,
+            cursor_pos: None,
+            vertices: vec![],
+            shift: false,
+            buffer: None,
+            mode: PolygonMode::Polygon,
+            filled: false,
+            snap: PolygonSnap::Angle45,
+            brush: Brush::pixel(),
+            pipeline: StrokePipeline::pixel_perfect(),
+        }
+    }
+
+    /// where the next vertex goes for the cursor at `p`
+    fn next_vertex(&self, p: Vec2f) -> Vec2f {
+        match self.vertices.last() {
+            Some(&last) if self.shift => self.snap.snap(last, p),
+            _ => p,
+        }
+    }
+
+    /// pixels of the shape through `points`
+    pub fn shape(&self, points: &[Vec2f], color: Color) -> Pixels {
+        let closed = self.mode == PolygonMode::Polygon;
+        // edge by edge so pixel perfect keeps the corners
+        let mut outline = Pixels::new();
+        if let Some(&first) = points.first() {
+            outline.push(pixel!(first, color));
+        }
+        for (from, to) in polygon_edges(points, closed) {
+            outline.extend(&self.pipeline.run(continuous_line(from, to, color)));
+        }
+        let mut pixs = self.brush.follow_stroke(&outline).unwrap_or(outline);
+        if closed && self.filled {
+            pixs.extend(&filled_polygon(points, color));
+        }
+        pixs
+    }
+
+    /// commits the shape if it has enough vertices
+    fn finish(&mut self, xpr: &Xprite) {
+        let needed = match self.mode {
+            PolygonMode::Polygon => 3,
+            PolygonMode::Polyline => 2,
+        };
+        if self.vertices.len() >= needed {
+            self.buffer = Some(self.shape(&self.vertices, xpr.color_for(self.button)));
+        }
+        self.cancel();
+    }
+
+    fn cancel(&mut self) {
+        self.vertices.clear();
+        self.button = None;
+    }
+}
+
+impl Tool for Polygon {
+    fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
+        self.cursor_pos = Some(xpr.canvas.shrink_size(p));
+        Ok(())
+    }
+
+    fn mouse_up(&mut self, _xpr: &mut Xprite, _p: Vec2f) -> Result<(), String> {
+        self.is_mouse_down = None;
+        Ok(())
+    }
+
+    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
+        if InputItem::Left != button && InputItem::Right != button {
+            return Ok(());
+        }
+        self.is_mouse_down = Some(button);
+        let point = self.next_vertex(xpr.canvas.shrink_size(p));
+        if self.vertices.last() == Some(&point) {
+            self.finish(xpr);
+            return Ok(());
+        }
+        if self.mode == PolygonMode::Polygon && self.vertices.len() > 2 && self.vertices[0] == point {
+            self.finish(xpr);
+            return Ok(());
+        }
+        if self.vertices.is_empty() {
+            // right button draws with the secondary color
+            self.button = Some(button);
+        }
+        self.vertices.push(point);
+        Ok(())
+    }
+
+    fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        if let Some(pixs) = &self.buffer {
+            xpr.finalize_pixels(&pixs)?;
+            self.buffer = None;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn draw(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        xpr.new_frame();
+        let color = xpr.color_for(self.button);
+        let cursor = self.cursor_pos.map(|p| self.next_vertex(p));
+        if let Some(p) = cursor {
+            xpr.set_cursor(&pixels!(pixel!(p, color)));
+        }
+        if self.vertices.is_empty() {
+            return Ok(false);
+        }
+        let mut points = self.vertices.clone();
+        points.extend(cursor);
+        xpr.add_pixels(&self.shape(&points, color));
+        Ok(true)
+    }
+
+    fn set(&mut self, xpr: &Xprite, option: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value for {}: {}", option, value);
+        match option {
+            "LShift" | "RShift" => match value {
+                "true" => self.shift = true,
+                "false" => self.shift = false,
+                _ => error!("unimpl for shift: {}", value),
+            },
+            "Return" | "Enter" => {
+                if value == "true" {
+                    self.finish(xpr);
+                }
+            }
+            "Escape" => {
+                if value == "true" {
+                    self.cancel();
+                }
+            }
+            "mode" => self.mode = PolygonMode::from_str(value).map_err(|_| invalid())?,
+            "snap" => self.snap = PolygonSnap::from_str(value).map_err(|_| invalid())?,
+            "brush" => {
+                self.brush = value.parse()?;
+            }
+            _ => {
+                self.pipeline.set(option, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_polygon_shape() {
+        use super::*;
+        let mut tool = Polygon::new();
+        let triangle = [vec2f!(0, 0), vec2f!(6, 6), vec2f!(6, 0)];
+        let outline = tool.shape(&triangle, Color::red());
+        tool.filled = true;
+        let filled = tool.shape(&triangle, Color::red());
+        assert!(outline.len() < filled.len());
+        assert_eq!(filled.len(), 28);
+
+        // a polyline is never filled
+        tool.mode = PolygonMode::Polyline;
+        let open = tool.shape(&triangle, Color::red());
+        assert!(open.len() < outline.len());
+        assert!(open.get_pixel(3, 0).is_none());
+
+        tool.shift = true;
+        tool.vertices.push(vec2f!(0, 0));
+        tool.snap = PolygonSnap::PixelRatio;
+        assert_eq!(tool.next_vertex(vec2f!(5, 11)), vec2f!(5, 10));
+        tool.snap = PolygonSnap::Angle45;
+        assert_eq!(tool.next_vertex(vec2f!(5, 11)), vec2f!(9, 9));
+
+        let xpr = Xprite::new("test".to_owned(), 10., 10.);
+        assert!(tool.set(&xpr, "snap", "sideways").is_err());
+        assert!(tool.set(&xpr, "mode", "").is_err());
+        tool.set(&xpr, "pixel_perfect", "true").unwrap();
+        assert_eq!(tool.pipeline.run_pixel_perfect, Some(true));
+    }
+}
@@ -19,6 +19,12 @@ pub struct Rect {
     snap: bool,
     symmetric: bool,
     pub filled: bool,
+    /// radius of the rounded corners, square corners when 0
+    pub corner_radius: u32,
+    /// only pixels on the pattern are painted
+    pub pattern: DitherPattern,
+    /// run over outlines, filled shapes are left alone
+    pub pipeline: StrokePipeline,
     buffer: Option<Pixels>,
 }
 
@@ -31,6 +37,9 @@ impl Rect {
             snap: false,
             symmetric: false,
             filled: false,
+            corner_radius: 0,
+            pattern: DitherPattern::Solid,
+            pipeline: StrokePipeline::new(),
             buffer: None,
         }
     }
@@ -66,7 +75,12 @@ impl Rect {
         if let (Some(start), Some(stop)) = (self.start_pos, self.cursor_pos) {
             let end = self.process_snap(start, stop);
             let begin_pos = self.process_symmetry(start, end);
-            get_rect(Some(begin_pos), Some(end), self.filled, color)
+            let pixs = if self.corner_radius > 0 {
+                get_rounded_rect(Some(begin_pos), Some(end), self.corner_radius as i32, self.filled, color)?
+            } else {
+                get_rect(Some(begin_pos), Some(end), self.filled, color)?
+            };
+            Ok(if self.filled { pixs } else { self.pipeline.run(pixs) })
         } else {
             Err("start or end is none".to_owned())
         }
@@ -103,7 +117,7 @@ impl Rect {
     }
 
     fn finalize_rect(&mut self, xpr: &Xprite) -> Result<(), String> {
-        if let Ok(pixs) = self.get_rect(xpr.color()) {
+        if let Ok(pixs) = self.get_rect(xpr.color_for(self.is_mouse_down)) {
             self.buffer = Some(pixs);
         }
         Ok(())
@@ -128,7 +142,7 @@ impl Tool for Rect {
     }
 
     fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
-        if InputItem::Left != button {
+        if InputItem::Left != button && InputItem::Right != button {
             return Ok(());
         }
         self.is_mouse_down = Some(button);
@@ -139,7 +153,7 @@ impl Tool for Rect {
 
     fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
         if let Some(pixs) = &self.buffer {
-            xpr.finalize_pixels(&pixs)?;
+            xpr.finalize_pixels(&self.pattern.mask(pixs))?;
             self.buffer = None;
             Ok(true)
         } else {
@@ -151,11 +165,11 @@ impl Tool for Rect {
         xpr.new_frame();
 
         if let Some(p) = self.cursor_pos {
-            xpr.set_cursor(&pixels!(pixel!(p, xpr.color())));
+            xpr.set_cursor(&pixels!(pixel!(p, xpr.color_for(self.is_mouse_down))));
         }
 
-        if let Ok(pixs) = self.get_rect(xpr.color()) {
-            xpr.add_pixels(&pixs);
+        if let Ok(pixs) = self.get_rect(xpr.color_for(self.is_mouse_down)) {
+            xpr.add_pixels(&self.pattern.mask(&pixs));
             Ok(true)
         } else {
             Ok(false)
@@ -178,10 +192,15 @@ impl Tool for Rect {
                 }
                 _ => error!("unimpl for ctrl: {}", value),
             },
+            "corner_radius" => {
+                self.corner_radius = value.parse().map_err(|_| format!("invalid corner radius: {}", value))?;
+            }
             "alt" => {
                 info!("alt pressed (unimplemented)");
             }
-            _ => (),
+            _ => {
+                self.pipeline.set(option, value)?;
+            }
         }
         Ok(())
     }
@@ -0,0 +1,304 @@
+use crate::algorithms::line::continuous_line;
+use crate::algorithms::polygon::*;
+use crate::algorithms::shapes::*;
+use crate::tools::*;
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum ShapeKind {
+    RegularPolygon,
+    Star,
+    /// closed by its chord when filled
+    Arc,
+    Pie,
+}
+
+impl ShapeKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ShapeKind::RegularPolygon => "Regular polygon",
+            ShapeKind::Star => "Star",
+            ShapeKind::Arc => "Arc",
+            ShapeKind::Pie => "Pie",
+        }
+    }
+
+    pub const VARIANTS: [ShapeKind; 4] = [ShapeKind::RegularPolygon, ShapeKind::Star, ShapeKind::Arc, ShapeKind::Pie];
+}
+
+impl FromStr for ShapeKind {
+    type Err = ();
+    fn from_str(string: &str) -> Result<Self, ()> {
+        match string {
+            "Regular polygon" => Ok(ShapeKind::RegularPolygon),
+            "Star" => Ok(ShapeKind::Star),
+            "Arc" => Ok(ShapeKind::Arc),
+            "Pie" => Ok(ShapeKind::Pie),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for ShapeKind {
+    fn default() -> Self {
+        ShapeKind::RegularPolygon
+    }
+}
+
+/// Regular polygons, stars, arcs and pie slices fitted into the dragged box
+/// the same way the ellipse tool fits its ellipse.
+#[derive(Clone, Debug)]
+pub struct Shape {
+    is_mouse_down: Option<InputItem>,
+    cursor_pos: Option<Vec2f>,
+    start_pos: Option<Vec2f>,
+    snap: bool,
+    symmetric: bool,
+    pub kind: ShapeKind,
+    pub filled: bool,
+    /// sides of the polygon or tips of the star
+    pub sides: usize,
+    /// degrees counter-clockwise
+    pub rotation: f32,
+    /// inner vertices of the star as a share of the outer radius
+    pub inner_radius: f32,
+    /// degrees counter-clockwise from the right
+    pub arc_start: f32,
+    /// degrees the arc runs counter-clockwise
+    pub arc_sweep: f32,
+    /// only pixels on the pattern are painted
+    pub pattern: DitherPattern,
+    /// run over polygon and star outlines, filled shapes, arcs and pies are left alone
+    pub pipeline: StrokePipeline,
+    buffer: Option<Pixels>,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape {
+    pub fn new() -> Self {
+        Self {
+            is_mouse_down: None,
+            cursor_pos: None,
+            start_pos: None,
+            snap: false,
+            symmetric: false,
+            kind: ShapeKind::RegularPolygon,
+            filled: false,
+            sides: 5,
+            rotation: 0.,
+            inner_radius: 0.5,
+            arc_start: 0.,
+            arc_sweep: 270.,
+            pattern: DitherPattern::Solid,
+            pipeline: StrokePipeline::new(),
+            buffer: None,
+        }
+    }
+
+    /// pixels of the shape fitted into the box from `p0` to `p1`
+    pub fn shape(&self, p0: Vec2f, p1: Vec2f, color: Color) -> Pixels {
+        let rotation = f64::from(self.rotation).to_radians();
+        let (start, sweep) = (f64::from(self.arc_start).to_radians(), f64::from(self.arc_sweep).to_radians());
+        let vertices = match self.kind {
+            ShapeKind::RegularPolygon => regular_polygon(p0, p1, self.sides, rotation),
+            ShapeKind::Star => star(p0, p1, self.sides, f64::from(self.inner_radius), rotation),
+            // arcs are not traced in path order, so the pipeline would punch holes in them
+            ShapeKind::Arc => return chord(p0, p1, start, sweep, self.filled, color),
+            ShapeKind::Pie => return pie(p0, p1, start, sweep, self.filled, color),
+        };
+        if self.filled {
+            return filled_polygon(&vertices, color);
+        }
+        // edge by edge so pixel perfect keeps the corners
+        let mut pixs = Pixels::new();
+        for (from, to) in polygon_edges(&vertices, true) {
+            pixs.extend(&self.pipeline.run(continuous_line(from, to, color)));
+        }
+        pixs
+    }
+
+    fn get_shape(&self, color: Color) -> Result<Pixels, String> {
+        if let (Some(start), Some(stop)) = (self.start_pos, self.cursor_pos) {
+            let end = self.process_snap(start, stop);
+            let begin_pos = self.process_symmetry(start, end);
+            if begin_pos == end {
+                return Err("empty shape".to_owned());
+            }
+            Ok(self.shape(begin_pos, end, color))
+        } else {
+            Err("start or end is none".to_owned())
+        }
+    }
+
+    fn process_snap(&self, start: Vec2f, stop: Vec2f) -> Vec2f {
+        if self.snap {
+            let x0 = start.x;
+            let y0 = start.y;
+            let x1 = stop.x;
+            let y1 = stop.y;
+            let dx = x1 - x0;
+            let dy = y1 - y0;
+            let d = f64::min(dx, dy);
+            let mut end = start;
+            end.x = start.x + d;
+            end.y = start.y + d;
+            end
+        } else {
+            stop
+        }
+    }
+
+    fn process_symmetry(&self, start: Vec2f, end: Vec2f) -> Vec2f {
+        if self.symmetric {
+            let x = start.x - (end.x - start.x);
+            let y = start.y - (end.y - start.y);
+            vec2f_xy! {x, y}
+        } else {
+            self.start_pos.unwrap()
+        }
+    }
+
+    fn finalize_shape(&mut self, xpr: &Xprite) {
+        if let Ok(pixs) = self.get_shape(xpr.color_for(self.is_mouse_down)) {
+            self.buffer = Some(pixs);
+        }
+    }
+}
+
+impl Tool for Shape {
+    fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
+        let point = xpr.canvas.shrink_size(p);
+        self.cursor_pos = Some(point);
+        Ok(())
+    }
+
+    fn mouse_up(&mut self, xpr: &mut Xprite, p: Vec2f) -> Result<(), String> {
+        let point = xpr.canvas.shrink_size(p);
+        self.cursor_pos = Some(point);
+        self.finalize_shape(xpr);
+        self.is_mouse_down = None;
+        self.start_pos = None;
+        Ok(())
+    }
+
+    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
+        if InputItem::Left != button && InputItem::Right != button {
+            return Ok(());
+        }
+        self.is_mouse_down = Some(button);
+        let point = xpr.canvas.shrink_size(p);
+        self.start_pos = Some(point);
+        Ok(())
+    }
+
+    fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        if let Some(pixs) = &self.buffer {
+            xpr.finalize_pixels(&self.pattern.mask(pixs))?;
+            self.buffer = None;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn draw(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        xpr.new_frame();
+        if let Some(p) = self.cursor_pos {
+            xpr.set_cursor(&pixels!(pixel!(p, xpr.color_for(self.is_mouse_down))));
+        }
+        if let Ok(pixs) = self.get_shape(xpr.color_for(self.is_mouse_down)) {
+            xpr.add_pixels(&self.pattern.mask(&pixs));
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn set(&mut self, _xpr: &Xprite, option: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value for {}: {}", option, value);
+        match option {
+            "LControl" | "RControl" => match value {
+                "true" => self.symmetric = true,
+                "false" => self.symmetric = false,
+                _ => error!("unimpl for ctrl: {}", value),
+            },
+            "LShift" | "RShift" => match value {
+                "true" => self.snap = true,
+                "false" => self.snap = false,
+                _ => error!("unimpl for shift: {}", value),
+            },
+            "kind" => {
+                if let Ok(kind) = ShapeKind::from_str(value) {
+                    self.kind = kind;
+                }
+            }
+            "filled" => self.filled = value.parse().map_err(|_| invalid())?,
+            "sides" => self.sides = value.parse().map_err(|_| invalid())?,
+            "rotation" => self.rotation = value.parse().map_err(|_| invalid())?,
+            "inner_radius" => self.inner_radius = value.parse().map_err(|_| invalid())?,
+            "arc_start" => self.arc_start = value.parse().map_err(|_| invalid())?,
+            "arc_sweep" => self.arc_sweep = value.parse().map_err(|_| invalid())?,
+            _ => {
+                self.pipeline.set(option, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_shape_kinds() {
+        use super::*;
+        let mut tool = Shape::new();
+        let (p0, p1) = (vec2f!(0, 0), vec2f!(12, 12));
+        for kind in ShapeKind::VARIANTS.iter() {
+            tool.kind = *kind;
+            tool.filled = false;
+            let outline = tool.shape(p0, p1, Color::red());
+            tool.filled = true;
+            let filled = tool.shape(p0, p1, Color::red());
+            assert!(!outline.is_empty());
+            assert!(outline.len() < filled.len(), "{}", kind.as_str());
+            assert!(outline.iter().all(|p| filled.0.contains(p)), "{}", kind.as_str());
+            assert!(filled.iter().all(|p| p.point.x.min(p.point.y) >= 0. && p.point.x.max(p.point.y) <= 12.));
+        }
+
+        // dragging up and left fits the same shape
+        tool.kind = ShapeKind::Star;
+        assert_eq!(tool.shape(p1, p0, Color::red()), tool.shape(p0, p1, Color::red()));
+        tool.start_pos = Some(p0);
+        tool.cursor_pos = Some(p0);
+        assert!(tool.get_shape(Color::red()).is_err());
+    }
+
+    #[test]
+    fn test_arc_stays_connected() {
+        use super::*;
+        let mut tool = Shape::new();
+        tool.pipeline = StrokePipeline::pixel_perfect();
+        tool.arc_start = 0.;
+        for &(kind, sweep) in &[(ShapeKind::Arc, 270.), (ShapeKind::Arc, 300.), (ShapeKind::Pie, 270.)] {
+            tool.kind = kind;
+            tool.arc_sweep = sweep;
+            let outline = tool.shape(vec2f!(0, 0), vec2f!(14, 20), Color::red());
+            let mut seen = vec![*outline.iter().next().unwrap()];
+            let mut i = 0;
+            while i < seen.len() {
+                let p = seen[i].point;
+                for q in outline.iter() {
+                    if (q.point.x - p.x).abs() <= 1. && (q.point.y - p.y).abs() <= 1. && !seen.contains(q) {
+                        seen.push(*q);
+                    }
+                }
+                i += 1;
+            }
+            assert_eq!(seen.len(), outline.len(), "{} {}", kind.as_str(), sweep);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::algorithms::ellipse::algo_ellipse;
+use crate::algorithms::spray::*;
+use crate::tools::*;
+
+#[derive(Clone, Debug)]
+pub struct Spray {
+    is_mouse_down: Option<InputItem>,
+    cursor: Option<Pixels>,
+    rng: SprayRng,
+    /// colors of the current stroke
+    colors: Vec<Color>,
+    draw_buffer: Pixels,
+    update_buffer: Option<Pixels>,
+    redraw: bool,
+    pub radius: f32,
+    /// share of the pixels under the spray painted by each dab
+    pub density: f32,
+    pub distribution: SprayDistribution,
+    /// strokes starting on the same pixel with the same seed scatter the same pixels
+    pub seed: u64,
+    /// also paint with the ramp entries up to this many steps from the current color
+    pub ramp_spread: usize,
+}
+
+impl Default for Spray {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spray {
+    pub fn new() -> Self {
+        Self {
+            is_mouse_down: None,
+            cursor: None,
+            rng: SprayRng::default(),
+            colors: vec![],
+            draw_buffer: Pixels::new(),
+            update_buffer: None,
+            redraw: false,
+            radius: 6.,
+            density: 0.1,
+            distribution: SprayDistribution::Uniform,
+            seed: 0,
+            ramp_spread: 0,
+        }
+    }
+
+    /// `color` and its ramp neighbours within `ramp_spread`
+    fn colors(&self, xpr: &Xprite, color: Color) -> Vec<Color> {
+        let pal = xpr.palette.current_palette();
+        let ramp = match color {
+            Color::Indexed(idx) => pal.ramp_of(idx),
+            _ => None,
+        };
+        match ramp {
+            Some((_, ramp, pos)) if self.ramp_spread > 0 => {
+                let lo = pos.saturating_sub(self.ramp_spread);
+                let hi = (pos + self.ramp_spread).min(ramp.len() - 1);
+                ramp[lo..=hi].iter().map(|&i| Color::Indexed(i)).collect()
+            }
+            _ => vec![color],
+        }
+    }
+
+    /// pixels painted by one dab at `point`
+    pub fn dab(&mut self, point: Vec2f) -> Pixels {
+        let r = f64::from(self.radius.max(0.));
+        let count = (f64::from(self.density) * std::f64::consts::PI * r * r).round().max(1.) as usize;
+        let mut pixs = Pixels::new();
+        // pixels hit twice are drawn again, a dense dab may still fall short
+        for _ in 0..count * 8 {
+            if pixs.len() >= count {
+                break;
+            }
+            let point = scatter(&mut self.rng, point, r, 1, self.distribution)[0];
+            let color = match self.colors.len() {
+                0 => Color::red(),
+                1 => self.colors[0],
+                n => self.colors[self.rng.below(n)],
+            };
+            pixs.push(Pixel { point, color });
+        }
+        pixs
+    }
+
+    /// starts a stroke at `point`, reseeding from `seed` and the pixel
+    pub fn begin(&mut self, point: Vec2f, colors: Vec<Color>) {
+        let pos = ((point.x as i64 as u64) << 32) ^ (point.y as i64 as u64 & 0xFFFF_FFFF);
+        self.rng = SprayRng::new(self.seed ^ pos);
+        self.colors = colors;
+        self.draw_buffer.clear();
+    }
+
+    fn set_cursor(&mut self, xpr: &Xprite, point: Vec2f) {
+        let r = self.radius.max(0.).round() as i32;
+        let (x, y) = (point.x as i32, point.y as i32);
+        self.cursor = Some(algo_ellipse(x - r, y - r, x + r, y + r, xpr.color()));
+    }
+}
+
+impl Tool for Spray {
+    fn mouse_move(&mut self, xpr: &Xprite, p: Vec2f) -> Result<(), String> {
+        let point = xpr.canvas.shrink_size(p);
+        self.set_cursor(xpr, point);
+        self.redraw = true;
+        if self.is_mouse_down.is_none() {
+            return Ok(());
+        }
+        let pixs = self.dab(point);
+        self.draw_buffer.extend(&pixs);
+        Ok(())
+    }
+
+    fn mouse_down(&mut self, xpr: &Xprite, p: Vec2f, button: InputItem) -> Result<(), String> {
+        let point = xpr.canvas.shrink_size(p);
+        self.is_mouse_down = Some(button);
+        // right button sprays with the secondary color
+        let colors = self.colors(xpr, xpr.color_for(Some(button)));
+        self.begin(point, colors);
+        let pixs = self.dab(point);
+        self.draw_buffer.extend(&pixs);
+        self.redraw = true;
+        Ok(())
+    }
+
+    fn mouse_up(&mut self, _xpr: &mut Xprite, _p: Vec2f) -> Result<(), String> {
+        if self.is_mouse_down.is_none() {
+            return Ok(());
+        }
+        self.is_mouse_down = None;
+        self.update_buffer = Some(self.draw_buffer.clone());
+        self.draw_buffer.clear();
+        self.redraw = true;
+        Ok(())
+    }
+
+    fn draw(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        if !self.redraw {
+            return Ok(false);
+        }
+        xpr.new_frame();
+        if let Some(cursor) = &self.cursor {
+            xpr.set_cursor(cursor);
+        }
+        xpr.add_pixels(&self.draw_buffer);
+        self.redraw = false;
+        Ok(true)
+    }
+
+    fn update(&mut self, xpr: &mut Xprite) -> Result<bool, String> {
+        if let Some(pixs) = &self.update_buffer {
+            xpr.finalize_pixels(pixs)?;
+            self.update_buffer = None;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn set(&mut self, _xpr: &Xprite, option: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value for {}: {}", option, value);
+        match option {
+            "radius" => self.radius = value.parse().map_err(|_| invalid())?,
+            "density" => self.density = value.parse().map_err(|_| invalid())?,
+            "seed" => self.seed = value.parse().map_err(|_| invalid())?,
+            "ramp_spread" => self.ramp_spread = value.parse().map_err(|_| invalid())?,
+            "distribution" => self.distribution = SprayDistribution::from_str(value).map_err(|_| invalid())?,
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_spray_is_reproducible() {
+        use super::*;
+        let stroke = |spray: &mut Spray| {
+            spray.begin(vec2f!(20, 20), vec![Color::Indexed(1), Color::Indexed(2)]);
+            let mut pixs = Pixels::new();
+            for x in 20..30 {
+                pixs.extend(&spray.dab(vec2f!(20, x)));
+            }
+            pixs
+        };
+        let mut spray = Spray::new();
+        let a = stroke(&mut spray);
+        let b = stroke(&mut spray);
+        assert_eq!(a, b);
+        assert_eq!(a.iter().map(|p| p.color).collect::<Vec<_>>(), b.iter().map(|p| p.color).collect::<Vec<_>>());
+        assert!(a.iter().any(|p| p.color == Color::Indexed(1)));
+        assert!(a.iter().any(|p| p.color == Color::Indexed(2)));
+
+        spray.seed = 1;
+        assert_ne!(stroke(&mut spray), a);
+
+        // density is the share of the disc painted by a dab
+        spray.density = 1.;
+        spray.radius = 3.;
+        spray.begin(vec2f!(0, 0), vec![Color::red()]);
+        assert!(spray.dab(vec2f!(0, 0)).len() >= 25);
+
+        let xpr = Xprite::new("test".to_owned(), 10., 10.);
+        assert!(spray.set(&xpr, "distribution", "sideways").is_err());
+        assert!(spray.set(&xpr, "radius", "wide").is_err());
+    }
+}
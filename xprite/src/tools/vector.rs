@@ -72,6 +72,7 @@ pub struct Vector {
     pub draw_bezier: bool,
     /// sort by segment
     pub mono_sort: bool,
+    pub pipeline: StrokePipeline,
 }
 
 impl Vector {
@@ -147,7 +148,7 @@ impl Vector {
             let mut ret = Pixels::new();
             for curve in &self.curves {
                 if let Some(ras) = curve.rasterize(self.mono_sort, xpr.color()) {
-                    let pixs = self.brush.follow_stroke(&ras).unwrap();
+                    let pixs = self.brush.follow_stroke(&self.pipeline.run(ras)).unwrap();
                     ret.extend(&pixs);
                 }
             }
@@ -316,7 +317,7 @@ impl Tool for Vector {
                     if self.draw_bezier {
                         xpr.bz_buf.extend(path.segments);
                     }
-                    ret.extend(&buf);
+                    ret.extend(&self.pipeline.preview(buf));
                 }
             }
             VectorMode::Curvature => {
@@ -325,7 +326,7 @@ impl Tool for Vector {
                         xpr.bz_buf.push(c.clone());
                     }
                     if let Some(ras) = c.rasterize(self.mono_sort, xpr.color()) {
-                        ret.extend(&ras);
+                        ret.extend(&self.pipeline.preview(ras));
                     }
                 }
             }
@@ -337,7 +338,7 @@ impl Tool for Vector {
                 xpr.bz_buf.push(curve.clone());
             }
             if let Some(ras) = curve.rasterize(self.mono_sort, xpr.color()) {
-                ret.extend(&ras);
+                ret.extend(&self.pipeline.run(ras));
             }
         }
 
@@ -380,8 +381,10 @@ impl Tool for Vector {
             "Return" | "Enter" => {
                 self.add_to_hist(xpr)?;
             }
-            _ => (),
             // i => info!("{}", i),
+            _ => {
+                self.pipeline.set(option, value)?;
+            }
         }
         Ok(())
     }